@@ -0,0 +1,1486 @@
+use std::{sync::Arc, time::Instant};
+
+use anyhow::Context;
+use smol::{
+    future::FutureExt,
+    io::AsyncReadExt,
+    stream::{Stream, StreamExt},
+};
+
+use crate::{
+    clock::Clock,
+    demo, diagnostics,
+    display::{self, CharacterDisplay, CursorPosition, CursorStyle, Line, Lines},
+    screen_file, state, view, volume_file,
+    widgets::{self, Widget},
+    Config, ExitScreen, Splash, TemperatureSource,
+};
+
+pub enum Event {
+    RradioEvent(anyhow::Result<rradio_messages::Event>),
+    TickEvent(Instant),
+    Done,
+}
+
+/// A connection to rradio, either over TCP or over a Unix domain socket
+type Connection = Box<dyn smol::io::AsyncRead + Unpin>;
+
+/// Race `read` against a timer, so that a half-open connection, which never delivers any more bytes, is detected
+async fn read_with_timeout(
+    read: impl std::future::Future<Output = std::io::Result<()>>,
+    read_timeout: std::time::Duration,
+) -> std::io::Result<()> {
+    read.or(async {
+        smol::Timer::after(read_timeout).await;
+        Err(std::io::ErrorKind::TimedOut.into())
+    })
+    .await
+}
+
+/// Returns true if `error` was caused by rradio not sending anything within the read timeout
+fn is_read_timeout(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<std::io::Error>(),
+        Some(err) if err.kind() == std::io::ErrorKind::TimedOut
+    )
+}
+
+async fn read_next_rradio_event(
+    (mut connection, mut event_buffer): (Connection, Vec<u8>),
+    read_timeout: std::time::Duration,
+) -> anyhow::Result<Option<(rradio_messages::Event, (Connection, Vec<u8>))>> {
+    let event_length = {
+        let mut event_length_buffer =
+            [0_u8; std::mem::size_of::<rradio_messages::MsgPackBufferLength>()];
+
+        match read_with_timeout(
+            connection.read_exact(&mut event_length_buffer),
+            read_timeout,
+        )
+        .await
+        {
+            Ok(()) => (),
+            Err(err) => {
+                return if let std::io::ErrorKind::UnexpectedEof = err.kind() {
+                    Ok(None) // Close the stream as the connection has correctly closed
+                } else {
+                    Err(err).context("Reading from rradio")
+                };
+            }
+        }
+
+        // initialize value of "event_length"
+        rradio_messages::MsgPackBufferLength::from_be_bytes(event_length_buffer)
+    };
+
+    event_buffer.resize(event_length as usize, 0);
+
+    read_with_timeout(connection.read_exact(event_buffer.as_mut()), read_timeout)
+        .await
+        .context("Reading from rradio")?;
+
+    let event: rradio_messages::Event = match rmp_serde::from_read_ref(&event_buffer) {
+        Ok(event) => event,
+        Err(err) => {
+            log::trace!(
+                "Failed to parse {} byte message: {:02X?}",
+                event_length,
+                event_buffer
+            );
+            return Err(err).context("Parsing msgpack");
+        }
+    };
+
+    Ok(Some((event, (connection, event_buffer))))
+}
+
+/// Connect to rradio, over the Unix domain socket at [Config::rradio_socket_path] if given, otherwise over TCP
+async fn connect_once(config: &Config) -> std::io::Result<Connection> {
+    Ok(match &config.rradio_socket_path {
+        Some(socket_path) => Box::new(smol::net::unix::UnixStream::connect(socket_path).await?),
+        None => Box::new(smol::net::TcpStream::connect(config.rradio_address).await?),
+    })
+}
+
+/// The address being connected to, as shown on the no connection screen
+fn rradio_address_description(config: &Config) -> String {
+    match &config.rradio_socket_path {
+        Some(socket_path) => socket_path.display().to_string(),
+        None => config.rradio_address.to_string(),
+    }
+}
+
+/// On the no connection screen, the second message line, the rradio address, and the connection attempts are each shown for this many seconds in turn
+const NO_CONNECTION_ALTERNATION_SECONDS: usize = 2;
+
+/// The number of attempts to connect to rradio, and how long ago the first was, so that it's clear that the screen driver is still trying
+struct ConnectionAttemptsDisplay {
+    attempts: usize,
+    since_first_attempt: std::time::Duration,
+}
+
+impl std::fmt::Display for ConnectionAttemptsDisplay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Try {} after {}s",
+            self.attempts,
+            self.since_first_attempt.as_secs()
+        )
+    }
+}
+
+/// Connect to rradio, showing a holding screen while waiting for rradio to start, preceded by `splash` if given
+async fn connect_to_rradio(
+    config: &Config,
+    ip_address: &str,
+    temperature_source: &mut impl TemperatureSource,
+    clock: &dyn Clock,
+    display: &mut impl display::TextDisplay,
+    splash: Option<&Splash>,
+) -> anyhow::Result<Connection> {
+    let rradio_address = rradio_address_description(config);
+
+    log::info!("Connecting to rradio at {}", rradio_address);
+
+    let mut is_retrying = false;
+
+    // Shared by connecting and the no connection screen. Each call counts afresh, so the count is reset by connecting
+    let attempts = std::cell::Cell::new(0_usize);
+    let first_attempt = Instant::now();
+
+    async {
+        loop {
+            attempts.set(attempts.get() + 1);
+
+            match connect_once(config).await {
+                Ok(connection) => {
+                    log::info!("Connected to rradio at {}", rradio_address);
+                    break Ok(connection);
+                }
+                Err(err) => {
+                    // If rradio hasn't started yet, a Unix domain socket might not have been created yet
+                    if let std::io::ErrorKind::ConnectionRefused | std::io::ErrorKind::NotFound =
+                        err.kind()
+                    {
+                        // Retries are frequent, so only the first is logged
+                        if !is_retrying {
+                            is_retrying = true;
+                            log::info!("rradio is not available ({}), retrying", err);
+                        }
+
+                        smol::Timer::after(std::time::Duration::from_millis(100)).await;
+                        continue;
+                    }
+
+                    break Err(anyhow::Error::from(err).context("Failed to connect to rradio"));
+                }
+            }
+        }
+    }
+    .or(async {
+        if let Some(splash) = splash {
+            show_splash(display, splash);
+            smol::Timer::after(splash.duration).await;
+        }
+
+        display.clear();
+        display.write_to(Line(0), ip_address);
+        let [no_connection_line1, no_connection_line2] = &config.messages.no_connection;
+        display.write_to(Line(1), no_connection_line1);
+
+        let (temperature_segment, time_segment) = Line(3).split(15);
+
+        let mut seconds = 0;
+
+        loop {
+            // Show which address is being retried, e.g. "No connection to" "127.0.0.1:8002", and how often
+            match seconds / NO_CONNECTION_ALTERNATION_SECONDS {
+                0 => display.write_to(Line(2), no_connection_line2),
+                1 => display.write_to(Line(2), &rradio_address),
+                _ => display.write_to(
+                    Line(2),
+                    ConnectionAttemptsDisplay {
+                        attempts: attempts.get(),
+                        since_first_attempt: first_attempt.elapsed(),
+                    },
+                ),
+            }
+
+            let temperature = temperature_source.get_temperature();
+
+            display.write_to(
+                temperature_segment,
+                format_args!("CPU Temp {:>3}C", temperature.0),
+            );
+
+            display.write_to(time_segment, clock.now().time().format("%R"));
+
+            display.flush();
+
+            seconds = (seconds + 1) % (3 * NO_CONNECTION_ALTERNATION_SECONDS);
+
+            smol::Timer::after(std::time::Duration::from_secs(1)).await;
+        }
+    })
+    .await
+}
+
+/// The events coming from rradio over `connection`, merged with a tick every `tick_interval`
+fn rradio_and_tick_events(
+    connection: Connection,
+    read_timeout: std::time::Duration,
+    tick_interval: std::time::Duration,
+) -> impl Stream<Item = Event> {
+    // rradio_events is a Stream of rradio Events coming from rradio having been decoded from the connection named "connection"
+    let rradio_events = smol::stream::try_unfold((connection, Vec::new()), move |state| {
+        read_next_rradio_event(state, read_timeout)
+    })
+    .map(Event::RradioEvent) // Map from a rradio_messages::Event to a app::Event to allow merging the stream with other local events
+    .chain(smol::stream::once(Event::Done)); // When the connection closes, also send a single app::Event::Done
+
+    // tick_events is a Stream of app::Event::TickEvent with the current time, produced every tick_interval
+    let tick_events = smol::stream::unfold(Instant::now(), move |previous_time| async move {
+        let new_time = smol::Timer::at(previous_time + tick_interval).await;
+        Some((Event::TickEvent(new_time), new_time))
+    });
+
+    // merge streams into a single multiplexed stream of app::Event so that we can wait for a message from any of the sources
+    rradio_events.or(tick_events)
+}
+
+/// How the connection to rradio ended without an error
+#[derive(Debug, PartialEq)]
+pub(crate) enum Disconnection {
+    /// rradio closed the connection, so the screen driver should stop
+    Closed,
+    /// rradio is speaking a different version of the protocol, possibly because it's being upgraded, so the screen driver should reconnect
+    VersionMismatch { rradio_version: String },
+    /// Nothing was received from rradio within the read timeout, so the connection is assumed to have been lost, and the screen driver should reconnect
+    TimedOut,
+}
+
+/// The screen saved when ending is only restored if the screen driver starts again within this long, so that stale contents aren't shown after e.g. a power cut
+const SCREEN_RESTORE_WINDOW: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How long the restored screen is shown while connecting to rradio before showing that rradio isn't available
+const RESTORED_SCREEN_DURATION: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// How long the version mismatch screen is shown before reconnecting
+const VERSION_MISMATCH_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Show both protocol versions, so that a mismatch during an upgrade can be diagnosed
+fn show_version_mismatch(display: &mut impl display::TextDisplay, rradio_version: &str) {
+    display.clear();
+    display.write_to(Line(0), "Version mismatch");
+    display.write_to(Line(1), format_args!("rradio: {}", rradio_version));
+    display.write_to(
+        Line(2),
+        format_args!("screen: {}", rradio_messages::VERSION),
+    );
+    display.write_to(Line(3), "Retrying...");
+    display.flush();
+}
+
+/// Show the splash screen, which stays until the screen is next written to
+fn show_splash(display: &mut impl display::TextDisplay, splash: &Splash) {
+    display.clear();
+
+    for (row, line) in (0..).zip(&splash.lines) {
+        display.write_to(Line(row), line);
+    }
+
+    display.flush();
+}
+
+/// The cursor is only shown by the main view, so is hidden when showing anything else
+fn hide_cursor(display: &mut impl display::TextDisplay) {
+    display.set_cursor(CursorPosition { row: 0, column: 0 }, CursorStyle::Off);
+}
+
+/// A view and the display which it's painted on. Every screen shows the same state, so several displays can share one connection to rradio
+trait Screen {
+    fn event(&mut self, event: &widgets::WidgetEvent, state: &Arc<state::PlayerState>);
+
+    fn update(&mut self, old_state: &Arc<state::PlayerState>, state: &Arc<state::PlayerState>);
+
+    /// Paint and flush the view, unless nothing has changed
+    fn paint(&mut self, state: &Arc<state::PlayerState>);
+}
+
+struct ViewScreen<'a, V, D> {
+    view: V,
+    display: &'a mut D,
+    show_paint_time: bool,
+    paint_timer: diagnostics::PaintTimer,
+}
+
+impl<'a, V, D> ViewScreen<'a, V, D> {
+    fn new(config: &Config, view: V, display: &'a mut D) -> Self {
+        Self {
+            view,
+            display,
+            show_paint_time: config.show_paint_time,
+            paint_timer: diagnostics::PaintTimer::default(),
+        }
+    }
+}
+
+impl<'a, V: Widget<Data = Arc<state::PlayerState>>, D: display::TextDisplay> Screen
+    for ViewScreen<'a, V, D>
+{
+    fn event(&mut self, event: &widgets::WidgetEvent, state: &Arc<state::PlayerState>) {
+        self.view.event(event, state);
+    }
+
+    fn update(&mut self, old_state: &Arc<state::PlayerState>, state: &Arc<state::PlayerState>) {
+        self.view.update(old_state, state);
+    }
+
+    fn paint(&mut self, state: &Arc<state::PlayerState>) {
+        // Nothing is painted or flushed if no widget has changed, e.g. on most ticks of a static screen
+        if !self.view.dirty() {
+            return;
+        }
+
+        let paint_start = Instant::now();
+
+        self.view.paint(state, self.display);
+
+        if self.show_paint_time {
+            self.paint_timer.paint_overlay(self.display);
+        }
+
+        self.display.flush();
+
+        self.paint_timer.frame_painted(paint_start);
+    }
+}
+
+impl Screen for [Box<dyn Screen + '_>] {
+    fn event(&mut self, event: &widgets::WidgetEvent, state: &Arc<state::PlayerState>) {
+        for screen in self {
+            screen.event(event, state);
+        }
+    }
+
+    fn update(&mut self, old_state: &Arc<state::PlayerState>, state: &Arc<state::PlayerState>) {
+        for screen in self {
+            screen.update(old_state, state);
+        }
+    }
+
+    fn paint(&mut self, state: &Arc<state::PlayerState>) {
+        for screen in self {
+            screen.paint(state);
+        }
+    }
+}
+
+/// A display which shows its own view of the same state as the main display, while connected to rradio
+trait SecondaryDisplay {
+    /// Blank the display, while there's no state to show
+    fn blank(&mut self);
+
+    /// The screen which shows the view on this display, for as long as a connection to rradio lasts
+    fn screen<'a>(
+        &'a mut self,
+        config: &Config,
+        clock: Arc<dyn Clock>,
+        ip_address: &'a str,
+        start_time: Instant,
+    ) -> Box<dyn Screen + 'a>;
+}
+
+struct ConfiguredDisplay<D> {
+    display: D,
+    idle_screen: view::IdleScreenOptions,
+}
+
+impl<D: display::TextDisplay> SecondaryDisplay for ConfiguredDisplay<D> {
+    fn blank(&mut self) {
+        hide_cursor(&mut self.display);
+        self.display.clear();
+        self.display.flush();
+    }
+
+    fn screen<'a>(
+        &'a mut self,
+        config: &Config,
+        clock: Arc<dyn Clock>,
+        ip_address: &'a str,
+        start_time: Instant,
+    ) -> Box<dyn Screen + 'a> {
+        let view = view::ViewBuilder::new(config)
+            .with_clock(clock)
+            .with_idle_screen(self.idle_screen)
+            .build(ip_address, start_time);
+
+        Box::new(ViewScreen::new(config, view, &mut self.display))
+    }
+}
+
+/// The async entry point of the application, which updates the display in response to `events`
+pub(crate) async fn do_run(
+    config: &Config,
+    start_time: Instant,
+    ip_address: impl AsRef<str>,
+    temperature_source: &mut impl TemperatureSource,
+    clock: Arc<dyn Clock>,
+    display: &mut impl display::TextDisplay,
+    events: impl Stream<Item = Event>,
+) -> anyhow::Result<Disconnection> {
+    do_run_on_displays(
+        config,
+        start_time,
+        ip_address.as_ref(),
+        temperature_source,
+        clock,
+        display,
+        None,
+        events,
+    )
+    .await
+}
+
+/// As [do_run], but also showing the state on `secondary_display`
+#[allow(clippy::too_many_arguments)]
+async fn do_run_on_displays<'d>(
+    config: &Config,
+    start_time: Instant,
+    ip_address: &str,
+    temperature_source: &mut impl TemperatureSource,
+    clock: Arc<dyn Clock>,
+    display: &mut impl display::TextDisplay,
+    secondary_display: Option<&mut (dyn SecondaryDisplay + 'd)>,
+    events: impl Stream<Item = Event>,
+) -> anyhow::Result<Disconnection> {
+    let view = view::ViewBuilder::new(config)
+        .with_clock(clock.clone())
+        .build(ip_address, start_time);
+
+    let mut screens: Vec<Box<dyn Screen + '_>> =
+        vec![Box::new(ViewScreen::new(config, view, display))];
+
+    if let Some(secondary_display) = secondary_display {
+        screens.push(secondary_display.screen(config, clock, ip_address, start_time));
+    }
+
+    run_screens(config, temperature_source, &mut screens, events).await
+}
+
+/// Update each of `screens` in response to `events`
+async fn run_screens(
+    config: &Config,
+    temperature_source: &mut impl TemperatureSource,
+    screens: &mut [Box<dyn Screen + '_>],
+    events: impl Stream<Item = Event>,
+) -> anyhow::Result<Disconnection> {
+    // pin "events" to the stack. See https://doc.rust-lang.org/std/pin/index.html
+    smol::pin!(events);
+
+    let mut state = state::PlayerState::default();
+
+    if let Some(volume_file) = &config.volume_file {
+        if let Some(volume) = volume_file::load(volume_file).await {
+            state.volume = volume;
+        }
+    }
+
+    // The state is shared with the view, so that the view's lenses don't clone the whole state every paint
+    let mut state = Arc::new(state);
+
+    // let mut app_widget = widgets::ApplicationWidget::new();
+
+    let first_tick_time = Instant::now();
+    let mut last_tick_time = first_tick_time;
+
+    while let Some(event) = events.next().await {
+        match event {
+            Event::RradioEvent(Err(err)) if is_read_timeout(&err) => {
+                return Ok(Disconnection::TimedOut)
+            }
+            Event::RradioEvent(rradio_event) => {
+                let rradio_event = rradio_event?;
+
+                log::debug!("rradio event: {:?}", rradio_event);
+
+                match rradio_event {
+                    rradio_messages::Event::ProtocolVersion(version) => {
+                        if version.as_str() != rradio_messages::VERSION {
+                            return Ok(Disconnection::VersionMismatch {
+                                rradio_version: version.to_string(),
+                            });
+                        }
+
+                        let new_state = Arc::new(state.as_ref().clone().with_rradio_event());
+                        screens.update(&state, &new_state);
+                        state = new_state;
+
+                        continue;
+                    }
+                    rradio_messages::Event::PlayerStateChanged(state_diff) => {
+                        let station_has_changed = state_diff.current_station.has_changed();
+                        let should_update_temperature = state_diff.ping_times.is_some();
+
+                        let new_state = state
+                            .as_ref()
+                            .clone()
+                            .with_rradio_event()
+                            .apply_diff(state_diff);
+
+                        let new_state = Arc::new(if should_update_temperature {
+                            new_state.with_new_temperature(temperature_source.get_temperature())
+                        } else {
+                            new_state
+                        });
+
+                        if let Some(volume_file) = &config.volume_file {
+                            if new_state.volume != state.volume {
+                                // Failing to save the volume only affects the next start up, so isn't worth stopping the application for
+                                if let Err(err) =
+                                    volume_file::save(volume_file, new_state.volume).await
+                                {
+                                    log::warn!("{:#}", err);
+                                }
+                            }
+                        }
+
+                        if station_has_changed {
+                            match &new_state.current_station {
+                                Some(station) => log::info!(
+                                    "Station changed to {:?} {:?}",
+                                    station.index,
+                                    station.title
+                                ),
+                                None => log::info!("Station cleared"),
+                            }
+                        }
+
+                        if new_state.pipeline_state != state.pipeline_state {
+                            log::info!("Pipeline state changed to {}", new_state.pipeline_state);
+                        }
+
+                        // The screen isn't cleared when the station changes, as the view wipes over the previous station
+                        screens.update(&state, &new_state);
+                        state = new_state;
+
+                        // app_widget.handle_state_changed(state_diff)
+                    }
+                    rradio_messages::Event::LogMessage(message) => {
+                        let new_state = Arc::new(
+                            state
+                                .as_ref()
+                                .clone()
+                                .with_rradio_event()
+                                .handle_log_message(message),
+                        );
+                        screens.update(&state, &new_state);
+                        state = new_state;
+                    }
+                }
+            }
+            Event::TickEvent(current_time) => {
+                let elapsed = current_time.saturating_duration_since(last_tick_time);
+                last_tick_time = current_time;
+
+                screens.event(
+                    &widgets::WidgetEvent::Tick(
+                        current_time.saturating_duration_since(first_tick_time),
+                        elapsed,
+                    ),
+                    &state,
+                );
+
+                if state.current_error.is_some() {
+                    let new_state = Arc::new(state.as_ref().clone().with_error_tick());
+                    screens.update(&state, &new_state);
+                    state = new_state;
+                }
+                // app_widget.handle_tick_event(current_time)
+            }
+            Event::Done => break,
+        }
+
+        screens.paint(&state);
+
+        // app_widget.paint(display);
+    }
+
+    Ok(Disconnection::Closed)
+}
+
+/// Run the application within the [smol] runtime, and if an error is raised, write it to the display
+pub fn run(
+    config: Config,
+    ip_address: impl AsRef<str>,
+    temperature_source: impl TemperatureSource,
+    clock: impl Clock + 'static,
+    character_display: impl CharacterDisplay,
+) {
+    run_until(
+        config,
+        ip_address,
+        temperature_source,
+        clock,
+        character_display,
+        smol::future::pending(),
+    )
+}
+
+/// As [run], but also end, showing the ending messages, when `shutdown` completes, e.g. when the process is asked to stop.
+/// The display is only written to between awaits, so shutting down never interrupts painting a frame
+pub fn run_until(
+    config: Config,
+    ip_address: impl AsRef<str>,
+    temperature_source: impl TemperatureSource,
+    clock: impl Clock + 'static,
+    character_display: impl CharacterDisplay,
+    shutdown: impl std::future::Future<Output = ()>,
+) {
+    run_on_displays(
+        config,
+        ip_address,
+        temperature_source,
+        clock,
+        character_display,
+        None,
+        shutdown,
+    )
+}
+
+/// As [run_until], but also show the state on `secondary_display`, e.g. a second screen dedicated to the clock and ping times.
+/// The main display shows the full view, and `secondary_idle_screen` configures which parts of the idle screen the secondary display shows.
+/// Both displays share one connection to rradio. Messages such as errors are only shown on the main display,
+/// and the secondary display is blank while not connected to rradio
+#[allow(clippy::too_many_arguments)]
+pub fn run_with_secondary_display(
+    config: Config,
+    ip_address: impl AsRef<str>,
+    temperature_source: impl TemperatureSource,
+    clock: impl Clock + 'static,
+    character_display: impl CharacterDisplay,
+    secondary_display: impl CharacterDisplay,
+    secondary_idle_screen: view::IdleScreenOptions,
+    shutdown: impl std::future::Future<Output = ()>,
+) {
+    let mut secondary_display = ConfiguredDisplay {
+        display: setup_display(&config, secondary_display),
+        idle_screen: secondary_idle_screen,
+    };
+
+    run_on_displays(
+        config,
+        ip_address,
+        temperature_source,
+        clock,
+        character_display,
+        Some(&mut secondary_display),
+        shutdown,
+    )
+}
+
+/// Run the application on `character_display`, and on `secondary_display` if provided, until `shutdown` completes
+fn run_on_displays<'d>(
+    config: Config,
+    ip_address: impl AsRef<str>,
+    temperature_source: impl TemperatureSource,
+    clock: impl Clock + 'static,
+    character_display: impl CharacterDisplay,
+    mut secondary_display: Option<&mut (dyn SecondaryDisplay + 'd)>,
+    shutdown: impl std::future::Future<Output = ()>,
+) {
+    use display::TextDisplay;
+
+    let start_time = Instant::now();
+
+    let clock: Arc<dyn Clock> = Arc::new(clock);
+
+    // Pinned so that it can be awaited again while showing an error
+    let mut shutdown = Box::pin(shutdown);
+
+    // After a quick restart, the screen is shown as it was when ending, rather than the splash, until rradio is connected to
+    let restored_screen = config.screen_file.as_ref().and_then(|screen_file| {
+        smol::block_on(screen_file::load(screen_file, SCREEN_RESTORE_WINDOW)).map(|lines| Splash {
+            lines,
+            duration: RESTORED_SCREEN_DURATION,
+        })
+    });
+
+    // The screen may have been cleared or changed since the restored screen was saved, e.g. by the exit screen, so it's written in full
+    let mut display = setup_display(&config, character_display);
+
+    let exit_status = smol::block_on(
+        async {
+            let mut temperature_source = temperature_source;
+
+            // The splash is only shown when first connecting
+            let mut splash = restored_screen.as_ref().or(config.splash.as_ref());
+
+            loop {
+                hide_cursor(&mut display);
+
+                if let Some(secondary_display) = &mut secondary_display {
+                    secondary_display.blank();
+                }
+
+                let connection = connect_to_rradio(
+                    &config,
+                    ip_address.as_ref(),
+                    &mut temperature_source,
+                    clock.as_ref(),
+                    &mut display,
+                    splash.take(),
+                )
+                .await?;
+
+                display.clear();
+
+                match do_run_on_displays(
+                    &config,
+                    start_time,
+                    ip_address.as_ref(),
+                    &mut temperature_source,
+                    clock.clone(),
+                    &mut display,
+                    secondary_display.as_deref_mut(),
+                    rradio_and_tick_events(connection, config.read_timeout, config.tick_interval),
+                )
+                .await?
+                {
+                    Disconnection::Closed => {
+                        log::info!("rradio closed the connection");
+                        break anyhow::Result::<()>::Ok(());
+                    }
+                    Disconnection::VersionMismatch { rradio_version } => {
+                        log::warn!(
+                            "Version mismatch. rradio: {}, screen: {}",
+                            rradio_version,
+                            rradio_messages::VERSION
+                        );
+                        show_version_mismatch(&mut display, &rradio_version);
+                        smol::Timer::after(VERSION_MISMATCH_RETRY_DELAY).await;
+                    }
+                    Disconnection::TimedOut => {
+                        log::warn!(
+                            "Nothing received from rradio for {:?}, reconnecting",
+                            config.read_timeout
+                        );
+                    }
+                }
+            }
+        }
+        .or(async {
+            (&mut shutdown).await;
+            log::info!("Shutting down");
+            Ok(())
+        }),
+    );
+
+    if let Some(screen_file) = &config.screen_file {
+        if let Some(lines) = display.character_display().shown_lines() {
+            // Failing to save the screen only affects the next start up, so isn't worth showing
+            if let Err(err) = smol::block_on(screen_file::save(screen_file, &lines)) {
+                log::warn!("{:#}", err);
+            }
+        }
+    }
+
+    if let Some(secondary_display) = secondary_display {
+        if config.exit_screen != ExitScreen::Unchanged {
+            secondary_display.blank();
+        }
+    }
+
+    show_exit_status(&config, &mut display, exit_status, shutdown);
+}
+
+/// As [run_until], but rather than connecting to rradio, show an endlessly repeating demo of synthetic events,
+/// for shop window displays and for developing without rradio
+pub fn run_demo(
+    config: Config,
+    ip_address: impl AsRef<str>,
+    mut temperature_source: impl TemperatureSource,
+    clock: impl Clock + 'static,
+    character_display: impl CharacterDisplay,
+    shutdown: impl std::future::Future<Output = ()>,
+) {
+    // The demo's volume and screen aren't real, so mustn't overwrite those saved by an installed radio
+    let config = Config {
+        volume_file: None,
+        screen_file: None,
+        ..config
+    };
+
+    let start_time = Instant::now();
+
+    let mut shutdown = Box::pin(shutdown);
+
+    let mut display = setup_display(&config, character_display);
+
+    let exit_status = smol::block_on(
+        async {
+            do_run(
+                &config,
+                start_time,
+                ip_address.as_ref(),
+                &mut temperature_source,
+                Arc::new(clock),
+                &mut display,
+                demo::demo_events(config.tick_interval),
+            )
+            .await
+            .map(|_| ())
+        }
+        .or(async {
+            (&mut shutdown).await;
+            log::info!("Shutting down");
+            Ok(())
+        }),
+    );
+
+    show_exit_status(&config, &mut display, exit_status, shutdown);
+}
+
+/// Apply the display settings in `config`
+fn setup_display<D: CharacterDisplay>(
+    config: &Config,
+    mut character_display: D,
+) -> display::WrappingTextDisplay<display::DiffingDisplay<D>> {
+    if let Some(brightness) = config.brightness {
+        character_display.set_brightness(brightness);
+    }
+
+    if let Some(contrast) = config.contrast {
+        character_display.set_contrast(contrast);
+    }
+
+    display::WrappingTextDisplay::new(display::DiffingDisplay::new(character_display))
+}
+
+/// Show `error` on the whole screen. Errors which don't fit scroll up a line at a time until the whole of the error has been shown,
+/// ending back at the beginning
+async fn show_error(config: &Config, display: &mut impl display::TextDisplay, error: String) {
+    let mut label =
+        widgets::MultiLineScrollingLabel::new(Lines(0, crate::SCREEN_HEIGHT - 1)).scroll_by_line();
+
+    label.paint(&error, display);
+    display.flush();
+
+    let first_tick_time = Instant::now();
+    let mut last_tick_time = first_tick_time;
+
+    for _ in 0..label.ticks_per_loop(&error) {
+        let current_time = smol::Timer::after(config.tick_interval).await;
+        let elapsed = current_time.saturating_duration_since(last_tick_time);
+        last_tick_time = current_time;
+
+        label.event(
+            &widgets::WidgetEvent::Tick(
+                current_time.saturating_duration_since(first_tick_time),
+                elapsed,
+            ),
+            &error,
+        );
+        label.paint(&error, display);
+        display.flush();
+    }
+}
+
+/// Show the exit screen, or the error which ended the application. Scrolling through a long error stops early when `shutdown` completes
+fn show_exit_status(
+    config: &Config,
+    display: &mut impl display::TextDisplay,
+    exit_status: anyhow::Result<()>,
+    shutdown: impl std::future::Future<Output = ()> + Unpin,
+) {
+    if let Err(err) = &exit_status {
+        log::error!("{:#}", err);
+    }
+
+    // The volume cursor isn't left on the screen, even if the screen is left as it is
+    hide_cursor(display);
+
+    if exit_status.is_ok() && config.exit_screen == ExitScreen::Unchanged {
+        return;
+    }
+
+    display.clear();
+
+    match exit_status {
+        Ok(()) => {
+            if config.exit_screen == ExitScreen::Message {
+                for (line, message) in (0..).map(Line).zip(&config.messages.ending) {
+                    display.write_to(line, message);
+                }
+            }
+
+            display.flush();
+        }
+        Err(error) => smol::block_on(show_error(config, display, format!("{:#}", error)).or(
+            async {
+                shutdown.await;
+                log::info!("Shutting down");
+            },
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{LocalClock, Messages, Temperature};
+    use display::{RecordingDisplay, WrappingTextDisplay};
+
+    struct FixedTemperature;
+
+    impl TemperatureSource for FixedTemperature {
+        fn get_temperature(&mut self) -> Temperature {
+            Temperature(42)
+        }
+    }
+
+    #[test]
+    fn scripted_events() {
+        let station = rradio_messages::Station {
+            index: Some("7".into()),
+            source_type: rradio_messages::StationType::UrlList,
+            title: Some("Radio Seven".into()),
+            tracks: vec![rradio_messages::Track {
+                title: Some("Track Title".into()),
+                album: None,
+                artist: None,
+                url: "http://example.com/track".into(),
+                is_notification: false,
+            }],
+        };
+
+        let now = Instant::now();
+
+        let events = smol::stream::iter(vec![
+            Event::RradioEvent(Ok(rradio_messages::Event::ProtocolVersion(
+                rradio_messages::VERSION.into(),
+            ))),
+            Event::RradioEvent(Ok(rradio_messages::Event::PlayerStateChanged(
+                rradio_messages::PlayerStateDiff {
+                    current_station: rradio_messages::OptionDiff::ChangedToSome(station),
+                    current_track_index: Some(0),
+                    volume: Some(35),
+                    ..Default::default()
+                },
+            ))),
+            // The station splash, then the station view wiping over it
+            Event::TickEvent(now),
+            Event::TickEvent(now),
+            Event::TickEvent(now),
+            Event::Done,
+        ]);
+
+        let config = Config {
+            volume_file: None,
+            screen_file: None,
+            ..Config::default()
+        };
+
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        assert_eq!(
+            smol::block_on(do_run(
+                &config,
+                Instant::now(),
+                "192.168.0.1",
+                &mut FixedTemperature,
+                Arc::new(LocalClock),
+                &mut display,
+                events,
+            ))
+            .unwrap(),
+            Disconnection::Closed
+        );
+
+        let snapshot = display.character_display().snapshot();
+
+        assert!(snapshot[0].starts_with("No Ping Times"), "{:?}", snapshot);
+        assert_eq!(snapshot[1], "Radio Seven         ");
+        assert_eq!(snapshot[2], "Track Title         ");
+    }
+
+    #[test]
+    fn secondary_display() {
+        let events = smol::stream::iter(vec![
+            Event::RradioEvent(Ok(rradio_messages::Event::ProtocolVersion(
+                rradio_messages::VERSION.into(),
+            ))),
+            Event::TickEvent(Instant::now()),
+            Event::Done,
+        ]);
+
+        let config = Config {
+            volume_file: None,
+            screen_file: None,
+            ..Config::default()
+        };
+
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        let mut secondary_display = ConfiguredDisplay {
+            display: WrappingTextDisplay::new(RecordingDisplay::default()),
+            idle_screen: view::IdleScreenOptions {
+                ping: true,
+                date: false,
+                clock: false,
+                temperature: false,
+            },
+        };
+
+        smol::block_on(do_run_on_displays(
+            &config,
+            Instant::now(),
+            "192.168.0.1",
+            &mut FixedTemperature,
+            Arc::new(crate::FixedClock(crate::test_harness::fixed_time())),
+            &mut display,
+            Some(&mut secondary_display),
+            events,
+        ))
+        .unwrap();
+
+        let primary = display.character_display().snapshot();
+        let secondary = secondary_display.display.character_display().snapshot();
+
+        // Both displays show the same state, each with its own view
+        assert!(primary[0].starts_with("192.168.0.1"), "{:?}", primary);
+        assert!(secondary[0].starts_with("192.168.0.1"), "{:?}", secondary);
+        assert_eq!(primary[2], "Tue 07 Mar 2023     ");
+        assert_eq!(secondary[2], " ".repeat(20));
+        assert_eq!(secondary[3], " ".repeat(20));
+    }
+
+    #[test]
+    fn restored_screen_is_written_in_full() {
+        /// Records each clear and each character written
+        #[derive(Default)]
+        struct Recorder(Vec<String>);
+
+        impl CharacterDisplay for Recorder {
+            fn clear(&mut self) {
+                self.0.push("clear".into());
+            }
+
+            fn move_cursor(&mut self, _position: CursorPosition) {}
+
+            fn write_char(&mut self, c: char) {
+                self.0.push(c.to_string());
+            }
+        }
+
+        let restored_screen = Splash {
+            lines: [
+                String::from("12 Radio 4"),
+                String::new(),
+                String::from("Track Title"),
+                String::from("Volume 42"),
+            ],
+            duration: RESTORED_SCREEN_DURATION,
+        };
+
+        let mut display = setup_display(&Config::default(), Recorder::default());
+
+        // The screen may show something else, such as the exit screen, so it's cleared and every character of the restored screen is written
+        show_splash(&mut display, &restored_screen);
+
+        let written = display.character_display().character_display().0.clone();
+
+        assert_eq!(written.first().map(String::as_str), Some("clear"));
+        assert_eq!(
+            written[1..].concat().replace(' ', ""),
+            "12Radio4TrackTitleVolume42"
+        );
+
+        // After that, only the changed character is written
+        let mut changed_screen = restored_screen.clone();
+        changed_screen.lines[3] = String::from("Volume 43");
+
+        show_splash(&mut display, &changed_screen);
+
+        assert_eq!(
+            display.character_display().character_display().0[written.len()..],
+            ["3"]
+        );
+    }
+
+    #[test]
+    fn ticks_carry_elapsed_time() {
+        struct TickRecorder<'a>(&'a mut Vec<std::time::Duration>);
+
+        impl Screen for TickRecorder<'_> {
+            fn event(&mut self, event: &widgets::WidgetEvent, _: &Arc<state::PlayerState>) {
+                let widgets::WidgetEvent::Tick(_, elapsed) = *event;
+                self.0.push(elapsed);
+            }
+
+            fn update(&mut self, _: &Arc<state::PlayerState>, _: &Arc<state::PlayerState>) {}
+
+            fn paint(&mut self, _: &Arc<state::PlayerState>) {}
+        }
+
+        let start = Instant::now();
+        let second = std::time::Duration::from_secs(1);
+
+        // The third tick is delayed
+        let events = smol::stream::iter(vec![
+            Event::TickEvent(start + second),
+            Event::TickEvent(start + 2 * second),
+            Event::TickEvent(start + 5 * second),
+            Event::Done,
+        ]);
+
+        let config = Config {
+            volume_file: None,
+            screen_file: None,
+            ..Config::default()
+        };
+
+        let mut elapsed = Vec::new();
+
+        smol::block_on(run_screens(
+            &config,
+            &mut FixedTemperature,
+            &mut [Box::new(TickRecorder(&mut elapsed)) as Box<dyn Screen>],
+            events,
+        ))
+        .unwrap();
+
+        assert_eq!(elapsed[1..], [second, 3 * second]);
+    }
+
+    #[test]
+    fn read_events_from_unix_socket() {
+        smol::block_on(async {
+            let (mut rradio, screen) = smol::net::unix::UnixStream::pair().unwrap();
+
+            let event = rmp_serde::to_vec(&rradio_messages::Event::ProtocolVersion(
+                rradio_messages::VERSION.into(),
+            ))
+            .unwrap();
+
+            smol::io::AsyncWriteExt::write_all(
+                &mut rradio,
+                &(event.len() as rradio_messages::MsgPackBufferLength).to_be_bytes(),
+            )
+            .await
+            .unwrap();
+            smol::io::AsyncWriteExt::write_all(&mut rradio, &event)
+                .await
+                .unwrap();
+            drop(rradio);
+
+            let read_timeout = std::time::Duration::from_secs(1);
+
+            let (event, connection) =
+                read_next_rradio_event((Box::new(screen), Vec::new()), read_timeout)
+                    .await
+                    .unwrap()
+                    .unwrap();
+
+            assert!(matches!(
+                event,
+                rradio_messages::Event::ProtocolVersion(version) if version == rradio_messages::VERSION
+            ));
+
+            assert!(read_next_rradio_event(connection, read_timeout)
+                .await
+                .unwrap()
+                .is_none());
+        });
+    }
+
+    #[test]
+    fn silent_connection_times_out() {
+        smol::block_on(async {
+            let (_rradio, screen) = smol::net::unix::UnixStream::pair().unwrap();
+
+            let events = rradio_and_tick_events(
+                Box::new(screen),
+                std::time::Duration::from_millis(10),
+                std::time::Duration::from_secs(1),
+            )
+            .filter(|event| !matches!(event, Event::TickEvent(_)));
+
+            let config = Config {
+                volume_file: None,
+                screen_file: None,
+                ..Config::default()
+            };
+
+            let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+            assert_eq!(
+                do_run(
+                    &config,
+                    Instant::now(),
+                    "192.168.0.1",
+                    &mut FixedTemperature,
+                    Arc::new(LocalClock),
+                    &mut display,
+                    events,
+                )
+                .await
+                .unwrap(),
+                Disconnection::TimedOut
+            );
+        });
+    }
+
+    #[test]
+    fn custom_no_connection_message() {
+        let config = Config {
+            rradio_socket_path: Some(std::env::temp_dir().join("rradio_missing_socket")),
+            messages: Messages {
+                no_connection: [
+                    String::from("Keine Verbindung zum internen Programm"),
+                    String::from("Bitte warten"),
+                ],
+                ..Messages::default()
+            },
+            ..Config::default()
+        };
+
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        // The socket doesn't exist, so connecting is retried until the timer ends
+        assert!(smol::block_on(
+            connect_to_rradio(
+                &config,
+                "192.168.0.1",
+                &mut FixedTemperature,
+                &LocalClock,
+                &mut display,
+                None
+            )
+            .or(async {
+                smol::Timer::after(std::time::Duration::from_millis(50)).await;
+                Err(anyhow::anyhow!("Still connecting"))
+            })
+        )
+        .is_err());
+
+        let snapshot = display.character_display().snapshot();
+
+        assert_eq!(snapshot[1], "Keine Verbindung zum");
+        assert_eq!(snapshot[2], "Bitte warten        ");
+    }
+
+    #[test]
+    fn splash_is_shown_before_the_no_connection_screen() {
+        let config = Config {
+            rradio_socket_path: Some(std::env::temp_dir().join("rradio_missing_socket")),
+            ..Config::default()
+        };
+
+        let splash = Splash {
+            lines: [
+                String::from("Internet Radio"),
+                String::new(),
+                String::from("Starting..."),
+                String::new(),
+            ],
+            duration: std::time::Duration::from_millis(100),
+        };
+
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        let connect_for = |display: &mut WrappingTextDisplay<RecordingDisplay>, duration| {
+            smol::block_on(
+                connect_to_rradio(
+                    &config,
+                    "192.168.0.1",
+                    &mut FixedTemperature,
+                    &LocalClock,
+                    display,
+                    Some(&splash),
+                )
+                .or(async {
+                    smol::Timer::after(duration).await;
+                    Err(anyhow::anyhow!("Still connecting"))
+                }),
+            )
+            .is_err()
+        };
+
+        assert!(connect_for(
+            &mut display,
+            std::time::Duration::from_millis(20)
+        ));
+        assert_eq!(
+            display.character_display().snapshot(),
+            [
+                "Internet Radio      ",
+                "                    ",
+                "Starting...         ",
+                "                    "
+            ]
+        );
+
+        assert!(connect_for(
+            &mut display,
+            std::time::Duration::from_millis(200)
+        ));
+        assert_eq!(
+            display.character_display().snapshot()[0],
+            "192.168.0.1         "
+        );
+    }
+
+    #[test]
+    fn rradio_address_descriptions() {
+        assert_eq!(
+            rradio_address_description(&Config::default()),
+            "127.0.0.1:8002"
+        );
+        assert_eq!(
+            rradio_address_description(&Config {
+                rradio_address: "[::1]:9000".parse().unwrap(),
+                ..Config::default()
+            }),
+            "[::1]:9000"
+        );
+        assert_eq!(
+            rradio_address_description(&Config {
+                rradio_socket_path: Some("/run/rradio.sock".into()),
+                ..Config::default()
+            }),
+            "/run/rradio.sock"
+        );
+    }
+
+    #[test]
+    fn connection_attempts() {
+        let attempts = ConnectionAttemptsDisplay {
+            attempts: 1234,
+            since_first_attempt: std::time::Duration::from_millis(125_500),
+        };
+
+        assert_eq!(attempts.to_string(), "Try 1234 after 125s");
+    }
+
+    #[test]
+    fn protocol_version_mismatch() {
+        let events = smol::stream::iter(vec![Event::RradioEvent(Ok(
+            rradio_messages::Event::ProtocolVersion("0.0.0".into()),
+        ))]);
+
+        let config = Config {
+            volume_file: None,
+            screen_file: None,
+            ..Config::default()
+        };
+
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        assert_eq!(
+            smol::block_on(do_run(
+                &config,
+                Instant::now(),
+                "192.168.0.1",
+                &mut FixedTemperature,
+                Arc::new(LocalClock),
+                &mut display,
+                events,
+            ))
+            .unwrap(),
+            Disconnection::VersionMismatch {
+                rradio_version: String::from("0.0.0")
+            }
+        );
+
+        show_version_mismatch(&mut display, "0.0.0");
+
+        let snapshot = display.character_display().snapshot();
+
+        assert_eq!(snapshot[0], "Version mismatch    ");
+        assert!(snapshot[1].starts_with("rradio: 0.0.0"), "{:?}", snapshot);
+        assert!(
+            snapshot[2].starts_with(&format!("screen: {}", rradio_messages::VERSION)),
+            "{:?}",
+            snapshot
+        );
+    }
+
+    #[test]
+    fn exit_screen() {
+        use display::TextDisplay;
+
+        let ending = |exit_screen| {
+            let config = Config {
+                exit_screen,
+                ..Config::default()
+            };
+
+            let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+            display.write_to(Line(0), "Radio Seven");
+            display.set_cursor(CursorPosition { row: 0, column: 0 }, CursorStyle::Blinking);
+            display.flush();
+
+            show_exit_status(&config, &mut display, Ok(()), smol::future::pending());
+
+            let (_, cursor_style) = display.character_display().cursor();
+            assert_eq!(cursor_style, CursorStyle::Off, "{:?}", exit_screen);
+
+            display.character_display().snapshot()
+        };
+
+        assert_eq!(ending(ExitScreen::Message)[0], "Ending screen driver");
+        assert_eq!(ending(ExitScreen::Blank)[0], " ".repeat(20));
+        assert_eq!(ending(ExitScreen::Unchanged)[0], "Radio Seven         ");
+    }
+
+    #[test]
+    fn long_errors_scroll_by_line() {
+        /// Records the text written to each line
+        #[derive(Default)]
+        struct LineRecorder {
+            writes: Vec<String>,
+        }
+
+        impl display::TextDisplay for LineRecorder {
+            fn clear(&mut self) {}
+
+            fn write_to(
+                &mut self,
+                _segment: impl Into<display::Segment>,
+                item: impl std::fmt::Display,
+            ) {
+                self.writes.push(item.to_string());
+            }
+        }
+
+        let config = Config {
+            tick_interval: std::time::Duration::from_millis(1),
+            ..Config::default()
+        };
+
+        let lines = [
+            "First line of error:",
+            "second line of error",
+            "third line of error:",
+            "fourth line of error",
+            "fifth",
+        ];
+
+        let mut display = LineRecorder::default();
+
+        show_exit_status(
+            &config,
+            &mut display,
+            Err(anyhow::anyhow!("{}", lines.concat())),
+            smol::future::pending(),
+        );
+
+        // The whole error is shown, and the screen ends back at the beginning
+        assert!(display.writes.iter().any(|line| line.starts_with(lines[4])));
+        assert_eq!(display.writes[display.writes.len() - 4..], lines[..4]);
+    }
+}
@@ -0,0 +1,142 @@
+//! The command line arguments of the screen drivers, which override the configuration file
+
+use std::{ffi::OsString, net::SocketAddr, path::PathBuf};
+
+use anyhow::Context;
+
+use crate::Config;
+
+/// Shown when the arguments can't be parsed
+pub const USAGE: &str =
+    "Usage: [--config <file>] [--rradio-address <ip:port>] [--demo] [--self-test]";
+
+/// The command line arguments. With no arguments, the application runs as configured by the configuration file
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Args {
+    /// `--config <file>`, the configuration file to read instead of the default
+    pub config_file: Option<PathBuf>,
+    /// `--rradio-address <ip:port>`, which overrides [Config::rradio_address]
+    pub rradio_address: Option<SocketAddr>,
+    /// `--demo`, to run the demo rather than connecting to rradio
+    pub demo: bool,
+    /// `--self-test`, to show the self-test rather than running the application
+    pub self_test: bool,
+}
+
+impl Args {
+    /// Parse the arguments of this process
+    pub fn from_env() -> anyhow::Result<Self> {
+        Self::parse(std::env::args_os().skip(1))
+    }
+
+    /// Parse `args`, which don't include the program name. Values may be given as the next argument, or after an `=`, e.g. `--config=screen.toml`
+    pub fn parse(args: impl IntoIterator<Item = impl Into<OsString>>) -> anyhow::Result<Self> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter().map(Into::into);
+
+        while let Some(arg) = args.next() {
+            let arg = arg
+                .into_string()
+                .map_err(|arg| anyhow::anyhow!("{:?} is not valid unicode. {}", arg, USAGE))?;
+
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(OsString::from(value))),
+                None => (arg.as_str(), None),
+            };
+
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .with_context(|| format!("{} needs a value. {}", name, USAGE))
+            };
+
+            match name {
+                "--config" => parsed.config_file = Some(value()?.into()),
+                "--rradio-address" => {
+                    let address = value()?;
+                    parsed.rradio_address = Some(
+                        address
+                            .to_str()
+                            .and_then(|address| address.parse().ok())
+                            .with_context(|| {
+                                format!("{:?} is not an address such as 127.0.0.1:8002", address)
+                            })?,
+                    );
+                }
+                "--demo" if inline_value.is_none() => parsed.demo = true,
+                "--self-test" if inline_value.is_none() => parsed.self_test = true,
+                _ => anyhow::bail!("Unknown argument {:?}. {}", arg, USAGE),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Override the settings of `config` which were given on the command line
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(rradio_address) = self.rradio_address {
+            config.rradio_address = rradio_address;
+            // The address is only used when not connecting over a Unix domain socket
+            config.rradio_socket_path = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_arguments() {
+        assert_eq!(Args::parse(Vec::<String>::new()).unwrap(), Args::default());
+    }
+
+    #[test]
+    fn all_arguments() {
+        let args = Args::parse([
+            "--config",
+            "screen.toml",
+            "--rradio-address=192.168.0.20:8002",
+            "--demo",
+            "--self-test",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            args,
+            Args {
+                config_file: Some(PathBuf::from("screen.toml")),
+                rradio_address: Some("192.168.0.20:8002".parse().unwrap()),
+                demo: true,
+                self_test: true,
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        assert!(Args::parse(["--config"]).is_err());
+        assert!(Args::parse(["--rradio-address", "localhost"]).is_err());
+        assert!(Args::parse(["--demo=yes"]).is_err());
+        assert!(Args::parse(["--verbose"]).is_err());
+    }
+
+    #[test]
+    fn command_line_overrides_config() {
+        let mut config = Config {
+            rradio_socket_path: Some(PathBuf::from("/run/rradio.sock")),
+            ..Config::default()
+        };
+
+        Args::default().apply(&mut config);
+        assert!(config.rradio_socket_path.is_some());
+
+        Args::parse(["--rradio-address", "[::1]:9000"])
+            .unwrap()
+            .apply(&mut config);
+
+        assert_eq!(config.rradio_address, "[::1]:9000".parse().unwrap());
+        assert_eq!(config.rradio_socket_path, None);
+    }
+}
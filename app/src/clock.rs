@@ -0,0 +1,26 @@
+//! The source of the current date and time shown on the idle screen, which tests replace with a fixed time
+
+/// Provides the current local date and time
+pub trait Clock {
+    fn now(&self) -> chrono::DateTime<chrono::Local>;
+}
+
+/// The system clock, in the local timezone
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LocalClock;
+
+impl Clock for LocalClock {
+    fn now(&self) -> chrono::DateTime<chrono::Local> {
+        chrono::Local::now()
+    }
+}
+
+/// A clock which is stopped at the given time
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClock(pub chrono::DateTime<chrono::Local>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> chrono::DateTime<chrono::Local> {
+        self.0
+    }
+}
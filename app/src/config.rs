@@ -0,0 +1,303 @@
+//! The configuration of the application, which the screen drivers read from a TOML file.
+//! Every field has a default, so a file only needs the fields which differ from the defaults, and an empty file is valid, e.g.
+//!
+//! ```toml
+//! rradio_address = "192.168.0.20:8002"
+//! date_format = "%d/%m/%Y"
+//! temperature_unit = "Fahrenheit"
+//! tick_interval = 0.5
+//!
+//! [messages]
+//! no_connection = ["Waiting for", "the radio"]
+//! ```
+//!
+//! Durations are given in seconds, and may be fractional
+
+use std::{collections::BTreeMap, net::SocketAddr, path::PathBuf, time::Duration};
+
+use serde::{de::IgnoredAny, Deserialize};
+
+use crate::{CursorPosition, CursorStyle, Locale};
+
+/// Deserialize a duration from a number of seconds
+fn seconds<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    Duration::try_from_secs_f64(f64::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+/// Text shown by the screen driver outside of the main view, which can be replaced for localisation or branding.
+/// Each message is a line of the screen, and is cut short if it's too long to fit
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Messages {
+    /// Shown below the IP address while waiting to connect to rradio
+    pub no_connection: [String; 2],
+    /// Shown when the screen driver ends without an error
+    pub ending: [String; 4],
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Self {
+            no_connection: [
+                String::from("No connection to"),
+                String::from("internal program"),
+            ],
+            ending: [
+                String::from("Ending screen driver"),
+                String::from("Computer not shut"),
+                String::from("down"),
+                String::new(),
+            ],
+        }
+    }
+}
+
+/// Shown when the screen driver starts, e.g. for branding.
+/// The lines may use the bar glyphs, which on the LCD screen can be replaced by a logo using a character patterns file
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Splash {
+    /// Each line of the screen, which is cut short if it's too long to fit
+    pub lines: [String; 4],
+    /// The splash is shown for this long, or until rradio is connected to, whichever is sooner
+    #[serde(deserialize_with = "seconds")]
+    pub duration: Duration,
+}
+
+/// A tag shown on the second line of the station view
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum StationTag {
+    /// The position of the current track in the playlist, e.g. "3/12". Only URL list stations have a track number
+    TrackNumber,
+    /// The name of the station, or the organisation reported by the stream
+    StationTitle,
+    Artist,
+    Album,
+}
+
+/// The order in which tags are shown for each type of station. Tags which a type of station doesn't have are skipped
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TagOrder {
+    pub url_list: Vec<StationTag>,
+    pub samba: Vec<StationTag>,
+    pub cd: Vec<StationTag>,
+    pub usb: Vec<StationTag>,
+}
+
+impl Default for TagOrder {
+    fn default() -> Self {
+        use StationTag::*;
+
+        Self {
+            url_list: vec![TrackNumber, StationTitle],
+            samba: vec![StationTitle, Artist, Album],
+            cd: vec![Artist, Album],
+            usb: vec![Artist, Album],
+        }
+    }
+}
+
+/// How the position within the current track is shown for CDs, USB sticks and Samba shares
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum TrackPositionStyle {
+    /// The track number, and the position and duration in seconds, e.g. "3, 65 of 200"
+    TrackNumberAndSeconds,
+    /// The track number, and the position and duration in minutes and seconds, e.g. "3, 1:05 of 3:20"
+    TrackNumberAndMinutes,
+    /// The elapsed and remaining time, e.g. "01:05 / 02:15", which is easier to read for long tracks such as podcasts
+    ElapsedAndRemaining,
+}
+
+/// How the volume is shown
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum VolumeStyle {
+    /// The volume as reported by rradio, e.g. "70"
+    Raw,
+    /// The volume as a percentage of the range from [Config::min_volume] to [Config::max_volume], e.g. "70%"
+    Percentage,
+}
+
+/// What is shown when the screen driver ends without an error. Errors are always shown
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum ExitScreen {
+    /// [Messages::ending]
+    Message,
+    /// Nothing, leaving a blank screen
+    Blank,
+    /// Whatever was shown last, e.g. the station which was playing
+    Unchanged,
+}
+
+/// The unit in which the CPU temperature is shown
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+/// Options which customise the behaviour of the application
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// If true, track tags which match one of [Config::placeholder_tags] are not displayed
+    pub filter_unknown_tags: bool,
+    /// Values which rradio and streams use for missing tags, such as "unknown".
+    /// Tags are compared ignoring case and surrounding whitespace
+    pub placeholder_tags: Vec<String>,
+    /// Shown between the tags on the second line of the station view
+    pub tag_separator: String,
+    /// The order of the tags on the second line of the station view
+    pub tag_order: TagOrder,
+    /// How the position within the current track is shown
+    pub track_position_style: TrackPositionStyle,
+    /// Once rradio has finished pinging the station, the station view alternates between the ping time and the CPU temperature, each shown for this long
+    #[serde(deserialize_with = "seconds")]
+    pub ping_and_temperature_alternation: Duration,
+    /// The indices of stations whose servers never reply to ping, such as those behind firewalls.
+    /// Remote ping errors aren't shown for these stations, and the CPU temperature is shown instead
+    pub non_pingable_stations: Vec<String>,
+    /// The title of the next track in a playlist is shown for this long before the end of the current track. If zero, it isn't shown
+    #[serde(deserialize_with = "seconds")]
+    pub up_next_duration: Duration,
+    /// If true, the progress through the current track of a URL list station is shown across the whole bottom line of the station view,
+    /// moving smoothly between updates from rradio, rather than leaving the last character for the buffering spinner.
+    /// The progress is only shown if both the position and the duration of the track are known, and the line is otherwise blank
+    pub full_width_progress_bar: bool,
+    /// If true, the bottom line of the station view shows recent buffering levels while buffering, rather than just the latest, to help diagnose flaky streams
+    pub show_buffering_trend: bool,
+    /// If true, the average time taken to paint the screen is shown in the bottom right corner, for diagnosing slow displays
+    pub show_paint_time: bool,
+    /// The lowest volume reported by rradio, used to reserve space for the volume
+    pub min_volume: i32,
+    /// The highest volume reported by rradio, used to reserve space for the volume
+    pub max_volume: i32,
+    /// How the volume is shown
+    pub volume_style: VolumeStyle,
+    /// The volume which rradio reports while muted, which is shown as "Mute" rather than a number. If `None`, every volume is shown as a number
+    pub muted_volume: Option<i32>,
+    /// The file in which the volume is saved, so that it can be shown immediately after restarting. If `None`, the volume isn't saved
+    pub volume_file: Option<PathBuf>,
+    /// The file in which the contents of the screen are saved when ending, so that after a quick restart the same contents are shown
+    /// until rradio is connected to, rather than the screen flashing. If `None`, the contents aren't saved
+    pub screen_file: Option<PathBuf>,
+    /// The [chrono::format::strftime] format of the date shown on the idle screen.
+    /// If the date might not fit on a line, a compact format is used instead
+    pub date_format: String,
+    /// The [chrono::format::strftime] format of the time shown on the idle screen, e.g. "%l:%M" for a 12 hour clock.
+    /// If the time might not fit in the five characters at the start of the bottom line, "%R" is used instead
+    pub time_format: String,
+    /// The language of the names of days and months in the date shown on the idle screen
+    pub locale: Locale,
+    /// The unit in which the CPU temperature is shown
+    pub temperature_unit: TemperatureUnit,
+    /// The address on which rradio listens for TCP connections
+    pub rradio_address: SocketAddr,
+    /// The path of the Unix domain socket on which rradio is listening. If `None`, rradio is connected to over TCP at [Config::rradio_address]
+    pub rradio_socket_path: Option<PathBuf>,
+    /// If nothing is received from rradio for this long, the connection is assumed to have been lost and is reopened
+    #[serde(deserialize_with = "seconds")]
+    pub read_timeout: Duration,
+    /// If nothing is received from rradio for this long, an indicator is shown to warn that the information on the screen might be out of date
+    #[serde(deserialize_with = "seconds")]
+    pub stale_after: Duration,
+    /// If true, the time since the screen driver started is shown on the idle screen, which helps to spot unexpected restarts
+    pub show_uptime: bool,
+    /// The backlight brightness, from 0 (off) to 255 (brightest). If `None`, the brightness isn't changed
+    pub brightness: Option<u8>,
+    /// The contrast, from 0 to 255. If `None`, the contrast isn't changed
+    pub contrast: Option<u8>,
+    /// The time between ticks, which drive scrolling and other animations
+    #[serde(deserialize_with = "seconds")]
+    pub tick_interval: Duration,
+    /// Scrolling text in the station view moves on a step this often, rounded up to a whole number of ticks,
+    /// so that long tags and titles can be read without slowing down the rest of the screen
+    #[serde(deserialize_with = "seconds")]
+    pub scroll_interval: Duration,
+    /// Scrolling text in the station view waits for this long, rounded up to a whole number of scroll steps, before it starts scrolling
+    #[serde(deserialize_with = "seconds")]
+    pub scroll_pause: Duration,
+    /// How long the volume is shown for after changing, even if not playing
+    #[serde(deserialize_with = "seconds")]
+    pub force_show_volume_duration: Duration,
+    /// The hardware cursor is shown with this style under the volume while it's being changed, to make it easier to see which value is changing
+    pub volume_cursor: CursorStyle,
+    /// A character at this position, e.g. `{ row = 3, column = 19 }`, alternates between two glyphs each time an event is received from rradio,
+    /// so that a steady blink shows that information is arriving. It's painted over the view, so should be somewhere the view leaves blank.
+    /// If `None`, it isn't shown
+    pub freshness_indicator: Option<CursorPosition>,
+    /// Text shown while waiting to connect to rradio and when ending
+    pub messages: Messages,
+    /// What is shown when the screen driver ends without an error
+    pub exit_screen: ExitScreen,
+    /// Shown at startup, before the screen shown while waiting to connect to rradio. If `None`, no splash is shown
+    pub splash: Option<Splash>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            filter_unknown_tags: true,
+            placeholder_tags: vec![String::from("unknown"), String::from("N/A"), String::new()],
+            tag_separator: String::from(", "),
+            tag_order: TagOrder::default(),
+            track_position_style: TrackPositionStyle::TrackNumberAndSeconds,
+            ping_and_temperature_alternation: Duration::from_secs(3),
+            non_pingable_stations: Vec::new(),
+            up_next_duration: Duration::from_secs(10),
+            full_width_progress_bar: false,
+            show_buffering_trend: false,
+            show_paint_time: false,
+            min_volume: 0,
+            max_volume: 100,
+            volume_style: VolumeStyle::Raw,
+            muted_volume: Some(0),
+            volume_file: Some(PathBuf::from("/var/lib/rradio-screen/volume")),
+            screen_file: Some(PathBuf::from("/var/lib/rradio-screen/screen")),
+            date_format: String::from("%a %d %b %Y"),
+            time_format: String::from("%R"),
+            locale: Locale::English,
+            temperature_unit: TemperatureUnit::Celsius,
+            rradio_address: SocketAddr::from((std::net::Ipv4Addr::LOCALHOST, 8002)),
+            rradio_socket_path: None,
+            read_timeout: Duration::from_secs(30),
+            stale_after: Duration::from_secs(10),
+            show_uptime: false,
+            brightness: None,
+            contrast: None,
+            tick_interval: Duration::from_secs(1),
+            scroll_interval: Duration::from_secs(1),
+            scroll_pause: Duration::from_secs(2),
+            force_show_volume_duration: Duration::from_secs(2),
+            volume_cursor: CursorStyle::Off,
+            freshness_indicator: None,
+            messages: Messages::default(),
+            exit_screen: ExitScreen::Message,
+            splash: None,
+        }
+    }
+}
+
+/// Keys of a configuration file which neither the application nor the driver reading it know, e.g. because of a typo.
+/// Unknown keys can't simply be denied, as the application's fields are flattened into the driver's configuration,
+/// so the driver flattens this alongside them to collect the keys left over, and rejects them with [UnknownKeys::check]
+#[derive(Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct UnknownKeys(BTreeMap<String, IgnoredAny>);
+
+impl UnknownKeys {
+    /// Fail, naming the keys, if there are any
+    pub fn check(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.0.is_empty(),
+            "Unknown keys: {}",
+            self.0
+                .keys()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,344 @@
+//! A scripted, endlessly repeating sequence of synthetic rradio events, which shows off every part of the view.
+//! Used for shop window displays, and for developing the view without rradio
+
+use std::time::{Duration, Instant};
+
+use rradio_messages::{
+    Event as RradioEvent, OptionDiff, PingTarget, PingTimes, PipelineState, PlayerStateDiff,
+    Station, StationType, Track, TrackTags,
+};
+use smol::stream::{Stream, StreamExt};
+
+use crate::Event;
+
+fn track(title: &str) -> Track {
+    Track {
+        title: Some(title.into()),
+        album: None,
+        artist: None,
+        url: "http://example.com/demo".into(),
+        is_notification: false,
+    }
+}
+
+fn change_state(diff: PlayerStateDiff) -> RradioEvent {
+    RradioEvent::PlayerStateChanged(diff)
+}
+
+fn play_station(station: Station) -> RradioEvent {
+    change_state(PlayerStateDiff {
+        current_station: OptionDiff::ChangedToSome(station),
+        current_track_index: Some(0),
+        current_track_tags: OptionDiff::ChangedToNone,
+        track_position: OptionDiff::ChangedToNone,
+        track_duration: OptionDiff::ChangedToNone,
+        pipeline_state: Some(PipelineState::Playing),
+        buffering: Some(100),
+        ..PlayerStateDiff::default()
+    })
+}
+
+fn track_position(seconds: u64, duration_seconds: u64) -> RradioEvent {
+    change_state(PlayerStateDiff {
+        track_position: OptionDiff::ChangedToSome(Duration::from_secs(seconds)),
+        track_duration: OptionDiff::ChangedToSome(Duration::from_secs(duration_seconds)),
+        ..PlayerStateDiff::default()
+    })
+}
+
+/// Each step of the demo, and the number of ticks to wait before it
+fn script() -> Vec<(usize, RradioEvent)> {
+    vec![
+        // The idle screen, with the clock
+        (
+            5,
+            change_state(PlayerStateDiff {
+                current_station: OptionDiff::ChangedToNone,
+                pipeline_state: Some(PipelineState::Null),
+                volume: Some(60),
+                ping_times: Some(PingTimes::Gateway(Ok(Duration::from_micros(2500)))),
+                ..PlayerStateDiff::default()
+            }),
+        ),
+        // An internet radio station, which buffers, then plays a track with a long title
+        (
+            8,
+            change_state(PlayerStateDiff {
+                current_station: OptionDiff::ChangedToSome(Station {
+                    index: Some("12".into()),
+                    source_type: StationType::UrlList,
+                    title: Some("Demo Radio".into()),
+                    tracks: vec![track("Demo Stream")],
+                }),
+                current_track_index: Some(0),
+                pipeline_state: Some(PipelineState::Paused),
+                buffering: Some(40),
+                ..PlayerStateDiff::default()
+            }),
+        ),
+        (
+            4,
+            change_state(PlayerStateDiff {
+                pipeline_state: Some(PipelineState::Playing),
+                buffering: Some(100),
+                current_track_tags: OptionDiff::ChangedToSome(TrackTags {
+                    title: Some(
+                        "A track title which is far too long to fit on the screen, so scrolls"
+                            .into(),
+                    ),
+                    organisation: Some("Demo Radio".into()),
+                    ..TrackTags::default()
+                }),
+                ping_times: Some(PingTimes::GatewayAndRemote {
+                    gateway_ping: Duration::from_micros(2500),
+                    remote_ping: Ok(Duration::from_millis(24)),
+                    latest: PingTarget::Remote,
+                }),
+                ..PlayerStateDiff::default()
+            }),
+        ),
+        // Changing the volume
+        (
+            10,
+            change_state(PlayerStateDiff {
+                volume: Some(65),
+                ..PlayerStateDiff::default()
+            }),
+        ),
+        (
+            1,
+            change_state(PlayerStateDiff {
+                volume: Some(70),
+                ..PlayerStateDiff::default()
+            }),
+        ),
+        // An error, which is shown briefly over the station
+        (
+            4,
+            RradioEvent::LogMessage(rradio_messages::LogMessage::Error(
+                rradio_messages::Error::PipelineError("Demo error: stream interrupted".into()),
+            )),
+        ),
+        // A CD, showing the track position
+        (
+            8,
+            play_station(Station {
+                index: Some("00".into()),
+                source_type: StationType::CD,
+                title: None,
+                tracks: vec![track("Track 1"), track("Track 2")],
+            }),
+        ),
+        (
+            1,
+            change_state(PlayerStateDiff {
+                current_track_tags: OptionDiff::ChangedToSome(TrackTags {
+                    title: Some("First Track".into()),
+                    artist: Some("The Demo Band".into()),
+                    album: Some("Greatest Hits".into()),
+                    ..TrackTags::default()
+                }),
+                ..PlayerStateDiff::default()
+            }),
+        ),
+        (0, track_position(65, 200)),
+        (3, track_position(68, 200)),
+        (3, track_position(71, 200)),
+        // A USB stick and a Samba share
+        (
+            3,
+            play_station(Station {
+                index: Some("01".into()),
+                source_type: StationType::Usb,
+                title: None,
+                tracks: vec![Track {
+                    artist: Some("USB Artist".into()),
+                    album: Some("USB Album".into()),
+                    ..track("USB Track")
+                }],
+            }),
+        ),
+        (3, track_position(5, 300)),
+        (
+            5,
+            play_station(Station {
+                index: Some("55".into()),
+                source_type: StationType::Samba,
+                title: Some("Music Share".into()),
+                tracks: vec![Track {
+                    artist: Some("Composer".into()),
+                    album: Some("Symphony".into()),
+                    ..track("Movement I")
+                }],
+            }),
+        ),
+        (3, track_position(400, 600)),
+    ]
+}
+
+struct DemoState {
+    tick_time: Instant,
+    step: usize,
+    ticks_until_step: usize,
+}
+
+/// The demo events, with a tick every `tick_interval`. The demo starts with the protocol version, and then repeats forever
+pub fn demo_events(tick_interval: Duration) -> impl Stream<Item = Event> {
+    let script = std::sync::Arc::new(script());
+
+    let protocol_version = smol::stream::once(Event::RradioEvent(Ok(
+        RradioEvent::ProtocolVersion(rradio_messages::VERSION.into()),
+    )));
+
+    let steps = smol::stream::unfold(
+        DemoState {
+            tick_time: Instant::now(),
+            step: 0,
+            ticks_until_step: script[0].0,
+        },
+        move |mut state| {
+            let script = script.clone();
+
+            async move {
+                let mut events = Vec::new();
+
+                // Several steps may happen on the same tick
+                while state.ticks_until_step == 0 {
+                    events.push(Event::RradioEvent(Ok(script[state.step].1.clone())));
+
+                    state.step = (state.step + 1) % script.len();
+                    state.ticks_until_step = script[state.step].0;
+                }
+
+                state.tick_time = smol::Timer::at(state.tick_time + tick_interval).await;
+                state.ticks_until_step -= 1;
+
+                events.push(Event::TickEvent(state.tick_time));
+
+                Some((smol::stream::iter(events), state))
+            }
+        },
+    )
+    .flatten();
+
+    protocol_version.chain(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_shows_every_station_type() {
+        let script = script();
+
+        let station_types = script
+            .iter()
+            .filter_map(|(_, event)| match event {
+                RradioEvent::PlayerStateChanged(PlayerStateDiff {
+                    current_station: OptionDiff::ChangedToSome(station),
+                    ..
+                }) => Some(station.source_type),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        for station_type in [
+            StationType::UrlList,
+            StationType::CD,
+            StationType::Usb,
+            StationType::Samba,
+        ] {
+            assert!(station_types.contains(&station_type), "{:?}", station_type);
+        }
+
+        // If no step waited for a tick, the demo would never tick
+        assert!(script.iter().any(|&(ticks, _)| ticks > 0));
+    }
+
+    #[test]
+    fn demo_runs_through_the_script() {
+        use crate::display::{
+            CharacterDisplay, CursorPosition, RecordingDisplay, WrappingTextDisplay,
+        };
+
+        /// Records the screen each time a frame is flushed
+        #[derive(Default)]
+        struct FrameRecorder {
+            display: RecordingDisplay,
+            frames: Vec<String>,
+        }
+
+        impl CharacterDisplay for FrameRecorder {
+            fn clear(&mut self) {
+                self.display.clear();
+            }
+
+            fn move_cursor(&mut self, position: CursorPosition) {
+                self.display.move_cursor(position);
+            }
+
+            fn write_char(&mut self, c: char) {
+                self.display.write_char(c);
+            }
+
+            fn flush(&mut self) {
+                self.frames.push(self.display.snapshot().join("\n"));
+            }
+        }
+
+        struct FixedTemperature;
+
+        impl crate::TemperatureSource for FixedTemperature {
+            fn get_temperature(&mut self) -> crate::Temperature {
+                crate::Temperature(42)
+            }
+        }
+
+        let script_ticks = script().iter().map(|(ticks, _)| ticks).sum::<usize>();
+
+        let events = demo_events(Duration::from_millis(1))
+            .take(2 * script_ticks + script().len() + 1)
+            .chain(smol::stream::once(Event::Done));
+
+        let config = crate::Config {
+            volume_file: None,
+            ..crate::Config::default()
+        };
+
+        let mut display = WrappingTextDisplay::new(FrameRecorder::default());
+
+        smol::block_on(crate::application::do_run(
+            &config,
+            Instant::now(),
+            "192.168.0.1",
+            &mut FixedTemperature,
+            std::sync::Arc::new(crate::LocalClock),
+            &mut display,
+            events,
+        ))
+        .unwrap();
+
+        let frames = &display.character_display().frames;
+
+        // Each part of the script is shown, and the script starts again after the end
+        for text in [
+            "192.168.0.1",
+            "Demo Radio",
+            "A track title",
+            "rradio error:",
+            "First Track",
+            "USB Track",
+            "Music Share",
+        ] {
+            assert!(
+                frames.iter().any(|frame| frame.contains(text)),
+                "{:?} is never shown",
+                text
+            );
+        }
+
+        let last_shown = |text| frames.iter().rposition(|frame| frame.contains(text));
+        assert!(last_shown("Demo Radio") > last_shown("Music Share"));
+    }
+}
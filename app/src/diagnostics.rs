@@ -0,0 +1,88 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::display::{Line, TextDisplay};
+
+/// Measures the time taken to paint each frame, including writing to the display, and keeps a rolling average and the worst case.
+/// Each frame time is logged at trace level, and each new worst case at debug level
+#[derive(Default)]
+pub struct PaintTimer {
+    samples: VecDeque<Duration>,
+    worst: Duration,
+}
+
+impl PaintTimer {
+    const WINDOW_SIZE: usize = 8; // The number of most recent paints which are averaged
+
+    /// Record the time taken to paint a frame which started at `start`
+    pub fn frame_painted(&mut self, start: Instant) {
+        self.record(start.elapsed());
+    }
+
+    fn record(&mut self, paint_time: Duration) {
+        log::trace!("Frame painted in {:?}", paint_time);
+
+        if paint_time > self.worst {
+            self.worst = paint_time;
+            log::debug!("Worst case frame time is now {:?}", paint_time);
+        }
+
+        if self.samples.len() == Self::WINDOW_SIZE {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back(paint_time);
+    }
+
+    pub fn average(&self) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::ZERO;
+        }
+
+        self.samples.iter().sum::<Duration>() / self.samples.len() as u32
+    }
+
+    /// Write the average paint time in the bottom right corner of the screen, over the top of the view
+    pub fn paint_overlay(&self, display: &mut impl TextDisplay) {
+        let (_, overlay_segment) = Line(3).split(14);
+
+        display.write_to(overlay_segment, PaintTimeDisplay(self.average()));
+    }
+}
+
+/// Displays a paint time in milliseconds, e.g. "12.3ms"
+struct PaintTimeDisplay(Duration);
+
+impl fmt::Display for PaintTimeDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>4.1}ms", self.0.as_secs_f32() * 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolling_average() {
+        let mut paint_timer = PaintTimer::default();
+
+        assert_eq!(paint_timer.average(), Duration::ZERO);
+
+        for _ in 0..PaintTimer::WINDOW_SIZE {
+            paint_timer.record(Duration::from_millis(10));
+        }
+
+        assert_eq!(paint_timer.average(), Duration::from_millis(10));
+
+        for _ in 0..(PaintTimer::WINDOW_SIZE / 2) {
+            paint_timer.record(Duration::from_millis(30));
+        }
+
+        assert_eq!(paint_timer.average(), Duration::from_millis(20));
+        assert_eq!(paint_timer.worst, Duration::from_millis(30));
+    }
+}
@@ -1,23 +1,30 @@
-use std::fmt::{self, Write};
+use alloc::{string::String, vec::Vec};
+use core::{
+    convert::TryFrom,
+    fmt::{self, Write},
+};
 
 use super::{SCREEN_HEIGHT, SCREEN_WIDTH};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(deny_unknown_fields))]
 pub struct CursorPosition {
     pub row: u8,
     pub column: u8,
 }
 
 impl CursorPosition {
-    pub fn offset(mut self, offset: u8) -> Self {
-        self.column += offset;
+    /// The position `offset` characters further along, wrapping onto following lines.
+    /// Positions past the last representable row saturate rather than overflowing
+    pub fn offset(self, offset: u8) -> Self {
+        let width = usize::from(SCREEN_WIDTH);
+        let index = usize::from(self.row) * width + usize::from(self.column) + usize::from(offset);
 
-        while self.column >= SCREEN_WIDTH {
-            self.row += 1;
-            self.column -= SCREEN_WIDTH;
+        Self {
+            row: u8::try_from(index / width).unwrap_or(u8::MAX),
+            column: (index % width) as u8,
         }
-
-        self
     }
 }
 
@@ -28,7 +35,43 @@ pub struct Segment {
 }
 
 impl Segment {
+    /// Create a segment, checking that it starts on the screen and doesn't run off the end of the bottom line
+    pub fn new(position: CursorPosition, length: u8) -> anyhow::Result<Self> {
+        let segment = Self { position, length };
+
+        anyhow::ensure!(
+            segment.fits_on_screen(),
+            "{:?} does not fit on a {}x{} screen",
+            segment,
+            SCREEN_WIDTH,
+            SCREEN_HEIGHT
+        );
+
+        Ok(segment)
+    }
+
+    /// Returns true if the segment starts on the screen and doesn't run off the end of the bottom line
+    pub fn fits_on_screen(&self) -> bool {
+        let CursorPosition { row, column } = self.position;
+
+        let start = usize::from(row) * usize::from(SCREEN_WIDTH) + usize::from(column);
+        let screen_size = usize::from(SCREEN_WIDTH) * usize::from(SCREEN_HEIGHT);
+
+        row < SCREEN_HEIGHT
+            && column < SCREEN_WIDTH
+            && start + usize::from(self.length) <= screen_size
+    }
+
+    /// Returns true if the segment has no space for any characters
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Split the segment into the first `offset` characters and the rest.
+    /// If `offset` is past the end of the segment, the whole segment is first and the rest is empty
     pub fn split(self, offset: u8) -> (Self, Self) {
+        let offset = offset.min(self.length);
+
         (
             Self {
                 position: self.position,
@@ -64,6 +107,8 @@ impl Line {
 
 impl From<Line> for Segment {
     fn from(Line(row): Line) -> Self {
+        debug_assert!(row < SCREEN_HEIGHT, "Line {} is off the screen", row);
+
         Self {
             position: CursorPosition { row, column: 0 },
             length: SCREEN_WIDTH,
@@ -76,6 +121,13 @@ pub struct Lines(pub u8, pub u8);
 
 impl From<Lines> for Segment {
     fn from(Lines(row_start, row_end): Lines) -> Self {
+        debug_assert!(
+            row_start <= row_end && row_end < SCREEN_HEIGHT,
+            "Lines {} to {} are not on the screen",
+            row_start,
+            row_end
+        );
+
         Self {
             position: CursorPosition {
                 row: row_start,
@@ -98,6 +150,87 @@ impl From<EntireScreen> for Segment {
     }
 }
 
+/// The appearance of the hardware cursor
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(serde::Deserialize))]
+pub enum CursorStyle {
+    Off,
+    Underline,
+    Blinking,
+}
+
+/// The custom characters which widgets use to draw bars with sub-character resolution.
+/// Each is a vertical line in one of the five columns of a character, and is written as a character in the Unicode private use area,
+/// starting at `\u{E000}` for the left column
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BarGlyph {
+    Left,
+    LeftOfCentre,
+    Centre,
+    RightOfCentre,
+    Right,
+}
+
+impl BarGlyph {
+    pub const ALL: [Self; 5] = [
+        Self::Left,
+        Self::LeftOfCentre,
+        Self::Centre,
+        Self::RightOfCentre,
+        Self::Right,
+    ];
+
+    const FIRST_CODEPOINT: u32 = 0xE000;
+
+    /// The glyph which `c` is written as, if any
+    pub fn from_char(c: char) -> Option<Self> {
+        Self::ALL
+            .get(usize::try_from(u32::from(c).checked_sub(Self::FIRST_CODEPOINT)?).ok()?)
+            .copied()
+    }
+
+    /// The character in the private use area which the glyph is written as
+    pub fn to_char(self) -> char {
+        char::from_u32(Self::FIRST_CODEPOINT + u32::from(self.column())).unwrap()
+    }
+
+    /// The column of the character, from 0 on the left to 4 on the right, which the vertical line is in
+    pub fn column(self) -> u8 {
+        self as u8
+    }
+}
+
+impl fmt::Display for BarGlyph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char(self.to_char())
+    }
+}
+
+/// How displays which can't define custom characters, such as the terminal, show each [BarGlyph].
+/// The provided methods give the default mapping, which displays can override
+pub trait GlyphMap {
+    /// The character shown in place of `glyph`
+    fn bar_glyph(&self, glyph: BarGlyph) -> char {
+        match glyph {
+            BarGlyph::Left => '▌',
+            BarGlyph::LeftOfCentre => '▏',
+            BarGlyph::Centre => '|',
+            BarGlyph::RightOfCentre => '▕',
+            BarGlyph::Right => '▐',
+        }
+    }
+
+    /// The character shown in place of `c`, which is `c` unless it's a [BarGlyph]
+    fn map_char(&self, c: char) -> char {
+        BarGlyph::from_char(c).map_or(c, |glyph| self.bar_glyph(glyph))
+    }
+}
+
+/// The default [GlyphMap]
+pub struct DefaultGlyphMap;
+
+impl GlyphMap for DefaultGlyphMap {}
+
 /// A CharacterDisplay displays characters onto a screen
 ///
 /// # Example
@@ -118,6 +251,26 @@ pub trait CharacterDisplay {
     fn move_cursor(&mut self, position: CursorPosition);
     /// Write a single character to the screen, and move the cursor one place to the right
     fn write_char(&mut self, c: char);
+    /// Write a run of characters which all fit on the current line, moving the cursor along the line.
+    /// Displays which can write several characters more efficiently than one at a time can override this
+    fn write_str(&mut self, s: &str) {
+        for c in s.chars() {
+            self.write_char(c);
+        }
+    }
+    /// Called after a complete frame has been written, so that displays which buffer characters can update the screen
+    fn flush(&mut self) {}
+    /// Set the backlight brightness, from 0 (off) to 255 (brightest). Displays without adjustable brightness ignore this
+    fn set_brightness(&mut self, _level: u8) {}
+    /// Set the contrast, from 0 to 255. Displays without adjustable contrast ignore this
+    fn set_contrast(&mut self, _level: u8) {}
+    /// Show or hide the hardware cursor, which is shown at the cursor position. Displays without a hardware cursor ignore this
+    fn set_cursor_style(&mut self, _style: CursorStyle) {}
+    /// Whether `c` is shown as itself rather than as a placeholder, so that text can be transliterated before it's written.
+    /// Displays which can show any character don't override this
+    fn can_render(&self, _c: char) -> bool {
+        true
+    }
 }
 
 /// A TextDisplay display formatted strings onto a screen
@@ -133,14 +286,27 @@ pub trait CharacterDisplay {
 pub trait TextDisplay {
     fn clear(&mut self);
     fn write_to(&mut self, segment: impl Into<Segment>, item: impl fmt::Display);
+    /// Note that `segment` has been left as it was rather than written to, e.g. as it's yet to be revealed by a transition,
+    /// so that it isn't blanked as if nothing covers it
+    fn skip(&mut self, _segment: impl Into<Segment>) {}
+    /// Leave the cursor at `position` with the given style once the frame has been written, or hide it if `style` is [CursorStyle::Off]
+    fn set_cursor(&mut self, _position: CursorPosition, _style: CursorStyle) {}
+    fn flush(&mut self) {}
 }
 
-/// WrappingTextDisplay wraps long strings by automatically moving the cursor when having written to the end of a line
+/// WrappingTextDisplay wraps long strings by automatically moving the cursor when having written to the end of a line.
+/// Segments which start part way along a line, such as the right hand side of a split line, are clipped at the end of the line instead,
+/// so that they don't run into the widgets on the next line
 ///
 /// It's mostly used via the [TextDisplay] trait
 pub struct WrappingTextDisplay<D: CharacterDisplay> {
     character_display: D,
     segment: Segment,
+    /// False if the current segment starts part way along a line
+    segment_wraps: bool,
+    /// Where the cursor is left after a frame has been written
+    cursor_position: CursorPosition,
+    cursor_style: CursorStyle,
 }
 
 impl<D: CharacterDisplay> WrappingTextDisplay<D> {
@@ -148,13 +314,52 @@ impl<D: CharacterDisplay> WrappingTextDisplay<D> {
         Self {
             character_display,
             segment: EntireScreen.into(),
+            segment_wraps: true,
+            cursor_position: CursorPosition { row: 0, column: 0 },
+            cursor_style: CursorStyle::Off,
         }
     }
 }
 
-impl<D: CharacterDisplay> core::fmt::Write for WrappingTextDisplay<D> {
-    fn write_char(&mut self, c: char) -> fmt::Result {
+impl<D: CharacterDisplay> WrappingTextDisplay<D> {
+    /// The wrapped display
+    pub fn character_display(&self) -> &D {
+        &self.character_display
+    }
+}
+
+/// The ASCII transliteration of `c`, e.g. "Zh" for "Ж", if `character_display` can't render `c` but can render its transliteration.
+/// Transliterations may be longer than one character, so the rest of the text is moved along, and clipped at the end of the segment
+#[cfg(feature = "std")]
+fn transliteration(character_display: &impl CharacterDisplay, c: char) -> Option<&'static str> {
+    if character_display.can_render(c) {
+        return None;
+    }
+
+    match unidecode::unidecode_char(c) {
+        "" | "[?]" => None,
+        transliteration => transliteration
+            .chars()
+            .all(|c| character_display.can_render(c))
+            .then_some(transliteration),
+    }
+}
+
+/// Without std, the transliteration tables aren't available, so characters are written as they are
+#[cfg(not(feature = "std"))]
+fn transliteration(_character_display: &impl CharacterDisplay, _c: char) -> Option<&'static str> {
+    None
+}
+
+impl<D: CharacterDisplay> WrappingTextDisplay<D> {
+    /// Write `c` as it is, moving to the next line if needed
+    fn write_renderable_char(&mut self, c: char) {
         if self.segment.position.column >= SCREEN_WIDTH {
+            if !self.segment_wraps {
+                self.segment.length = 0;
+                return;
+            }
+
             self.segment.position.row += 1;
             self.segment.position.column = 0;
             self.character_display.move_cursor(self.segment.position);
@@ -165,12 +370,58 @@ impl<D: CharacterDisplay> core::fmt::Write for WrappingTextDisplay<D> {
             self.segment.position.column += 1;
             self.segment.length -= 1;
         }
+    }
+}
+
+impl<D: CharacterDisplay> core::fmt::Write for WrappingTextDisplay<D> {
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        match transliteration(&self.character_display, c) {
+            Some(transliteration) => transliteration
+                .chars()
+                .for_each(|c| self.write_renderable_char(c)),
+            None => self.write_renderable_char(c),
+        }
 
         Ok(())
     }
 
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        s.chars().try_for_each(|c| self.write_char(c))
+    fn write_str(&mut self, mut s: &str) -> fmt::Result {
+        while !s.is_empty() && self.segment.length > 0 {
+            if self.segment.position.column >= SCREEN_WIDTH {
+                if !self.segment_wraps {
+                    self.segment.length = 0;
+                    break;
+                }
+
+                self.segment.position.row += 1;
+                self.segment.position.column = 0;
+                self.character_display.move_cursor(self.segment.position);
+            }
+
+            // The characters which fit on the rest of both the line and the segment are written together
+            let run_length = (SCREEN_WIDTH - self.segment.position.column).min(self.segment.length);
+
+            let (run, rest) = match s.char_indices().nth(usize::from(run_length)) {
+                Some((run_end, _)) => s.split_at(run_end),
+                None => (s, ""),
+            };
+
+            if run.chars().all(|c| self.character_display.can_render(c)) {
+                let run_length = run.chars().count() as u8;
+
+                self.character_display.write_str(run);
+                self.segment.position.column += run_length;
+                self.segment.length -= run_length;
+            } else {
+                // Transliterations may be longer than the characters they replace, so the rest of the text is written by character
+                s.chars().try_for_each(|c| self.write_char(c))?;
+                break;
+            }
+
+            s = rest;
+        }
+
+        Ok(())
     }
 }
 
@@ -181,6 +432,18 @@ impl<D: CharacterDisplay> TextDisplay for WrappingTextDisplay<D> {
 
     fn write_to(&mut self, segment: impl Into<Segment>, item: impl fmt::Display) {
         self.segment = segment.into();
+        self.segment_wraps = self.segment.position.column == 0;
+
+        if self.segment.is_empty() {
+            return;
+        }
+
+        debug_assert!(
+            self.segment.fits_on_screen(),
+            "{:?} does not fit on the screen",
+            self.segment
+        );
+
         self.character_display.move_cursor(self.segment.position);
 
         // Cannot fail as Self::write_char cannot fail
@@ -191,6 +454,233 @@ impl<D: CharacterDisplay> TextDisplay for WrappingTextDisplay<D> {
             let _ = self.write_char(' ');
         }
     }
+
+    fn set_cursor(&mut self, position: CursorPosition, style: CursorStyle) {
+        if style != self.cursor_style {
+            self.character_display.set_cursor_style(style);
+        }
+
+        self.cursor_position = position;
+        self.cursor_style = style;
+    }
+
+    fn flush(&mut self) {
+        if self.cursor_style != CursorStyle::Off {
+            self.character_display.move_cursor(self.cursor_position);
+        }
+
+        self.character_display.flush();
+    }
+}
+
+type Cells = [[char; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize];
+
+const BLANK_CELLS: Cells = [[' '; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize];
+
+/// A [CharacterDisplay] which collects each frame, and when flushed, only writes the characters which have changed to the wrapped display.
+/// Writing to some displays, such as the LCD screen, takes long enough that repainting the whole screen causes a visible sweep
+pub struct DiffingDisplay<D: CharacterDisplay> {
+    character_display: D,
+    /// The frame being written
+    frame: Cells,
+    /// What the wrapped display shows, or None before the first frame has been flushed
+    shown: Option<Cells>,
+    cursor: CursorPosition,
+    cursor_style: CursorStyle,
+    /// The position of the cursor of the wrapped display, if known
+    display_cursor: Option<CursorPosition>,
+}
+
+impl<D: CharacterDisplay> DiffingDisplay<D> {
+    pub fn new(character_display: D) -> Self {
+        Self {
+            character_display,
+            frame: BLANK_CELLS,
+            shown: None,
+            cursor: CursorPosition { row: 0, column: 0 },
+            cursor_style: CursorStyle::Off,
+            display_cursor: None,
+        }
+    }
+
+    /// The wrapped display
+    pub fn character_display(&self) -> &D {
+        &self.character_display
+    }
+
+    /// What the wrapped display shows, one line per row, or `None` before the first frame has been flushed
+    pub fn shown_lines(&self) -> Option<Vec<String>> {
+        self.shown
+            .as_ref()
+            .map(|shown| shown.iter().map(|line| line.iter().collect()).collect())
+    }
+}
+
+impl<D: CharacterDisplay> CharacterDisplay for DiffingDisplay<D> {
+    fn clear(&mut self) {
+        self.frame = BLANK_CELLS;
+        self.cursor = CursorPosition { row: 0, column: 0 };
+    }
+
+    fn move_cursor(&mut self, position: CursorPosition) {
+        self.cursor = position;
+    }
+
+    fn write_char(&mut self, c: char) {
+        let CursorPosition { row, column } = self.cursor;
+
+        if let Some(character) = self
+            .frame
+            .get_mut(usize::from(row))
+            .and_then(|line| line.get_mut(usize::from(column)))
+        {
+            *character = c;
+        }
+
+        self.cursor.column += 1;
+    }
+
+    fn flush(&mut self) {
+        let shown = match &mut self.shown {
+            Some(shown) => shown,
+            None => {
+                self.character_display.clear();
+                self.display_cursor = None;
+                self.shown.insert(BLANK_CELLS)
+            }
+        };
+
+        // Consecutive changed characters are written together
+        let mut run = String::new();
+
+        for (row, (frame_line, shown_line)) in (0..).zip(self.frame.iter().zip(shown.iter_mut())) {
+            for (column, (&c, shown_c)) in (0..).zip(frame_line.iter().zip(shown_line.iter_mut())) {
+                if c == *shown_c {
+                    continue;
+                }
+
+                let position = CursorPosition { row, column };
+
+                if self.display_cursor != Some(position) {
+                    if !run.is_empty() {
+                        self.character_display.write_str(&run);
+                        run.clear();
+                    }
+
+                    self.character_display.move_cursor(position);
+                }
+
+                run.push(c);
+                *shown_c = c;
+
+                // The cursor of the wrapped display doesn't necessarily wrap onto the next line
+                self.display_cursor = Some(CursorPosition {
+                    row,
+                    column: column + 1,
+                });
+            }
+        }
+
+        if !run.is_empty() {
+            self.character_display.write_str(&run);
+        }
+
+        // A visible cursor is left where the frame left it
+        if self.cursor_style != CursorStyle::Off && self.display_cursor != Some(self.cursor) {
+            self.character_display.move_cursor(self.cursor);
+            self.display_cursor = Some(self.cursor);
+        }
+
+        self.character_display.flush();
+    }
+
+    fn set_brightness(&mut self, level: u8) {
+        self.character_display.set_brightness(level);
+    }
+
+    fn set_contrast(&mut self, level: u8) {
+        self.character_display.set_contrast(level);
+    }
+
+    fn set_cursor_style(&mut self, style: CursorStyle) {
+        self.cursor_style = style;
+        self.character_display.set_cursor_style(style);
+
+        // Setting the style may move the cursor to another controller of a screen with chained controllers, so the cursor is moved before the next write
+        self.display_cursor = None;
+    }
+
+    fn can_render(&self, c: char) -> bool {
+        self.character_display.can_render(c)
+    }
+}
+
+/// A [CharacterDisplay] which records the characters on the screen, so that tests can check what is shown
+#[cfg(test)]
+pub struct RecordingDisplay {
+    characters: [[char; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize],
+    cursor: CursorPosition,
+    cursor_style: CursorStyle,
+}
+
+#[cfg(test)]
+impl Default for RecordingDisplay {
+    fn default() -> Self {
+        Self {
+            characters: [[' '; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize],
+            cursor: CursorPosition { row: 0, column: 0 },
+            cursor_style: CursorStyle::Off,
+        }
+    }
+}
+
+#[cfg(test)]
+impl RecordingDisplay {
+    /// The position and style of the hardware cursor
+    pub fn cursor(&self) -> (CursorPosition, CursorStyle) {
+        (self.cursor, self.cursor_style)
+    }
+
+    /// The text of each line of the screen
+    pub fn snapshot(&self) -> Vec<String> {
+        self.characters
+            .iter()
+            .map(|line| line.iter().collect())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+impl CharacterDisplay for RecordingDisplay {
+    fn clear(&mut self) {
+        // Like a real screen, clearing doesn't change the cursor style
+        *self = Self {
+            cursor_style: self.cursor_style,
+            ..Self::default()
+        };
+    }
+
+    fn move_cursor(&mut self, position: CursorPosition) {
+        self.cursor = position;
+    }
+
+    fn set_cursor_style(&mut self, style: CursorStyle) {
+        self.cursor_style = style;
+    }
+
+    fn write_char(&mut self, c: char) {
+        let CursorPosition { row, column } = self.cursor;
+
+        if let Some(character) = self
+            .characters
+            .get_mut(usize::from(row))
+            .and_then(|line| line.get_mut(usize::from(column)))
+        {
+            *character = c;
+        }
+
+        self.cursor.column += 1;
+    }
 }
 
 #[cfg(test)]
@@ -209,6 +699,22 @@ mod tests {
 
     use mockall::{predicate::eq, Sequence};
 
+    #[test]
+    fn bar_glyphs() {
+        for glyph in BarGlyph::ALL {
+            assert_eq!(BarGlyph::from_char(glyph.to_char()), Some(glyph));
+        }
+
+        assert_eq!(BarGlyph::Left.to_char(), '\u{E000}');
+        assert_eq!(BarGlyph::Centre.to_string(), "\u{E002}");
+        assert_eq!(BarGlyph::from_char('\u{E004}'), Some(BarGlyph::Right));
+        assert_eq!(BarGlyph::from_char('\u{E005}'), None);
+        assert_eq!(BarGlyph::from_char('A'), None);
+
+        assert_eq!(DefaultGlyphMap.map_char('\u{E002}'), '|');
+        assert_eq!(DefaultGlyphMap.map_char('A'), 'A');
+    }
+
     fn expect_move_cursor(
         mock_character_device: &mut MockCharacterDisplay,
         seq: &mut Sequence,
@@ -276,14 +782,14 @@ mod tests {
 
         let mut mock_character_device = MockCharacterDisplay::new();
 
-        let first_line_cursor_position = CursorPosition { row: 1, column: 18 };
+        let first_line_cursor_position = CursorPosition { row: 1, column: 0 };
         let second_line_cursor_position = CursorPosition { row: 2, column: 0 };
-        let first_line = "ab";
-        let second_line = "cd";
+        let first_line = "abcdefghijklmnopqrst";
+        let second_line = "uv";
 
         let segment = Segment {
             position: first_line_cursor_position,
-            length: 4,
+            length: 22,
         };
 
         expect_move_cursor(
@@ -304,6 +810,65 @@ mod tests {
         display.write_to(segment, format_args!("{}{}", first_line, second_line));
     }
 
+    #[test]
+    fn test_clipping_string_starting_mid_line() {
+        let mut seq = Sequence::new();
+
+        let mut mock_character_device = MockCharacterDisplay::new();
+
+        let cursor_position = CursorPosition { row: 1, column: 18 };
+
+        let segment = Segment {
+            position: cursor_position,
+            length: 4,
+        };
+
+        expect_move_cursor(&mut mock_character_device, &mut seq, cursor_position);
+        expect_write_string(&mut mock_character_device, &mut seq, "ab");
+
+        mock_character_device.expect_move_cursor().never();
+
+        let mut display = WrappingTextDisplay::new(mock_character_device);
+
+        display.write_to(segment, "abcd");
+    }
+
+    #[test]
+    fn overflowing_split_segment_leaves_next_line() {
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        display.write_to(Line(2), "Left of next line");
+
+        let (_, right) = Line(1).split(15);
+        let right = Segment {
+            length: 10,
+            ..right
+        };
+
+        display.write_to(right, "0123456789");
+
+        assert_eq!(
+            display.character_display().snapshot()[1..3],
+            ["               01234", "Left of next line   "]
+        );
+    }
+
+    #[test]
+    fn two_labels_on_one_line() {
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        let (left, right) = Line(1).split(12);
+
+        display.write_to(Line(2), "Next line");
+        display.write_to(left, "A left label which is too long");
+        display.write_to(right, "Right label which is too long");
+
+        assert_eq!(
+            display.character_display().snapshot()[1..3],
+            ["A left labelRight la", "Next line           "]
+        );
+    }
+
     #[test]
     fn test_write_to_end_of_line() {
         let mut seq = Sequence::new();
@@ -331,6 +896,35 @@ mod tests {
         display.write_to(segment, text);
     }
 
+    #[test]
+    fn zero_length_segment() {
+        let mut mock_character_device = MockCharacterDisplay::new();
+
+        mock_character_device.expect_move_cursor().never();
+        mock_character_device.expect_write_char().never();
+
+        let (segment, _) = Line(1).split(0);
+
+        let mut display = WrappingTextDisplay::new(mock_character_device);
+
+        display.write_to(segment, "abc");
+    }
+
+    #[test]
+    fn full_width_split_of_bottom_line() {
+        let mut mock_character_device = MockCharacterDisplay::new();
+
+        mock_character_device.expect_move_cursor().never();
+        mock_character_device.expect_write_char().never();
+
+        let (_, rest) = Line(SCREEN_HEIGHT - 1).split(SCREEN_WIDTH);
+
+        let mut display = WrappingTextDisplay::new(mock_character_device);
+
+        display.write_to(rest, "abc");
+        display.write_to(rest, "");
+    }
+
     #[test]
     fn multiple_writes_without_wrapping() {
         use std::convert::TryInto;
@@ -362,4 +956,265 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn checked_segments() {
+        let segment = |row, column, length| Segment::new(CursorPosition { row, column }, length);
+
+        assert!(segment(0, 0, SCREEN_WIDTH * SCREEN_HEIGHT).is_ok());
+        assert!(segment(3, 19, 1).is_ok());
+        assert!(segment(2, 5, 35).is_ok());
+        assert!(segment(3, 19, 0).is_ok());
+
+        assert!(segment(0, 0, SCREEN_WIDTH * SCREEN_HEIGHT + 1).is_err());
+        assert!(segment(3, 19, 2).is_err());
+        assert!(segment(4, 0, 0).is_err());
+        assert!(segment(0, 20, 1).is_err());
+    }
+
+    #[test]
+    fn lines_fit_on_screen() {
+        for row in 0..SCREEN_HEIGHT {
+            assert!(Segment::from(Line(row)).fits_on_screen());
+        }
+
+        assert!(Segment::from(Lines(1, 3)).fits_on_screen());
+        assert!(Segment::from(EntireScreen).fits_on_screen());
+    }
+
+    #[test]
+    fn offset_positions() {
+        let position = |row, column| CursorPosition { row, column };
+
+        assert_eq!(position(0, 5).offset(3), position(0, 8));
+        assert_eq!(position(0, 19).offset(1), position(1, 0));
+        assert_eq!(position(1, 10).offset(50), position(4, 0));
+        assert_eq!(position(0, 19).offset(u8::MAX), position(13, 14));
+        assert_eq!(position(u8::MAX, 19).offset(1).row, u8::MAX);
+    }
+
+    #[test]
+    fn split_segments() {
+        let segment = Segment {
+            position: CursorPosition { row: 1, column: 5 },
+            length: 10,
+        };
+
+        let end = Segment {
+            position: CursorPosition { row: 1, column: 15 },
+            length: 0,
+        };
+
+        assert_eq!(
+            segment.split(4),
+            (
+                Segment {
+                    position: segment.position,
+                    length: 4
+                },
+                Segment {
+                    position: CursorPosition { row: 1, column: 9 },
+                    length: 6
+                }
+            )
+        );
+        assert_eq!(segment.split(10), (segment, end));
+        assert_eq!(segment.split(11), (segment, end));
+        assert_eq!(segment.split(u8::MAX), (segment, end));
+    }
+
+    #[test]
+    #[should_panic]
+    fn line_off_the_screen() {
+        let _ = Segment::from(Line(SCREEN_HEIGHT));
+    }
+
+    #[test]
+    fn diffing_display_only_writes_changed_characters() {
+        let mut seq = Sequence::new();
+
+        let mut mock_character_device = MockCharacterDisplay::new();
+
+        // The first frame clears the screen, then writes everything which isn't blank
+        mock_character_device
+            .expect_clear()
+            .once()
+            .in_sequence(&mut seq)
+            .returning(|| ());
+        expect_move_cursor(
+            &mut mock_character_device,
+            &mut seq,
+            CursorPosition { row: 1, column: 0 },
+        );
+        expect_write_string(&mut mock_character_device, &mut seq, "abc");
+
+        // The second frame only writes the changed character
+        expect_move_cursor(
+            &mut mock_character_device,
+            &mut seq,
+            CursorPosition { row: 1, column: 1 },
+        );
+        expect_write_string(&mut mock_character_device, &mut seq, "x");
+
+        let mut display = WrappingTextDisplay::new(DiffingDisplay::new(mock_character_device));
+
+        for text in ["abc", "axc", "axc"] {
+            display.clear();
+            display.write_to(Line(1), text);
+            display.flush();
+        }
+    }
+
+    #[test]
+    fn diffing_display_moves_cursor_after_setting_cursor_style() {
+        /// Records each clear, move of the cursor, run of characters, and cursor style
+        #[derive(Default)]
+        struct Recorder(Vec<String>);
+
+        impl CharacterDisplay for Recorder {
+            fn clear(&mut self) {
+                self.0.push("clear".into());
+            }
+
+            fn move_cursor(&mut self, CursorPosition { row, column }: CursorPosition) {
+                self.0.push(format!("({}, {})", row, column));
+            }
+
+            fn write_char(&mut self, c: char) {
+                self.0.push(c.to_string());
+            }
+
+            fn set_cursor_style(&mut self, style: CursorStyle) {
+                self.0.push(format!("{:?}", style));
+            }
+        }
+
+        let mut display = DiffingDisplay::new(Recorder::default());
+
+        display.move_cursor(CursorPosition { row: 1, column: 0 });
+        display.write_char('a');
+        display.flush();
+
+        display.set_cursor_style(CursorStyle::Blinking);
+
+        display.move_cursor(CursorPosition { row: 1, column: 1 });
+        display.write_char('b');
+        display.flush();
+
+        assert_eq!(
+            display.character_display.0,
+            ["clear", "(1, 0)", "a", "Blinking", "(1, 1)", "b"]
+        );
+    }
+
+    #[test]
+    fn shown_lines() {
+        let mut display =
+            WrappingTextDisplay::new(DiffingDisplay::new(RecordingDisplay::default()));
+
+        assert_eq!(display.character_display().shown_lines(), None);
+
+        display.write_to(Line(1), "abc");
+
+        // Only flushed frames are shown
+        assert_eq!(display.character_display().shown_lines(), None);
+
+        display.flush();
+
+        let blank_line = " ".repeat(SCREEN_WIDTH.into());
+        let second_line = format!("abc{}", " ".repeat(usize::from(SCREEN_WIDTH) - 3));
+
+        assert_eq!(
+            display.character_display().shown_lines(),
+            Some(vec![
+                blank_line.clone(),
+                second_line,
+                blank_line.clone(),
+                blank_line
+            ])
+        );
+    }
+
+    #[test]
+    fn text_is_written_in_runs() {
+        /// Records each move of the cursor, and each run of characters
+        #[derive(Default)]
+        struct RunRecorder(Vec<String>);
+
+        impl CharacterDisplay for RunRecorder {
+            fn clear(&mut self) {}
+
+            fn move_cursor(&mut self, CursorPosition { row, column }: CursorPosition) {
+                self.0.push(format!("({}, {})", row, column));
+            }
+
+            fn write_char(&mut self, c: char) {
+                self.0.push(c.to_string());
+            }
+
+            fn write_str(&mut self, s: &str) {
+                self.0.push(s.into());
+            }
+        }
+
+        let mut display = WrappingTextDisplay::new(RunRecorder::default());
+
+        display.write_to(
+            Segment {
+                position: CursorPosition { row: 0, column: 0 },
+                length: 25,
+            },
+            "The quick brown fox jumps over",
+        );
+
+        assert_eq!(
+            display.character_display().0,
+            ["(0, 0)", "The quick brown fox ", "(1, 0)", "jumps"]
+        );
+    }
+
+    #[test]
+    fn unrenderable_characters_are_transliterated() {
+        /// Records each character written, and can only render ASCII
+        #[derive(Default)]
+        struct AsciiRecorder(String);
+
+        impl CharacterDisplay for AsciiRecorder {
+            fn clear(&mut self) {}
+
+            fn move_cursor(&mut self, _position: CursorPosition) {}
+
+            fn write_char(&mut self, c: char) {
+                self.0.push(c);
+            }
+
+            fn can_render(&self, c: char) -> bool {
+                c.is_ascii()
+            }
+        }
+
+        let mut display = WrappingTextDisplay::new(AsciiRecorder::default());
+
+        display.write_to(
+            Segment {
+                position: CursorPosition { row: 0, column: 0 },
+                length: 10,
+            },
+            "Café ß€ ✓",
+        );
+
+        // Transliterations may be longer than the characters they replace, and the text is clipped at the end of the segment
+        assert_eq!(display.character_display().0, "Cafe ssEUR");
+
+        display.write_to(
+            Segment {
+                position: CursorPosition { row: 0, column: 0 },
+                length: 4,
+            },
+            "✓ ✓",
+        );
+
+        // Characters without a transliteration are written as they are
+        assert_eq!(display.character_display().0, "Cafe ssEUR✓ ✓ ");
+    }
 }
@@ -0,0 +1,64 @@
+//! Localised names of days and months, used when formatting dates
+
+use std::fmt::{self, Write};
+
+use chrono::{Date, NaiveDate, Utc};
+
+/// The language of the names of days and months
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+pub enum Locale {
+    English,
+    French,
+    German,
+    Spanish,
+}
+
+impl Locale {
+    fn chrono_locale(self) -> chrono::Locale {
+        match self {
+            Locale::English => chrono::Locale::en_GB,
+            Locale::French => chrono::Locale::fr_FR,
+            Locale::German => chrono::Locale::de_DE,
+            Locale::Spanish => chrono::Locale::es_ES,
+        }
+    }
+}
+
+/// Format `date` with the [chrono::format::strftime] `format`, naming days and months in the language of `locale`.
+/// Characters which the screen can't display, such as the "û" of "août", are transliterated when written to the screen
+pub fn format_date(date: NaiveDate, format: &str, locale: Locale) -> Result<String, fmt::Error> {
+    let mut text = String::new();
+    write!(
+        text,
+        "{}",
+        Date::<Utc>::from_utc(date, Utc).format_localized(format, locale.chrono_locale())
+    )?;
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn localised_names() {
+        let date = NaiveDate::from_ymd(2021, 2, 3);
+
+        assert_eq!(
+            format_date(date, "%a %d %b %Y", Locale::English).unwrap(),
+            "Wed 03 Feb 2021"
+        );
+        assert_eq!(
+            format_date(date, "%a %d %b %Y", Locale::French).unwrap(),
+            "mer. 03 févr. 2021"
+        );
+        assert_eq!(
+            format_date(date, "%A %d %B", Locale::German).unwrap(),
+            "Mittwoch 03 Februar"
+        );
+        assert_eq!(
+            format_date(NaiveDate::from_ymd(2021, 8, 7), "%a %d %b", Locale::Spanish).unwrap(),
+            "sáb 07 ago"
+        );
+    }
+}
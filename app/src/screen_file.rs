@@ -0,0 +1,108 @@
+//! Persisting the contents of the screen between runs, so that a quick restart doesn't flash the screen
+
+use std::{path::Path, time::Duration};
+
+use anyhow::Context;
+
+/// Load the screen saved by [save], if it was saved less than `max_age` ago. If the file is missing, corrupt, or too old, returns `None`
+pub async fn load(path: &Path, max_age: Duration) -> Option<[String; 4]> {
+    let age = smol::fs::metadata(path)
+        .await
+        .ok()?
+        .modified()
+        .ok()?
+        .elapsed()
+        .ok()?;
+
+    if age > max_age {
+        return None;
+    }
+
+    let contents = smol::fs::read_to_string(path).await.ok()?;
+
+    let mut lines = contents.split('\n').map(String::from);
+
+    let screen = [lines.next()?, lines.next()?, lines.next()?, lines.next()?];
+
+    lines.next().is_none().then_some(screen)
+}
+
+/// Save the contents of the screen so that they can be restored by [load]
+pub async fn save(path: &Path, lines: &[String]) -> anyhow::Result<()> {
+    smol::fs::write(path, lines.join("\n"))
+        .await
+        .with_context(|| format!("Failed to save screen to {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_AGE: Duration = Duration::from_secs(60);
+
+    fn test_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "rradio_screen_screen_{}_{}",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = test_path("round_trip");
+
+        let lines = [
+            String::from("12 Radio 4"),
+            String::new(),
+            String::from("  Bars: \u{E080}  "),
+            String::from("Volume 42"),
+        ];
+
+        smol::block_on(async {
+            save(&path, &lines).await.unwrap();
+            assert_eq!(load(&path, MAX_AGE).await, Some(lines));
+        });
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn old_file() {
+        let path = test_path("old_file");
+
+        let lines = [
+            String::from("Hello"),
+            String::new(),
+            String::new(),
+            String::new(),
+        ];
+
+        smol::block_on(async {
+            save(&path, &lines).await.unwrap();
+            assert_eq!(load(&path, Duration::ZERO).await, None);
+        });
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn missing_file() {
+        let path = test_path("missing_file");
+
+        assert_eq!(smol::block_on(load(&path, MAX_AGE)), None);
+    }
+
+    #[test]
+    fn wrong_number_of_lines() {
+        let path = test_path("wrong_number_of_lines");
+
+        for contents in ["one\ntwo\nthree", "one\ntwo\nthree\nfour\nfive"] {
+            std::fs::write(&path, contents).unwrap();
+
+            assert_eq!(smol::block_on(load(&path, MAX_AGE)), None);
+        }
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -24,6 +24,39 @@ fn update_option_arc<T>(
     )
 }
 
+/// The number of recent buffering levels kept, which is enough to fill the buffering bar
+pub const BUFFERING_HISTORY_LENGTH: usize = 19;
+
+/// The most recent buffering levels reported by rradio, so that whether a stream is recovering or degrading can be shown
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct BufferingHistory {
+    /// The buffering levels as fractions of a full buffer, oldest first
+    samples: [f32; BUFFERING_HISTORY_LENGTH],
+    count: usize,
+}
+
+impl BufferingHistory {
+    /// Add the latest buffering level, as a percentage, dropping the oldest if the history is full
+    pub fn push(&mut self, buffering: u8) {
+        if self.count == BUFFERING_HISTORY_LENGTH {
+            self.samples.rotate_left(1);
+            self.count -= 1;
+        }
+
+        self.samples[self.count] = f32::from(buffering) / 100.0;
+        self.count += 1;
+    }
+}
+
+impl AsRef<[f32]> for BufferingHistory {
+    fn as_ref(&self) -> &[f32] {
+        &self.samples[..self.count]
+    }
+}
+
+/// How many ticks an error reported by rradio is shown for before being cleared
+const ERROR_DISPLAY_TICS: usize = 5;
+
 #[derive(Clone)]
 pub struct PlayerState {
     pub pipeline_state: PipelineState,
@@ -32,41 +65,115 @@ pub struct PlayerState {
     pub current_track_tags: Option<TrackTags>,
     pub volume: i32,
     pub buffering: u8,
+    pub buffering_history: BufferingHistory,
     pub track_duration: Option<Duration>,
     pub track_position: Option<Duration>,
     pub ping_times: PingTimes,
+    /// The number of pings in a row which failed to look up the host name.
+    /// rradio doesn't say whether a DNS error is a blip or persistent, such as a mistyped host name, so the count shows which it is
+    pub consecutive_dns_errors: usize,
     pub station_not_found: Option<ArcStr>,
-    pub temperature: crate::Temperature,
+    /// The most recent error reported by rradio, which is cleared after [ERROR_DISPLAY_TICS] ticks
+    pub current_error: Option<ArcStr>,
+    pub current_error_tics_remaining: usize,
+    /// `None` until the temperature has been measured, which happens when ping times are received
+    pub temperature: Option<crate::Temperature>,
+    /// The number of events received from rradio, which wraps around, so that the view can show that events are still arriving
+    pub rradio_event_count: usize,
 }
 
 impl PlayerState {
     pub fn handle_log_message(mut self, message: rradio_messages::LogMessage) -> Self {
-        if let rradio_messages::LogMessage::Error(rradio_messages::Error::StationError(
+        let rradio_messages::LogMessage::Error(error) = message;
+
+        if let rradio_messages::Error::StationError(
             rradio_messages::StationError::StationNotFound { index, .. },
-        )) = message
+        ) = error
         {
             self.station_not_found = Some(index);
+        } else {
+            self.current_error = Some(ArcStr::from(error.to_string()));
+            self.current_error_tics_remaining = ERROR_DISPLAY_TICS;
+        }
+
+        self
+    }
+
+    /// Count down the time remaining to show the current error, clearing it once it has been shown for long enough
+    pub fn with_error_tick(mut self) -> Self {
+        self.current_error_tics_remaining = self.current_error_tics_remaining.saturating_sub(1);
+
+        if self.current_error_tics_remaining == 0 {
+            self.current_error = None;
         }
 
         self
     }
 
+    /// Count an event received from rradio
+    pub fn with_rradio_event(mut self) -> Self {
+        self.rradio_event_count = self.rradio_event_count.wrapping_add(1);
+
+        self
+    }
+
+    /// The track position, advanced by `time_played_since_reported`, the time which the track has been playing for since rradio last reported its position,
+    /// so that it moves smoothly between reports
+    pub fn interpolated_track_position(
+        &self,
+        time_played_since_reported: Duration,
+    ) -> Option<Duration> {
+        let track_position = self.track_position? + time_played_since_reported;
+
+        Some(match self.track_duration {
+            Some(track_duration) => track_position.min(track_duration),
+            None => track_position,
+        })
+    }
+
     pub fn with_new_temperature(mut self, temperature: crate::Temperature) -> Self {
-        self.temperature = temperature;
+        self.temperature = Some(temperature);
 
         self
     }
 
     pub fn apply_diff(mut self, diff: PlayerStateDiff) -> Self {
         update_value(&mut self.pipeline_state, diff.pipeline_state);
+        let station_has_changed =
+            !matches!(diff.current_station, rradio_messages::OptionDiff::NoChange);
         update_option_arc(&mut self.current_station, diff.current_station);
+        if station_has_changed {
+            // The previous station's buffering says nothing about how the new station's stream is doing
+            self.buffering_history = BufferingHistory::default();
+        }
         update_value(&mut self.current_track_index, diff.current_track_index);
         update_option(&mut self.current_track_tags, diff.current_track_tags);
         update_value(&mut self.volume, diff.volume);
-        update_value(&mut self.buffering, diff.buffering);
+        if let Some(buffering) = diff.buffering {
+            self.buffering = buffering;
+            self.buffering_history.push(buffering);
+        }
         update_option(&mut self.track_duration, diff.track_duration);
         update_option(&mut self.track_position, diff.track_position);
-        update_value(&mut self.ping_times, diff.ping_times);
+        if let Some(ping_times) = diff.ping_times {
+            self.consecutive_dns_errors = match ping_times {
+                PingTimes::Gateway(Err(rradio_messages::PingError::Dns))
+                | PingTimes::GatewayAndRemote {
+                    remote_ping: Err(rradio_messages::PingError::Dns),
+                    latest: rradio_messages::PingTarget::Remote,
+                    ..
+                } => self.consecutive_dns_errors + 1,
+                // Gateway pings between remote pings don't look up the host name, so don't end a run of DNS errors
+                PingTimes::GatewayAndRemote {
+                    latest: rradio_messages::PingTarget::Gateway,
+                    ..
+                }
+                | PingTimes::FinishedPingingRemote { .. } => self.consecutive_dns_errors,
+                _ => 0,
+            };
+
+            self.ping_times = ping_times;
+        }
 
         self
     }
@@ -81,11 +188,16 @@ impl Default for PlayerState {
             current_track_tags: None,
             volume: -1,
             buffering: 0,
+            buffering_history: BufferingHistory::default(),
             track_duration: None,
             track_position: None,
             station_not_found: None,
+            current_error: None,
+            current_error_tics_remaining: 0,
             ping_times: PingTimes::None,
-            temperature: crate::Temperature(255),
+            consecutive_dns_errors: 0,
+            temperature: None,
+            rradio_event_count: 0,
         }
     }
 }
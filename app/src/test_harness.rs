@@ -0,0 +1,392 @@
+//! Render the whole application for a script of rradio events, so that tests can check the complete screen
+
+use std::{convert::TryInto, sync::Arc, time::Instant};
+
+use rradio_messages::{LogMessage, PlayerStateDiff};
+
+use crate::{
+    display::{RecordingDisplay, WrappingTextDisplay},
+    Config, Event, FixedClock, Temperature, TemperatureSource,
+};
+
+/// An event in a test script
+#[allow(clippy::large_enum_variant)] // Scripts are short, so boxing the diffs isn't worth the noise in tests
+pub enum ScriptedEvent {
+    StateChanged(PlayerStateDiff),
+    Log(LogMessage),
+    Tick,
+}
+
+struct FixedTemperature;
+
+impl TemperatureSource for FixedTemperature {
+    fn get_temperature(&mut self) -> Temperature {
+        Temperature(42)
+    }
+}
+
+/// The IP address shown on the idle screen
+pub const IP_ADDRESS: &str = "192.168.0.1";
+
+/// The time shown on the idle screen, 14:35 on Tuesday 7th March 2023
+pub fn fixed_time() -> chrono::DateTime<chrono::Local> {
+    use chrono::TimeZone;
+
+    chrono::Local.ymd(2023, 3, 7).and_hms(14, 35, 0)
+}
+
+/// Run the application with `config` for `script`, and return the text of each line of the final screen
+pub fn render_screen(
+    config: &Config,
+    script: impl IntoIterator<Item = ScriptedEvent>,
+) -> [String; 4] {
+    let config = Config {
+        volume_file: None,
+        ..config.clone()
+    };
+
+    let events = std::iter::once(Event::RradioEvent(Ok(
+        rradio_messages::Event::ProtocolVersion(rradio_messages::VERSION.into()),
+    )))
+    .chain(script.into_iter().map(|event| match event {
+        ScriptedEvent::StateChanged(diff) => {
+            Event::RradioEvent(Ok(rradio_messages::Event::PlayerStateChanged(diff)))
+        }
+        ScriptedEvent::Log(message) => {
+            Event::RradioEvent(Ok(rradio_messages::Event::LogMessage(message)))
+        }
+        ScriptedEvent::Tick => Event::TickEvent(Instant::now()),
+    }))
+    .chain(std::iter::once(Event::Done))
+    .collect::<Vec<_>>();
+
+    let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+    smol::block_on(crate::application::do_run(
+        &config,
+        Instant::now(),
+        IP_ADDRESS,
+        &mut FixedTemperature,
+        Arc::new(FixedClock(fixed_time())),
+        &mut display,
+        smol::stream::iter(events),
+    ))
+    .expect("Scripted events should not fail");
+
+    let [line0, line1, line2, line3]: [String; 4] = display
+        .character_display()
+        .snapshot()
+        .try_into()
+        .expect("The screen has four lines");
+
+    [line0, line1, line2, line3]
+}
+
+mod tests {
+    use std::time::Duration;
+
+    use rradio_messages::{
+        OptionDiff, PingTimes, PipelineState, Station, StationType, Track, TrackTags,
+    };
+
+    use super::*;
+
+    fn track(title: &str) -> Track {
+        Track {
+            title: Some(title.into()),
+            album: None,
+            artist: None,
+            url: "http://example.com/track".into(),
+            is_notification: false,
+        }
+    }
+
+    /// Select `station`, then tick past the new station screen
+    fn play_station(station: Station, diff: PlayerStateDiff) -> Vec<ScriptedEvent> {
+        vec![
+            ScriptedEvent::StateChanged(PlayerStateDiff {
+                current_station: OptionDiff::ChangedToSome(station),
+                current_track_index: Some(0),
+                pipeline_state: Some(PipelineState::Playing),
+                volume: Some(70),
+                ..Default::default()
+            }),
+            ScriptedEvent::StateChanged(diff),
+            ScriptedEvent::Tick,
+            ScriptedEvent::Tick,
+            ScriptedEvent::Tick,
+        ]
+    }
+
+    #[test]
+    fn url_list_station_playing() {
+        let station = Station {
+            index: Some("12".into()),
+            source_type: StationType::UrlList,
+            title: Some("Radio Twelve".into()),
+            tracks: vec![track("Morning Show"), track("Evening Show")],
+        };
+
+        let screen = render_screen(
+            &Config::default(),
+            play_station(
+                station,
+                PlayerStateDiff {
+                    current_track_index: Some(1),
+                    ping_times: Some(PingTimes::Gateway(Ok(Duration::from_millis(3)))),
+                    ..Default::default()
+                },
+            ),
+        );
+
+        assert_eq!(
+            screen,
+            [
+                "LPing  3.0ms Vol  70",
+                "2/2, Radio Twelve   ",
+                "Evening Show        ",
+                "\u{E000}                   ",
+            ]
+        );
+    }
+
+    #[test]
+    fn cd_playing() {
+        let station = Station {
+            index: Some("00".into()),
+            source_type: StationType::CD,
+            title: None,
+            tracks: vec![track("Track 1"), track("Track 2")],
+        };
+
+        let screen = render_screen(
+            &Config::default(),
+            play_station(
+                station,
+                PlayerStateDiff {
+                    current_track_index: Some(1),
+                    current_track_tags: OptionDiff::ChangedToSome(TrackTags {
+                        title: Some("Second Song".into()),
+                        artist: Some("The Band".into()),
+                        album: Some("The Album".into()),
+                        ..Default::default()
+                    }),
+                    track_position: OptionDiff::ChangedToSome(Duration::from_secs(65)),
+                    track_duration: OptionDiff::ChangedToSome(Duration::from_secs(200)),
+                    ..Default::default()
+                },
+            ),
+        );
+
+        assert_eq!(
+            screen,
+            [
+                "2, 65 of 200 Vol  70",
+                "The Band, The Album ",
+                "Second Song         ",
+                "                    ",
+            ]
+        );
+    }
+
+    #[test]
+    fn samba_share_playing() {
+        let station = Station {
+            index: Some("55".into()),
+            source_type: StationType::Samba,
+            title: Some("Music Share".into()),
+            tracks: vec![Track {
+                artist: Some("Composer".into()),
+                album: Some("Symphony".into()),
+                ..track("Movement I")
+            }],
+        };
+
+        let screen = render_screen(
+            &Config::default(),
+            play_station(
+                station,
+                PlayerStateDiff {
+                    track_position: OptionDiff::ChangedToSome(Duration::from_secs(5)),
+                    track_duration: OptionDiff::ChangedToSome(Duration::from_secs(600)),
+                    ..Default::default()
+                },
+            ),
+        );
+
+        assert_eq!(
+            screen,
+            [
+                "1, 5 of 600  Vol  70",
+                "Music Share, Compose",
+                "Movement I          ",
+                "                    ",
+            ]
+        );
+    }
+
+    fn url_list_track_title(title: &str) -> [String; 4] {
+        let station = Station {
+            index: Some("12".into()),
+            source_type: StationType::UrlList,
+            title: Some("Radio Twelve".into()),
+            tracks: vec![track(title)],
+        };
+
+        render_screen(
+            &Config::default(),
+            play_station(
+                station,
+                PlayerStateDiff {
+                    track_position: OptionDiff::ChangedToSome(Duration::from_secs(30)),
+                    track_duration: OptionDiff::ChangedToSome(Duration::from_secs(60)),
+                    ..Default::default()
+                },
+            ),
+        )
+    }
+
+    /// The progress bar half way through a track
+    const HALF_PROGRESS_BAR: &str = "█████████\u{E002}          ";
+
+    #[test]
+    fn track_titles_which_fit_on_one_line_leave_room_for_the_progress_bar() {
+        let screen = url_list_track_title("Nineteen characters");
+        assert_eq!(screen[2], "Nineteen characters ");
+        assert_eq!(screen[3], HALF_PROGRESS_BAR);
+
+        let screen = url_list_track_title("Twenty characters!!!");
+        assert_eq!(screen[2], "Twenty characters!!!");
+        assert_eq!(screen[3], HALF_PROGRESS_BAR);
+    }
+
+    #[test]
+    fn longer_track_titles_wrap_over_the_progress_bar() {
+        let screen = url_list_track_title("Twenty-one characters");
+        assert_eq!(screen[2], "Twenty-one character");
+        assert_eq!(screen[3], "s                   ");
+    }
+
+    #[test]
+    fn buffering_trend() {
+        let station = Station {
+            index: Some("12".into()),
+            source_type: StationType::UrlList,
+            title: Some("Radio Twelve".into()),
+            tracks: vec![track("Morning Show")],
+        };
+
+        let buffering = |buffering| {
+            ScriptedEvent::StateChanged(PlayerStateDiff {
+                buffering: Some(buffering),
+                ..Default::default()
+            })
+        };
+
+        let mut script = play_station(station, PlayerStateDiff::default());
+        script.extend([buffering(100), buffering(75), buffering(40), buffering(10)]);
+
+        let config = Config {
+            show_buffering_trend: true,
+            ..Config::default()
+        };
+
+        assert_eq!(
+            render_screen(&config, script)[3],
+            "\u{E004}\u{E003}\u{E002}\u{E000}                "
+        );
+    }
+
+    #[test]
+    fn error_banner() {
+        let screen = render_screen(
+            &Config::default(),
+            vec![
+                ScriptedEvent::Log(LogMessage::Error(rradio_messages::Error::PipelineError(
+                    "Stream not found".into(),
+                ))),
+                ScriptedEvent::Tick,
+            ],
+        );
+
+        assert_eq!(
+            screen,
+            [
+                "rradio error:       ",
+                "Pipeline error: Stre",
+                "am not found        ",
+                "                    ",
+            ]
+        );
+    }
+
+    #[test]
+    fn no_station_idle_screen() {
+        let screen = render_screen(
+            &Config::default(),
+            vec![
+                ScriptedEvent::StateChanged(PlayerStateDiff {
+                    pipeline_state: Some(PipelineState::Null),
+                    volume: Some(70),
+                    ping_times: Some(PingTimes::Gateway(Ok(Duration::from_millis(3)))),
+                    ..Default::default()
+                }),
+                ScriptedEvent::Tick,
+                ScriptedEvent::Tick,
+                ScriptedEvent::Tick,
+            ],
+        );
+
+        assert_eq!(screen[0], "192.168.0.1     Null");
+        assert_eq!(screen[1], "Gateway: 3.0ms      ");
+        assert_eq!(screen[2], "Tue 07 Mar 2023     ");
+        assert_eq!(screen[3], "14:35  CPU Temp  42C");
+    }
+
+    #[test]
+    fn repeated_dns_errors_are_counted() {
+        let remote_ping = |remote_ping| {
+            ScriptedEvent::StateChanged(PlayerStateDiff {
+                ping_times: Some(PingTimes::GatewayAndRemote {
+                    gateway_ping: Duration::from_millis(3),
+                    remote_ping,
+                    latest: rradio_messages::PingTarget::Remote,
+                }),
+                ..Default::default()
+            })
+        };
+
+        let gateway_ping = || {
+            ScriptedEvent::StateChanged(PlayerStateDiff {
+                ping_times: Some(PingTimes::GatewayAndRemote {
+                    gateway_ping: Duration::from_millis(3),
+                    remote_ping: Err(rradio_messages::PingError::Dns),
+                    latest: rradio_messages::PingTarget::Gateway,
+                }),
+                ..Default::default()
+            })
+        };
+
+        let dns_error = || remote_ping(Err(rradio_messages::PingError::Dns));
+
+        let screen = render_screen(&Config::default(), vec![dns_error()]);
+        assert_eq!(screen[1], "Remote: DNS error   ");
+
+        let screen = render_screen(
+            &Config::default(),
+            vec![dns_error(), gateway_ping(), dns_error(), dns_error()],
+        );
+        assert_eq!(screen[1], "Remote: DNS x3      ");
+
+        let screen = render_screen(
+            &Config::default(),
+            vec![
+                dns_error(),
+                dns_error(),
+                remote_ping(Ok(Duration::from_millis(20))),
+                dns_error(),
+            ],
+        );
+        assert_eq!(screen[1], "Remote: DNS error   ");
+    }
+}
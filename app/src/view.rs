@@ -1,33 +1,49 @@
 use std::{
     fmt::{self, Write},
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use rradio_messages::{ArcStr, PipelineState, Station};
 
 use crate::{
-    display::{Line, Lines, Segment},
-    state::PlayerState,
+    clock::{Clock, LocalClock},
+    config::{Config, StationTag, TagOrder, TemperatureUnit, TrackPositionStyle, VolumeStyle},
+    display::{BarGlyph, CursorPosition, CursorStyle, Line, Lines, Segment, TextDisplay},
+    locale::{self, Locale},
+    state::{BufferingHistory, PlayerState},
     widgets::{
-        Either, EitherWidget, FixedLabel, FunctionScope, GeneratedLabel, Label, ScrollingLabel,
-        Widget, WidgetEvent, WidgetExt,
+        Blinker, Either, EitherWidget, FixedLabel, FunctionScope, GeneratedLabel, Label,
+        MultiLineScrollingLabel, ProgressBar, Rotating, Scope, ScopeWidget, ScrollMode,
+        ScrollingLabel, Sparkline, Spinner, Widget, WidgetEvent, WidgetExt, WipeTransition,
     },
+    SCREEN_HEIGHT,
 };
 
+/// Returns true if `tag` is one of `placeholder_tags`, ignoring case and surrounding whitespace
+fn is_placeholder_tag(tag: &str, placeholder_tags: &[String]) -> bool {
+    let tag = tag.trim();
+
+    placeholder_tags
+        .iter()
+        .any(|placeholder| tag.eq_ignore_ascii_case(placeholder.trim()))
+}
+
 #[derive(Clone, PartialEq, Eq)]
-struct ConcatenatedTrackTags<const N: usize> {
-    pub sep: &'static str,
-    pub tags: [Option<ArcStr>; N],
+struct ConcatenatedTrackTags<'a> {
+    pub sep: &'a str,
+    /// Tags which are skipped. Empty if placeholder tags aren't filtered
+    pub placeholder_tags: &'a [String],
+    pub tags: Vec<Option<ArcStr>>,
 }
 
-impl<const N: usize> fmt::Display for ConcatenatedTrackTags<N> {
+impl<'a> fmt::Display for ConcatenatedTrackTags<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut tags = self
             .tags
             .iter()
             .flatten()
-            .filter(|tag| tag.as_str() != "unknown"); // TODO: Case insentivive compare?
+            .filter(|tag| !is_placeholder_tag(tag, self.placeholder_tags));
 
         if let Some(first_tag) = tags.next() {
             f.write_str(first_tag.as_str())?;
@@ -62,30 +78,83 @@ fn display_short_ping_duration(
     write!(f, "{} {}ms", prefix, ShortPingDurationDisplay(ping))
 }
 
+/// A ping error. Repeated DNS errors are shown with a count, e.g. "DNS x3", to tell a persistent problem from a blip
+struct PingErrorDisplay {
+    error: rradio_messages::PingError,
+    consecutive_dns_errors: usize,
+}
+
+impl fmt::Display for PingErrorDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.error {
+            rradio_messages::PingError::Dns if self.consecutive_dns_errors > 1 => {
+                write!(f, "DNS x{}", self.consecutive_dns_errors)
+            }
+            rradio_messages::PingError::Dns => f.write_str("DNS error"),
+            rradio_messages::PingError::FailedToSendICMP => f.write_str("Tx fail"),
+            rradio_messages::PingError::FailedToRecieveICMP => f.write_str("Rx fail"),
+            rradio_messages::PingError::Timeout => f.write_str("No reply"),
+            rradio_messages::PingError::DestinationUnreachable => f.write_str("Unreachable"),
+        }
+    }
+}
+
 fn display_short_ping_error(
     f: &mut fmt::Formatter<'_>,
     prefix: &str,
-    error: rradio_messages::PingError,
+    error: PingErrorDisplay,
 ) -> fmt::Result {
-    write!(
-        f,
-        "{} {}",
-        prefix,
-        match error {
-            rradio_messages::PingError::Dns => "DNS error",
-            rradio_messages::PingError::FailedToSendICMP => "Tx fail",
-            rradio_messages::PingError::FailedToRecieveICMP => "Rx fail",
-            rradio_messages::PingError::Timeout => "No reply",
-            rradio_messages::PingError::DestinationUnreachable => "Unreachable",
-        }
-    )
+    write!(f, "{} {}", prefix, error)
+}
+
+/// A temperature and its unit, e.g. "45C". The width, if given, is the width of the number
+#[derive(Clone, Copy, PartialEq)]
+struct TemperatureDisplay {
+    temperature: crate::Temperature,
+    unit: TemperatureUnit,
+}
+
+impl fmt::Display for TemperatureDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let celsius = u16::from(self.temperature.0);
+
+        let (value, symbol) = match self.unit {
+            TemperatureUnit::Celsius => (celsius, 'C'),
+            TemperatureUnit::Fahrenheit => (celsius * 9 / 5 + 32, 'F'),
+        };
+
+        value.fmt(f)?;
+        f.write_char(symbol)
+    }
 }
 
 #[derive(PartialEq)]
 struct PingAndTemperatureDisplay {
     ping_times: rradio_messages::PingTimes,
-    temperature: crate::Temperature,
+    consecutive_dns_errors: usize,
+    /// `None` until the temperature has been measured
+    temperature: Option<TemperatureDisplay>,
     display_temperature: bool,
+    /// If true, the station doesn't reply to ping, so the temperature is shown rather than remote ping errors
+    hide_remote_ping_errors: bool,
+}
+
+impl PingAndTemperatureDisplay {
+    fn error(&self, error: rradio_messages::PingError) -> PingErrorDisplay {
+        PingErrorDisplay {
+            error,
+            consecutive_dns_errors: self.consecutive_dns_errors,
+        }
+    }
+
+    /// Whether the temperature is shown in place of the ping time, as the pinging has finished
+    fn shows_temperature(&self) -> bool {
+        self.temperature.is_some()
+            && matches!(
+                self.ping_times,
+                rradio_messages::PingTimes::FinishedPingingRemote { .. }
+            )
+    }
 }
 
 impl fmt::Display for PingAndTemperatureDisplay {
@@ -97,7 +166,7 @@ impl fmt::Display for PingAndTemperatureDisplay {
                 display_short_ping_duration(f, "LPing", gateway_ping)
             }
             rradio_messages::PingTimes::Gateway(Err(gateway_error)) => {
-                display_short_ping_error(f, "LPing", gateway_error)
+                display_short_ping_error(f, "LPing", self.error(gateway_error))
             }
             rradio_messages::PingTimes::GatewayAndRemote {
                 gateway_ping,
@@ -113,12 +182,18 @@ impl fmt::Display for PingAndTemperatureDisplay {
                 gateway_ping: _,
                 remote_ping: Err(remote_error),
                 latest: rradio_messages::PingTarget::Remote,
-            } => display_short_ping_error(f, "RPing", remote_error),
-            rradio_messages::PingTimes::FinishedPingingRemote { gateway_ping } => {
-                if self.display_temperature {
-                    write!(f, "CPU Temp {}C", self.temperature.0)
+            } => {
+                if let (true, Some(temperature)) = (self.hide_remote_ping_errors, self.temperature)
+                {
+                    write!(f, "CPU Temp {}", temperature)
                 } else {
-                    display_short_ping_duration(f, "LPing", gateway_ping)
+                    display_short_ping_error(f, "RPing", self.error(remote_error))
+                }
+            }
+            rradio_messages::PingTimes::FinishedPingingRemote { gateway_ping } => {
+                match (self.display_temperature, self.temperature) {
+                    (true, Some(temperature)) => write!(f, "CPU Temp {}", temperature),
+                    _ => display_short_ping_duration(f, "LPing", gateway_ping),
                 }
             }
         }
@@ -134,11 +209,19 @@ fn space_required_for_digits(n: usize) -> usize {
     }
 }
 
-struct OptionDurationDisplay(Option<Duration>);
+/// Displays a duration in seconds, e.g. "187", or if `show_minutes` is true, in minutes and seconds, e.g. "3:07".
+/// Unknown durations are shown as "?"
+struct OptionDurationDisplay {
+    duration: Option<Duration>,
+    show_minutes: bool,
+}
 
 impl OptionDurationDisplay {
     fn space_required(&self) -> usize {
-        match self.0 {
+        match self.duration {
+            Some(duration) if self.show_minutes => {
+                space_required_for_digits((duration.as_secs() / 60) as usize) + 3
+            }
             Some(duration) => space_required_for_digits(duration.as_secs() as usize),
             None => 1,
         }
@@ -147,26 +230,92 @@ impl OptionDurationDisplay {
 
 impl fmt::Display for OptionDurationDisplay {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self.0 {
+        match self.duration {
+            Some(duration) if self.show_minutes => {
+                let seconds = duration.as_secs();
+                write!(f, "{}:{:02}", seconds / 60, seconds % 60)
+            }
             Some(duration) => duration.as_secs().fmt(f),
             None => '?'.fmt(f),
         }
     }
 }
 
+/// Displays a duration like a clock, e.g. "03:07", or "1:03:07" if longer than an hour, or "--:--" if unknown
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct ClockStyleDuration(Option<Duration>);
+
+impl ClockStyleDuration {
+    fn space_required(&self) -> usize {
+        match self.0 {
+            Some(duration) if duration.as_secs() >= 3600 => {
+                space_required_for_digits((duration.as_secs() / 3600) as usize) + 6
+            }
+            _ => 5,
+        }
+    }
+}
+
+impl fmt::Display for ClockStyleDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = match self.0 {
+            Some(duration) => duration.as_secs(),
+            None => return f.write_str("--:--"),
+        };
+
+        let (hours, minutes, seconds) = (seconds / 3600, (seconds / 60) % 60, seconds % 60);
+
+        if hours > 0 {
+            write!(f, "{}:{:02}:{:02}", hours, minutes, seconds)
+        } else {
+            write!(f, "{:02}:{:02}", minutes, seconds)
+        }
+    }
+}
+
 #[derive(PartialEq, Eq)]
 struct TrackPositionDisplay {
     track_index: usize,
     track_position: Option<Duration>,
     track_duration: Option<Duration>,
+    style: TrackPositionStyle,
+}
+
+impl TrackPositionDisplay {
+    /// Displays the elapsed and remaining time, e.g. "01:05 / 02:15", leaving out the remaining time if there isn't space for it
+    fn fmt_elapsed_and_remaining(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let elapsed = ClockStyleDuration(self.track_position);
+        let remaining = ClockStyleDuration(
+            self.track_duration
+                .map(|duration| duration.saturating_sub(self.track_position.unwrap_or_default())),
+        );
+
+        match elapsed.space_required() + remaining.space_required() {
+            0..=10 => write!(f, "{} / {}", elapsed, remaining),
+            11..=12 => write!(f, "{}/{}", elapsed, remaining),
+            _ => write!(f, "{}", elapsed),
+        }
+    }
 }
 
 impl fmt::Display for TrackPositionDisplay {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let TrackPositionStyle::ElapsedAndRemaining = self.style {
+            return self.fmt_elapsed_and_remaining(f);
+        }
+
         let track_index_space_required = if self.track_index < 10 { 1 } else { 2 };
 
-        let track_position = OptionDurationDisplay(self.track_position);
-        let track_duration = OptionDurationDisplay(self.track_duration);
+        let show_minutes = self.style == TrackPositionStyle::TrackNumberAndMinutes;
+
+        let track_position = OptionDurationDisplay {
+            duration: self.track_position,
+            show_minutes,
+        };
+        let track_duration = OptionDurationDisplay {
+            duration: self.track_duration,
+            show_minutes,
+        };
 
         let total_space_required = track_index_space_required
             + track_position.space_required()
@@ -190,7 +339,7 @@ impl fmt::Display for TrackPositionDisplay {
             ),
             10 => write!(
                 f,
-                "{}, {}of{}",
+                "{},{}of{}",
                 self.track_index, track_position, track_duration
             ),
             _ => write!(f, "{}, {}", self.track_index, track_position),
@@ -198,32 +347,166 @@ impl fmt::Display for TrackPositionDisplay {
     }
 }
 
+/// The number of characters needed to display any volume between `min_volume` and `max_volume`
+fn volume_width(min_volume: i32, max_volume: i32) -> u8 {
+    let width = |volume: i32| volume.to_string().len();
+
+    width(min_volume).max(width(max_volume)) as u8
+}
+
+/// Choose the label in front of the volume, and the space reserved for it, so that a volume of width `volume_width` fits within `segment_length`
+fn volume_label_layout(segment_length: u8, volume_width: u8) -> (&'static str, u8) {
+    let label_length = segment_length.saturating_sub(volume_width);
+
+    let label = match label_length {
+        0..=1 => "",
+        2..=3 => "V",
+        _ => "Vol",
+    };
+
+    (label, label_length)
+}
+
+/// The number of ticks, rounded up, which last at least `duration`
+fn duration_in_tics(duration: Duration, tick_interval: Duration) -> usize {
+    if tick_interval.is_zero() {
+        return 0;
+    }
+
+    let tics = duration.as_secs_f64() / tick_interval.as_secs_f64();
+
+    tics.ceil() as usize
+}
+
+/// An event filter which passes on every `tics_per_step`th tick, carrying the time elapsed since the last tick passed on,
+/// so that the filtered widget, such as a scrolling label, moves more slowly than the rest of the screen
+fn every_nth_tick<Data>(
+    tics_per_step: usize,
+) -> impl FnMut(&WidgetEvent, &Data) -> Option<WidgetEvent> {
+    let mut tics = 0_usize;
+    let mut elapsed_since_step = Duration::ZERO;
+
+    move |event, _| match *event {
+        WidgetEvent::Tick(time, elapsed) => {
+            tics += 1;
+            elapsed_since_step += elapsed;
+
+            if tics < tics_per_step {
+                return None;
+            }
+
+            let step = WidgetEvent::Tick(time, elapsed_since_step);
+
+            tics = 0;
+            elapsed_since_step = Duration::ZERO;
+
+            Some(step)
+        }
+    }
+}
+
+/// The space needed for a volume shown as a percentage, i.e. "100%"
+const VOLUME_PERCENTAGE_WIDTH: u8 = 4;
+
+/// A volume, or "--" if it's below the lowest volume, e.g. before rradio has reported the volume, when it's -1
+#[derive(Clone, Copy, PartialEq)]
+struct VolumeDisplay {
+    volume: i32,
+    min_volume: i32,
+    max_volume: i32,
+    style: VolumeStyle,
+}
+
+impl VolumeDisplay {
+    /// The volume as a percentage of the volume range, rounded to the nearest percent
+    fn percentage(&self) -> i64 {
+        let range = i64::from(self.max_volume) - i64::from(self.min_volume);
+
+        if range <= 0 {
+            return 100;
+        }
+
+        let volume = i64::from(self.volume.min(self.max_volume)) - i64::from(self.min_volume);
+
+        (200 * volume + range) / (2 * range)
+    }
+}
+
+impl fmt::Display for VolumeDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.volume < self.min_volume {
+            f.pad("--")
+        } else {
+            match self.style {
+                VolumeStyle::Raw => self.volume.fmt(f),
+                VolumeStyle::Percentage => f.pad(&format!("{}%", self.percentage())),
+            }
+        }
+    }
+}
+
+type VolumeAndPipelineState = (i32, PipelineState);
+type VolumeOrPipelineState = Either<i32, PipelineState>;
+
 fn volume_and_pipeline_state_view(
+    config: &Config,
     segment: impl Into<Segment>,
-) -> impl Widget<Data = (i32, rradio_messages::PipelineState)> {
+) -> ScopeWidget<
+    VolumeAndPipelineState,
+    impl Widget<Data = VolumeOrPipelineState>,
+    impl Scope<In = VolumeAndPipelineState, Out = VolumeOrPipelineState>,
+> {
     let segment: Segment = segment.into();
 
     let volume = {
-        let (s1, s2) = segment.split(4);
-        FixedLabel::new("Vol", s1).group(Label::new(s2).align_right())
+        let volume_width = match config.volume_style {
+            VolumeStyle::Raw => volume_width(config.min_volume, config.max_volume),
+            VolumeStyle::Percentage => VOLUME_PERCENTAGE_WIDTH,
+        };
+        let (label, label_length) = volume_label_layout(segment.length, volume_width);
+        let (s1, s2) = segment.split(label_length);
+        let level = FixedLabel::new(label, s1).group(Label::new(s2).align_right());
+
+        let min_volume = config.min_volume;
+        let max_volume = config.max_volume;
+        let style = config.volume_style;
+        let muted_volume = config.muted_volume;
+
+        EitherWidget::new(level, Label::new(segment).align_right()).with_lens(move |&volume| {
+            if Some(volume) == muted_volume {
+                Either::B("Mute")
+            } else {
+                Either::A(VolumeDisplay {
+                    volume,
+                    min_volume,
+                    max_volume,
+                    style,
+                })
+            }
+        })
     };
-    let pipeline_state = Label::new(segment).align_right();
+    let pipeline_state = Label::new(segment).align_right().truncate_with_ellipsis();
+
+    // The volume is shown for this many ticks after changing, even if not playing
+    let force_show_volume_tics =
+        duration_in_tics(config.force_show_volume_duration, config.tick_interval);
 
     EitherWidget::new(volume, pipeline_state).with_scope(FunctionScope::new(
         0_usize,
         |force_show_volume_tics_remaining, event, _| match event {
-            WidgetEvent::Tick(_) => {
+            WidgetEvent::Tick(..) => {
                 *force_show_volume_tics_remaining =
                     force_show_volume_tics_remaining.saturating_sub(1)
             }
         },
-        |force_show_volume_tics_remaining, &(old_volume, old_state), &(volume, state)| {
-            if old_volume != volume {
-                *force_show_volume_tics_remaining = 2;
+        move |force_show_volume_tics_remaining, &(old_volume, old_state), &(volume, state)| {
+            // The pipeline state is shown as soon as it changes, unless the volume is being adjusted, i.e. has changed since the last tick
+            if old_state != state && *force_show_volume_tics_remaining < force_show_volume_tics {
+                *force_show_volume_tics_remaining = 0;
             }
 
-            if old_state != state {
-                *force_show_volume_tics_remaining = 0;
+            if old_volume != volume {
+                *force_show_volume_tics_remaining = force_show_volume_tics;
             }
         },
         |&force_show_volume_tics_remaining, &(volume, pipeline_state)| {
@@ -242,6 +525,7 @@ fn volume_and_pipeline_state_view(
 enum StationTags {
     UrlList {
         current_track_index: Option<usize>,
+        track_count: usize,
         station_title: Option<ArcStr>,
     },
 
@@ -260,52 +544,157 @@ enum StationTags {
     },
 }
 
-impl fmt::Display for StationTags {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let sep = ", ";
-        match self.clone() {
-            StationTags::UrlList {
-                current_track_index,
-                station_title,
-            } => ConcatenatedTrackTags {
-                sep,
-                tags: [
-                    current_track_index.map(|current_track_index| {
-                        rradio_messages::arcstr::format!("{}", current_track_index)
-                    }),
-                    station_title,
-                ],
+#[derive(Clone, PartialEq)]
+struct StationTagsDisplay {
+    station_tags: StationTags,
+    placeholder_tags: Arc<[String]>,
+    tag_separator: ArcStr,
+    tag_order: Arc<TagOrder>,
+}
+
+impl StationTags {
+    /// The value of `tag`, or `None` if this type of station doesn't have it
+    fn tag(&self, tag: StationTag) -> Option<ArcStr> {
+        match (self, tag) {
+            (
+                StationTags::UrlList {
+                    current_track_index,
+                    track_count,
+                    ..
+                },
+                StationTag::TrackNumber,
+            ) => current_track_index.map(|current_track_index| {
+                rradio_messages::arcstr::format!("{}/{}", current_track_index, track_count)
+            }),
+            (StationTags::UrlList { station_title, .. }, StationTag::StationTitle)
+            | (StationTags::Samba { station_title, .. }, StationTag::StationTitle) => {
+                station_title.clone()
             }
-            .fmt(f),
-            StationTags::Samba {
-                station_title,
-                artist,
-                album,
-            } => ConcatenatedTrackTags {
-                sep,
-                tags: [station_title, artist, album],
-            }
-            .fmt(f),
-            StationTags::CD { artist, album } | StationTags::Usb { artist, album } => {
-                ConcatenatedTrackTags {
-                    sep,
-                    tags: [artist, album],
+            (StationTags::Samba { artist, .. }, StationTag::Artist)
+            | (StationTags::CD { artist, .. }, StationTag::Artist)
+            | (StationTags::Usb { artist, .. }, StationTag::Artist) => artist.clone(),
+            (StationTags::Samba { album, .. }, StationTag::Album)
+            | (StationTags::CD { album, .. }, StationTag::Album)
+            | (StationTags::Usb { album, .. }, StationTag::Album) => album.clone(),
+            _ => None,
+        }
+    }
+
+    fn order<'a>(&self, tag_order: &'a TagOrder) -> &'a [StationTag] {
+        match self {
+            StationTags::UrlList { .. } => &tag_order.url_list,
+            StationTags::Samba { .. } => &tag_order.samba,
+            StationTags::CD { .. } => &tag_order.cd,
+            StationTags::Usb { .. } => &tag_order.usb,
+        }
+    }
+}
+
+impl fmt::Display for StationTagsDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        ConcatenatedTrackTags {
+            sep: &self.tag_separator,
+            placeholder_tags: &self.placeholder_tags,
+            tags: self
+                .station_tags
+                .order(&self.tag_order)
+                .iter()
+                .map(|&tag| self.station_tags.tag(tag))
+                .collect(),
+        }
+        .fmt(f)
+    }
+}
+
+/// Returns "Next: " followed by the title of the next track in the playlist if the current track is within `up_next_duration` of its end.
+/// Notifications are skipped, and nothing is shown for the last track
+fn up_next_title(
+    station: &Station,
+    state: &PlayerState,
+    time_played_since_reported: Duration,
+    up_next_duration: Duration,
+) -> Option<ArcStr> {
+    let time_remaining = state
+        .track_duration?
+        .saturating_sub(state.interpolated_track_position(time_played_since_reported)?);
+
+    if up_next_duration.is_zero() || time_remaining > up_next_duration {
+        return None;
+    }
+
+    let next_track = station
+        .tracks
+        .iter()
+        .skip(state.current_track_index + 1)
+        .find(|track| !track.is_notification)?;
+
+    Some(rradio_messages::arcstr::format!(
+        "Next: {}",
+        next_track.title.as_ref()?
+    ))
+}
+
+/// Measures the time which the current track has been playing for since rradio last reported its position, from the time between ticks,
+/// so that the track position can be interpolated between reports. Other updates from rradio leave the time alone
+#[derive(Default)]
+struct TimePlayedSinceReported(Duration);
+
+impl Scope for TimePlayedSinceReported {
+    type In = (Arc<Station>, Arc<PlayerState>);
+    type Out = (Arc<Station>, Arc<PlayerState>, Duration);
+
+    fn event(&mut self, event: &WidgetEvent, (_, state): &Self::In) {
+        match event {
+            WidgetEvent::Tick(_, elapsed) => {
+                if state.pipeline_state == PipelineState::Playing && state.track_position.is_some()
+                {
+                    self.0 += *elapsed;
                 }
-                .fmt(f)
             }
         }
     }
+
+    fn update(&mut self, (_, old_state): &Self::In, (_, state): &Self::In) {
+        if old_state.track_position != state.track_position
+            || old_state.current_track_index != state.current_track_index
+        {
+            self.0 = Duration::ZERO;
+        }
+    }
+
+    // The track keeps playing while the view is repainted
+    fn reset(&mut self) {}
+
+    fn data(&self, (station, state): &Self::In) -> Self::Out {
+        (station.clone(), state.clone(), self.0)
+    }
 }
 
-fn displayed_url_list_track_index(station: &Station, state: &PlayerState) -> Option<usize> {
-    let playlist_starts_with_notification = station.tracks.get(0)?.is_notification;
-    let track_index_offset = if playlist_starts_with_notification {
-        0
+/// The number of tracks in the station's playlist, excluding notifications
+fn url_list_track_count(station: &Station) -> usize {
+    station
+        .tracks
+        .iter()
+        .filter(|track| !track.is_notification)
+        .count()
+}
+
+/// The track number shown to the user, counting from 1.
+/// Playlists may start with a notification, such as a station announcement, which isn't counted
+fn displayed_track_number(station: &Station, track_index: usize) -> usize {
+    let playlist_starts_with_notification =
+        matches!(station.tracks.first(), Some(track) if track.is_notification);
+
+    if playlist_starts_with_notification {
+        track_index
     } else {
-        1
-    };
+        track_index + 1
+    }
+}
 
-    let track_index = state.current_track_index + track_index_offset;
+/// The number of the current track, or `None` for the first track, which is the only track of most URL lists
+fn displayed_url_list_track_index(station: &Station, state: &PlayerState) -> Option<usize> {
+    let track_index = displayed_track_number(station, state.current_track_index);
 
     if track_index > 1 {
         Some(track_index)
@@ -319,60 +708,70 @@ struct BufferingBar(u8);
 
 impl fmt::Display for BufferingBar {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let position = (self.0 / 5).min(19);
-        let char_code = self.0 % 5;
+        let position = (self.0 / 5).min(18);
+        let glyph = BarGlyph::ALL[usize::from(self.0 % 5)];
 
         for _ in 0..position {
             f.write_char(' ')?;
         }
 
-        f.write_char(unsafe { char::from_u32_unchecked(0xE000_u32 + u32::from(char_code)) })
+        glyph.fmt(f)
     }
 }
 
-fn station_view() -> impl Widget<Data = (Arc<Station>, PlayerState)> {
+/// The view of a station which is playing
+pub fn station_view(config: &Config) -> impl Widget<Data = (Arc<Station>, Arc<PlayerState>)> {
     let (ping_segment, volume_and_pipeline_state_segment) = Line(0).split(13);
 
-    let ping_and_temperature = Label::new(ping_segment).with_scope(FunctionScope::new(
-        false,
-        |_, _, _| {},
-        |display_temperature,
-         (_, old_state): &(Arc<Station>, PlayerState),
-         (_, state): &(Arc<Station>, PlayerState)| {
-            if old_state.ping_times != state.ping_times {
-                *display_temperature = !*display_temperature;
-            }
-        },
-        |&display_temperature, (_, state): &(Arc<Station>, PlayerState)| {
+    let non_pingable_stations: Arc<[String]> = config.non_pingable_stations.clone().into();
+    let temperature_unit = config.temperature_unit;
+
+    // The ping time and temperature each stay for several ticks, so that they don't flicker when pings are frequent
+    let ping_and_temperature_tics = duration_in_tics(
+        config.ping_and_temperature_alternation,
+        config.tick_interval,
+    )
+    .max(1);
+
+    let ping_and_temperature_display =
+        move |(station, state): &(Arc<Station>, Arc<PlayerState>), display_temperature: bool| {
             PingAndTemperatureDisplay {
                 ping_times: state.ping_times.clone(),
-                temperature: state.temperature,
+                consecutive_dns_errors: state.consecutive_dns_errors,
+                temperature: state.temperature.map(|temperature| TemperatureDisplay {
+                    temperature,
+                    unit: temperature_unit,
+                }),
                 display_temperature,
+                hide_remote_ping_errors: non_pingable_stations
+                    .iter()
+                    .any(|index| station.index.as_deref() == Some(index.as_str())),
             }
-        },
-    ));
-
-    let track_position =
-        Label::new(ping_segment).with_lens(|(station, state): &(Arc<Station>, PlayerState)| {
-            let offset = match station.tracks.first() {
-                Some(first_track) => {
-                    if first_track.is_notification {
-                        0
-                    } else {
-                        1
-                    }
-                }
-                None => 0,
-            };
-            TrackPositionDisplay {
-                track_index: state.current_track_index + offset,
-                track_position: state.track_position,
-                track_duration: state.track_duration,
-            }
+        };
+
+    let ping_and_temperature = Rotating::new(ping_segment, ping_and_temperature_tics)
+        .with_item({
+            let ping_and_temperature_display = ping_and_temperature_display.clone();
+            move |data| Some(ping_and_temperature_display(data, false))
+        })
+        .with_item(move |data| {
+            Some(ping_and_temperature_display(data, true))
+                .filter(PingAndTemperatureDisplay::shows_temperature)
         });
 
+    let track_position_style = config.track_position_style;
+
+    let track_position = Label::new(ping_segment).with_lens(
+        move |(station, state): &(Arc<Station>, Arc<PlayerState>)| TrackPositionDisplay {
+            track_index: displayed_track_number(station, state.current_track_index),
+            track_position: state.track_position,
+            track_duration: state.track_duration,
+            style: track_position_style,
+        },
+    );
+
     let ping_or_track_position = EitherWidget::new(ping_and_temperature, track_position).with_lens(
-        |(station, state): &(Arc<Station>, PlayerState)| {
+        |(station, state): &(Arc<Station>, Arc<PlayerState>)| {
             if let rradio_messages::StationType::UrlList = station.source_type {
                 Either::A((station.clone(), state.clone()))
             } else {
@@ -381,13 +780,29 @@ fn station_view() -> impl Widget<Data = (Arc<Station>, PlayerState)> {
         },
     );
 
-    let volume_and_pipeline_state = volume_and_pipeline_state_view(
-        volume_and_pipeline_state_segment,
-    )
-    .with_lens(|(_, state): &(Arc<Station>, PlayerState)| (state.volume, state.pipeline_state));
+    let volume_and_pipeline_state =
+        volume_and_pipeline_state_view(config, volume_and_pipeline_state_segment).with_lens(
+            |(_, state): &(Arc<Station>, Arc<PlayerState>)| (state.volume, state.pipeline_state),
+        );
+
+    let placeholder_tags: Arc<[String]> = if config.filter_unknown_tags {
+        config.placeholder_tags.clone().into()
+    } else {
+        Arc::new([])
+    };
+    let tag_separator = ArcStr::from(config.tag_separator.as_str());
+    let tag_order = Arc::new(config.tag_order.clone());
+
+    let scroll_tics = duration_in_tics(config.scroll_interval, config.tick_interval).max(1);
+    // The pause is counted in scroll steps, as the scrolling labels only see every few ticks
+    let scroll_pause_tics = duration_in_tics(
+        config.scroll_pause,
+        config.scroll_interval.max(config.tick_interval),
+    );
 
-    let station_tags =
-        ScrollingLabel::new(Line(1)).with_lens(|(station, state): &(Arc<Station>, PlayerState)| {
+    let station_tags = ScrollingLabel::new(Line(1))
+        .with_pause_ticks(scroll_pause_tics)
+        .with_lens(move |(station, state): &(Arc<Station>, Arc<PlayerState>)| {
             let current_track = station.tracks.get(state.current_track_index);
             let current_tags = state.current_track_tags.as_ref();
 
@@ -403,9 +818,10 @@ fn station_view() -> impl Widget<Data = (Arc<Station>, PlayerState)> {
                 .and_then(|tags| tags.album.clone())
                 .or_else(|| current_track.and_then(|track| track.album.clone()));
 
-            match station.source_type {
+            let station_tags = match station.source_type {
                 rradio_messages::StationType::UrlList => StationTags::UrlList {
                     current_track_index: displayed_url_list_track_index(station, state),
+                    track_count: url_list_track_count(station),
                     station_title,
                 },
                 rradio_messages::StationType::Samba => StationTags::Samba {
@@ -415,42 +831,148 @@ fn station_view() -> impl Widget<Data = (Arc<Station>, PlayerState)> {
                 },
                 rradio_messages::StationType::CD => StationTags::CD { artist, album },
                 rradio_messages::StationType::Usb => StationTags::Usb { artist, album },
+            };
+
+            StationTagsDisplay {
+                station_tags,
+                placeholder_tags: placeholder_tags.clone(),
+                tag_separator: tag_separator.clone(),
+                tag_order: tag_order.clone(),
             }
-        });
+        })
+        .with_event_filter(every_nth_tick(scroll_tics));
+
+    let up_next_duration = config.up_next_duration;
+    let show_buffering_trend = config.show_buffering_trend;
+    let full_width_progress_bar = config.full_width_progress_bar;
 
     let track_title = EitherWidget::new(
         {
-            let track_metadata =
-                ScrollingLabel::new(Line(2)).with_lens(|(tags, _): &(ArcStr, _)| tags.clone());
-            let buffer = Label::new(Line(3)).with_lens(|&(_, buffering)| BufferingBar(buffering));
-            track_metadata.group(buffer)
+            // A full width bar leaves no room for the buffering spinner
+            let (bar_segment, spinner_segment) = if full_width_progress_bar {
+                (Line(3).into(), None)
+            } else {
+                let (bar_segment, spinner_segment) = Line(3).split(19);
+                (bar_segment, Some(spinner_segment))
+            };
+
+            let track_metadata = ScrollingLabel::new(Line(2))
+                .with_pause_ticks(scroll_pause_tics)
+                .with_lens(|(tags, _, _, _): &(ArcStr, _, _, _)| tags.clone())
+                .with_event_filter(every_nth_tick(scroll_tics));
+            let buffering_bar = EitherWidget::new(
+                Sparkline::new(bar_segment),
+                Label::new(bar_segment).with_lens(|&buffering| BufferingBar(buffering)),
+            )
+            .with_lens(move |(buffering, history): &(u8, BufferingHistory)| {
+                if show_buffering_trend {
+                    Either::A(*history)
+                } else {
+                    Either::B(*buffering)
+                }
+            });
+            // Without a duration, the full width bar is hidden, as a buffering bar in its place would look like progress
+            let buffer_or_progress = EitherWidget::new(
+                ProgressBar::new(bar_segment),
+                buffering_bar.or_blank(bar_segment),
+            )
+            .with_lens(
+                move |&(_, buffering, progress, _): &(
+                    ArcStr,
+                    (u8, BufferingHistory),
+                    Option<f32>,
+                    bool,
+                )| match progress {
+                    Some(progress) => Either::A(progress),
+                    None if full_width_progress_bar => Either::B(None),
+                    None => Either::B(Some(buffering)),
+                },
+            );
+            let buffering_spinner = spinner_segment.map(|spinner_segment| {
+                Spinner::new(spinner_segment).with_lens(
+                    |&(_, _, _, is_buffering): &(
+                        ArcStr,
+                        (u8, BufferingHistory),
+                        Option<f32>,
+                        bool,
+                    )| is_buffering,
+                )
+            });
+            track_metadata
+                .group(buffer_or_progress)
+                .group(buffering_spinner)
         },
-        ScrollingLabel::new(Lines(2, 3)),
+        MultiLineScrollingLabel::new(Lines(2, 3))
+            .with_pause_ticks(scroll_pause_tics)
+            .with_event_filter(every_nth_tick(scroll_tics)),
     )
-    .with_lens(|(station, state): &(Arc<Station>, PlayerState)| {
-        let current_track = station.tracks.get(state.current_track_index);
-        let current_tags = state.current_track_tags.as_ref();
+    .map_data(
+        move |(station, state, time_played_since_reported): &(
+            Arc<Station>,
+            Arc<PlayerState>,
+            Duration,
+        )| {
+            let current_track = station.tracks.get(state.current_track_index);
+            let current_tags = state.current_track_tags.as_ref();
 
-        let title = current_tags
-            .and_then(|tags| tags.title.clone())
-            .or_else(|| current_track.and_then(|track| track.title.clone()))
-            .unwrap_or_default();
+            let title = current_tags
+                .and_then(|tags| tags.title.clone())
+                .or_else(|| current_track.and_then(|track| track.title.clone()))
+                .unwrap_or_default();
 
-        if let rradio_messages::StationType::UrlList = station.source_type {
-            if title.chars().count() > 20 {
-                Either::B(title)
+            if let rradio_messages::StationType::UrlList = station.source_type {
+                // The next track is previewed on the title line, leaving the progress bar in place
+                let up_next = up_next_title(
+                    station,
+                    state,
+                    *time_played_since_reported,
+                    up_next_duration,
+                );
+
+                // A title which fits on one line leaves the line below for the progress bar,
+                // whereas longer titles wrap onto both lines, and only scroll if they don't fit on two
+                if up_next.is_none() && title.chars().count() > usize::from(crate::SCREEN_WIDTH) {
+                    Either::B(title)
+                } else {
+                    // The full width bar moves smoothly between updates from rradio
+                    let track_position = if full_width_progress_bar {
+                        state.interpolated_track_position(*time_played_since_reported)
+                    } else {
+                        state.track_position
+                    };
+
+                    // Without a duration, there's no progress bar, and the buffering level is shown instead, unless the bar is full width
+                    let progress = track_position
+                        .zip(state.track_duration)
+                        .filter(|(_, duration)| !duration.is_zero())
+                        .map(|(position, duration)| {
+                            position.as_secs_f32() / duration.as_secs_f32()
+                        });
+
+                    let is_buffering = state.buffering > 0
+                        && state.buffering < 100
+                        && state.pipeline_state != PipelineState::Playing;
+
+                    Either::A((
+                        up_next.unwrap_or(title),
+                        (state.buffering, state.buffering_history),
+                        progress,
+                        is_buffering,
+                    ))
+                }
             } else {
-                Either::A((title, state.buffering))
+                Either::B(title)
             }
-        } else {
-            Either::B(title)
-        }
-    });
+        },
+    )
+    .with_scope(TimePlayedSinceReported::default());
 
-    ping_or_track_position
-        .group(volume_and_pipeline_state)
-        .group(station_tags)
-        .group(track_title)
+    (
+        ping_or_track_position,
+        volume_and_pipeline_state,
+        station_tags,
+        track_title,
+    )
 }
 
 #[derive(Clone, PartialEq, Eq)]
@@ -475,35 +997,36 @@ fn display_ping_duration(
 fn display_ping_error(
     f: &mut fmt::Formatter<'_>,
     prefix: &str,
-    error: rradio_messages::PingError,
+    error: PingErrorDisplay,
 ) -> fmt::Result {
-    write!(
-        f,
-        "{}: {}",
-        prefix,
-        match error {
-            rradio_messages::PingError::Dns => "DNS error",
-            rradio_messages::PingError::FailedToSendICMP => "Tx fail",
-            rradio_messages::PingError::FailedToRecieveICMP => "Rx fail",
-            rradio_messages::PingError::Timeout => "No reply",
-            rradio_messages::PingError::DestinationUnreachable => "Unreachable",
-        }
-    )
+    write!(f, "{}: {}", prefix, error)
 }
 
 #[derive(PartialEq)]
-struct PingDisplay(rradio_messages::PingTimes);
+struct PingDisplay {
+    ping_times: rradio_messages::PingTimes,
+    consecutive_dns_errors: usize,
+}
+
+impl PingDisplay {
+    fn error(&self, error: rradio_messages::PingError) -> PingErrorDisplay {
+        PingErrorDisplay {
+            error,
+            consecutive_dns_errors: self.consecutive_dns_errors,
+        }
+    }
+}
 
 impl fmt::Display for PingDisplay {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self.0 {
+        match self.ping_times {
             rradio_messages::PingTimes::None => f.write_str("No Ping Times"),
             rradio_messages::PingTimes::BadUrl => f.write_str("Bad URL"),
             rradio_messages::PingTimes::Gateway(Ok(gateway_ping)) => {
                 display_ping_duration(f, "Gateway", gateway_ping)
             }
             rradio_messages::PingTimes::Gateway(Err(gateway_error)) => {
-                display_ping_error(f, "Local", gateway_error)
+                display_ping_error(f, "Local", self.error(gateway_error))
             }
             rradio_messages::PingTimes::GatewayAndRemote {
                 gateway_ping,
@@ -519,7 +1042,7 @@ impl fmt::Display for PingDisplay {
                 gateway_ping: _,
                 remote_ping: Err(remote_error),
                 latest: rradio_messages::PingTarget::Remote,
-            } => display_ping_error(f, "Remote", remote_error),
+            } => display_ping_error(f, "Remote", self.error(remote_error)),
             rradio_messages::PingTimes::FinishedPingingRemote { gateway_ping } => {
                 display_ping_duration(f, "Gateway", gateway_ping)
             }
@@ -528,92 +1051,1911 @@ impl fmt::Display for PingDisplay {
 }
 
 #[derive(PartialEq, Eq)]
-struct DateFormatter(chrono::NaiveDate);
+struct DateFormatter {
+    date: chrono::NaiveDate,
+    format: ArcStr,
+    locale: Locale,
+}
 
 impl fmt::Display for DateFormatter {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.0.format("%a %d %b %Y").fmt(f)
+        f.write_str(&locale::format_date(self.date, &self.format, self.locale)?)
     }
 }
 
-#[derive(PartialEq, Eq)]
-struct TimeFormatter(chrono::NaiveTime);
+/// A date format which always fits on a line
+const COMPACT_DATE_FORMAT: &str = "%Y-%m-%d";
 
-impl fmt::Display for TimeFormatter {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.0.format("%R").fmt(f)
+/// Returns `format` if it's valid and every day of the year fits on a line when formatted with it, otherwise returns [COMPACT_DATE_FORMAT]
+fn validate_date_format(format: &str, locale: Locale) -> &str {
+    let first_day_of_leap_year = chrono::NaiveDate::from_ymd(2000, 1, 1);
+
+    let fits_on_line = (0..366).all(|day| {
+        let date = first_day_of_leap_year + chrono::Duration::days(day);
+
+        match locale::format_date(date, format, locale) {
+            Ok(text) => text.chars().count() <= usize::from(crate::SCREEN_WIDTH),
+            Err(fmt::Error) => false,
+        }
+    });
+
+    if fits_on_line {
+        format
+    } else {
+        COMPACT_DATE_FORMAT
     }
 }
 
-fn no_station(ip_address: impl AsRef<str>) -> impl Widget<Data = PlayerState> {
-    let (station_not_found_segment, volume_and_pipeline_state_segment) = Line(0).split(13);
-
-    let local_ip = FixedLabel::new(ip_address, station_not_found_segment);
+/// The width of the clock at the start of the bottom line of the idle screen
+const CLOCK_WIDTH: u8 = 5;
 
-    let station_not_found =
-        EitherWidget::new(ScrollingLabel::new(station_not_found_segment), local_ip).with_lens(
-            |state: &PlayerState| state.station_not_found.clone().map(StationNotFoundMessage),
-        );
+/// A time format which always fits in the clock
+const DEFAULT_TIME_FORMAT: &str = "%R";
 
-    let volume_and_pipeline_state =
-        volume_and_pipeline_state_view(volume_and_pipeline_state_segment)
-            .with_lens(|state: &PlayerState| (state.volume, state.pipeline_state));
+/// Returns `format` if it's valid and every minute of the day fits in the clock when formatted with it, otherwise returns [DEFAULT_TIME_FORMAT]
+fn validate_time_format(format: &str) -> &str {
+    let fits_in_clock = (0..24 * 60).all(|minute| {
+        let time = chrono::NaiveTime::from_hms(minute / 60, minute % 60, 0);
 
-    let ping =
-        Label::new(Line(1)).with_lens(|state: &PlayerState| PingDisplay(state.ping_times.clone()));
+        let mut text = String::new();
 
-    let clock_date = GeneratedLabel::new(Line(2), || {
-        DateFormatter(chrono::Local::now().naive_local().date())
+        write!(text, "{}", time.format(format)).is_ok()
+            && text.chars().count() <= usize::from(CLOCK_WIDTH)
     });
 
-    let (clock_time_segment, _cpu_temperature_segment) = Line(3).split(5);
+    if fits_in_clock {
+        format
+    } else {
+        DEFAULT_TIME_FORMAT
+    }
+}
 
-    let clock_time = GeneratedLabel::new(clock_time_segment, || {
-        TimeFormatter(chrono::Local::now().time())
-    });
+#[derive(PartialEq, Eq)]
+struct TimeFormatter {
+    time: chrono::NaiveTime,
+    format: ArcStr,
+}
 
-    station_not_found
-        .group(volume_and_pipeline_state)
-        .group(ping)
-        .group(clock_date)
-        .group(clock_time)
+impl fmt::Display for TimeFormatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.time.format(&self.format).fmt(f)
+    }
 }
 
-pub fn app(ip_address: impl AsRef<str>) -> impl Widget<Data = PlayerState> {
-    let new_station_tics = 2_usize;
+/// Information which is rotated through on the spare space of the idle screen
+#[derive(PartialEq)]
+enum IdleInfo {
+    Temperature(TemperatureDisplay),
+    Uptime(UptimeDisplay),
+}
 
-    let new_station_index = Label::new(Line(0))
-        .with_lens(|station: &Arc<Station>| station.index.clone().unwrap_or_default());
+impl fmt::Display for IdleInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdleInfo::Temperature(temperature) => write!(f, "CPU Temp {:>3}", temperature),
+            IdleInfo::Uptime(uptime) => uptime.fmt(f),
+        }
+    }
+}
 
-    let new_station_title = ScrollingLabel::new(Line(1))
-        .with_lens(|station: &Arc<Station>| station.title.clone().unwrap_or_default());
-
-    let station_view =
-        EitherWidget::new(new_station_index.group(new_station_title), station_view()).with_scope(
-            FunctionScope::new(
-                new_station_tics,
-                |tics_remaining, event, _| match event {
-                    WidgetEvent::Tick(_) => *tics_remaining = tics_remaining.saturating_sub(1),
-                },
-                move |tics_remaining, (old_station, _), (station, _)| {
-                    if !Arc::ptr_eq(old_station, station) {
-                        *tics_remaining = new_station_tics;
-                    }
-                },
-                |&tics_remaining, (station, state): &(Arc<Station>, PlayerState)| {
-                    if tics_remaining > 0 {
-                        Either::A(station.clone())
-                    } else {
-                        Either::B((station.clone(), state.clone()))
-                    }
-                },
-            ),
-        );
+/// Displays how long the screen driver has been running, to the nearest minute, e.g. "up 3h07m"
+#[derive(PartialEq)]
+struct UptimeDisplay(Duration);
 
-    EitherWidget::new(station_view, no_station(ip_address)).with_lens(|state: &PlayerState| {
-        match &state.current_station {
-            Some(station) => Either::A((station.clone(), state.clone())),
-            None => Either::B(state.clone()),
+impl fmt::Display for UptimeDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let minutes = self.0.as_secs() / 60;
+        let hours = minutes / 60;
+        let days = hours / 24;
+
+        if days > 0 {
+            write!(f, "up {}d{:02}h", days, hours % 24)
+        } else if hours > 0 {
+            write!(f, "up {}h{:02}m", hours, minutes % 60)
+        } else {
+            write!(f, "up {}m", minutes)
         }
-    })
+    }
+}
+
+/// Which parts of the idle screen, which is shown when no station is playing, are shown. Parts which aren't shown are left blank
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdleScreenOptions {
+    /// The ping times, on the second line
+    pub ping: bool,
+    /// The date, on the third line
+    pub date: bool,
+    /// The time, at the start of the bottom line
+    pub clock: bool,
+    /// The CPU temperature, which takes turns with the uptime at the end of the bottom line
+    pub temperature: bool,
+}
+
+impl Default for IdleScreenOptions {
+    fn default() -> Self {
+        Self {
+            ping: true,
+            date: true,
+            clock: true,
+            temperature: true,
+        }
+    }
+}
+
+/// The idle screen, which is shown when no station is playing
+pub fn no_station(
+    config: &Config,
+    options: IdleScreenOptions,
+    clock: Arc<dyn Clock>,
+    ip_address: impl AsRef<str>,
+    start_time: Instant,
+) -> impl Widget<Data = Arc<PlayerState>> {
+    let (station_not_found_segment, volume_and_pipeline_state_segment) = Line(0).split(13);
+
+    let local_ip = FixedLabel::new(ip_address, station_not_found_segment);
+
+    let station_not_found = EitherWidget::new(
+        ScrollingLabel::new(station_not_found_segment)
+            .with_scroll_mode(ScrollMode::PingPong)
+            .align_right(),
+        local_ip,
+    )
+    .with_lens(|state: &Arc<PlayerState>| {
+        state.station_not_found.clone().map(StationNotFoundMessage)
+    });
+
+    let volume_and_pipeline_state =
+        volume_and_pipeline_state_view(config, volume_and_pipeline_state_segment)
+            .with_lens(|state: &Arc<PlayerState>| (state.volume, state.pipeline_state));
+
+    let ping = options.ping.then(|| {
+        Label::new(Line(1)).with_lens(|state: &Arc<PlayerState>| PingDisplay {
+            ping_times: state.ping_times.clone(),
+            consecutive_dns_errors: state.consecutive_dns_errors,
+        })
+    });
+
+    let locale = config.locale;
+    let date_format = ArcStr::from(validate_date_format(&config.date_format, locale));
+
+    let date_clock = clock.clone();
+
+    let clock_date = options.date.then(|| {
+        GeneratedLabel::new(Line(2), move || DateFormatter {
+            date: date_clock.now().naive_local().date(),
+            format: date_format.clone(),
+            locale,
+        })
+    });
+
+    let (clock_time_segment, idle_info_segment) = Line(3).split(CLOCK_WIDTH);
+    let (_, idle_info_segment) = idle_info_segment.split(2);
+
+    let time_format = ArcStr::from(validate_time_format(&config.time_format));
+
+    let clock_time = options.clock.then(|| {
+        GeneratedLabel::new(clock_time_segment, move || TimeFormatter {
+            time: clock.now().time(),
+            format: time_format.clone(),
+        })
+    });
+
+    let idle_info_ticks = 5;
+
+    let show_uptime = config.show_uptime;
+    let show_temperature = options.temperature;
+    let temperature_unit = config.temperature_unit;
+
+    let idle_info = Rotating::new(idle_info_segment, idle_info_ticks)
+        .with_item(move |state: &Arc<PlayerState>| {
+            // The temperature is only measured when ping times are received
+            state
+                .temperature
+                .filter(|_| show_temperature)
+                .map(|temperature| {
+                    IdleInfo::Temperature(TemperatureDisplay {
+                        temperature,
+                        unit: temperature_unit,
+                    })
+                })
+        })
+        .with_item(move |_: &Arc<PlayerState>| {
+            show_uptime.then(|| IdleInfo::Uptime(UptimeDisplay(start_time.elapsed())))
+        });
+
+    (
+        station_not_found,
+        volume_and_pipeline_state,
+        ping,
+        clock_date,
+        clock_time,
+        idle_info,
+    )
+}
+
+/// The view of the application. `start_time` is when the screen driver started, and is used to show the uptime
+pub fn app(
+    config: &Config,
+    ip_address: impl AsRef<str>,
+    start_time: Instant,
+) -> impl Widget<Data = Arc<PlayerState>> {
+    ViewBuilder::new(config).build(ip_address, start_time)
+}
+
+/// Builds the view of the application, with parts of the idle screen optionally hidden.
+/// By default, the view is the same as [app]
+pub struct ViewBuilder<'a> {
+    config: &'a Config,
+    idle_screen: IdleScreenOptions,
+    clock: Arc<dyn Clock>,
+}
+
+impl<'a> ViewBuilder<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        Self {
+            config,
+            idle_screen: IdleScreenOptions::default(),
+            clock: Arc::new(LocalClock),
+        }
+    }
+
+    /// The clock which the idle screen shows, which is the system clock by default
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Which parts of the idle screen are shown, which is all of them by default
+    pub fn with_idle_screen(mut self, idle_screen: IdleScreenOptions) -> Self {
+        self.idle_screen = idle_screen;
+        self
+    }
+
+    pub fn show_ping(mut self, show: bool) -> Self {
+        self.idle_screen.ping = show;
+        self
+    }
+
+    pub fn show_date(mut self, show: bool) -> Self {
+        self.idle_screen.date = show;
+        self
+    }
+
+    pub fn show_clock(mut self, show: bool) -> Self {
+        self.idle_screen.clock = show;
+        self
+    }
+
+    pub fn show_temperature(mut self, show: bool) -> Self {
+        self.idle_screen.temperature = show;
+        self
+    }
+
+    /// Build the view. `start_time` is when the screen driver started, and is used to show the uptime
+    pub fn build(
+        self,
+        ip_address: impl AsRef<str>,
+        start_time: Instant,
+    ) -> impl Widget<Data = Arc<PlayerState>> {
+        view(
+            self.config,
+            self.idle_screen,
+            self.clock,
+            ip_address,
+            start_time,
+        )
+    }
+}
+
+fn view(
+    config: &Config,
+    idle_screen: IdleScreenOptions,
+    clock: Arc<dyn Clock>,
+    ip_address: impl AsRef<str>,
+    start_time: Instant,
+) -> impl Widget<Data = Arc<PlayerState>> {
+    let new_station_tics = 2_usize;
+
+    // The splash, and then the station view, each wipe onto the screen from the top down over this many ticks
+    let transition_tics = 2_u8;
+    let rows_per_tic = SCREEN_HEIGHT / transition_tics;
+    let revealed_rows = move |tics: usize| {
+        if tics + 1 < usize::from(transition_tics) {
+            (tics as u8 + 1) * rows_per_tic
+        } else {
+            SCREEN_HEIGHT
+        }
+    };
+    let final_tics = new_station_tics + usize::from(transition_tics);
+
+    let new_station_index = Label::new(Line(0))
+        .or_blank(Line(0))
+        .with_lens(|station: &Arc<Station>| station.index.clone());
+
+    let new_station_title = ScrollingLabel::new(Line(1))
+        .or_blank(Line(1))
+        .with_lens(|station: &Arc<Station>| station.title.clone());
+
+    // The lines below the splash are blanked rather than the screen being cleared, which would flash
+    let splash = WipeTransition((
+        new_station_index,
+        new_station_title,
+        FixedLabel::new("", Line(2)),
+        FixedLabel::new("", Line(3)),
+    ));
+
+    let station_view = EitherWidget::new(splash, WipeTransition(station_view(config))).with_scope(
+        FunctionScope::new(
+            0_usize,
+            move |tics_since_change, event, _| match event {
+                WidgetEvent::Tick(..) => {
+                    *tics_since_change = (*tics_since_change + 1).min(final_tics)
+                }
+            },
+            |tics_since_change, (old_station, _), (station, _)| {
+                if !Arc::ptr_eq(old_station, station) {
+                    *tics_since_change = 0;
+                }
+            },
+            move |&tics_since_change, (station, state): &(Arc<Station>, Arc<PlayerState>)| {
+                if tics_since_change < new_station_tics {
+                    Either::A((revealed_rows(tics_since_change), station.clone()))
+                } else {
+                    Either::B((
+                        revealed_rows(tics_since_change - new_station_tics),
+                        (station.clone(), state.clone()),
+                    ))
+                }
+            },
+        ),
+    );
+
+    // The state is shared, so choosing between views clones an Arc rather than the whole state, which happens every tick
+    let player_view = EitherWidget::new(
+        station_view,
+        no_station(config, idle_screen, clock, ip_address, start_time),
+    )
+    .with_lens(|state: &Arc<PlayerState>| match &state.current_station {
+        Some(station) => Either::A((station.clone(), state.clone())),
+        None => Either::B(state.clone()),
+    });
+
+    let main_view =
+        EitherWidget::new(error_view(), player_view).with_lens(|state: &Arc<PlayerState>| {
+            match &state.current_error {
+                Some(error) => Either::A(error.clone()),
+                None => Either::B(state.clone()),
+            }
+        });
+
+    let main_view = VolumeCursor {
+        view: main_view,
+        style: config.volume_cursor,
+        show_tics: duration_in_tics(config.force_show_volume_duration, config.tick_interval),
+        tics_remaining: 0,
+        current_style: CursorStyle::Off,
+        painted_style: None,
+    };
+
+    let main_view = FreshnessIndicator {
+        view: main_view,
+        // The position comes from the config file, so might be off the screen
+        indicator: config.freshness_indicator.and_then(|position| {
+            match Segment::new(position, 1) {
+                Ok(segment) => Some(Blinker::new(FRESHNESS_INDICATOR_GLYPHS, segment)),
+                Err(err) => {
+                    log::warn!("Not showing the freshness indicator: {:#}", err);
+                    None
+                }
+            }
+        }),
+    };
+
+    StalenessIndicator {
+        view: main_view,
+        stale_after: config.stale_after,
+        time_since_last_rradio_event: Duration::ZERO,
+        indicator_needs_painting: false,
+    }
+}
+
+/// The last digit of the volume, in the top right corner
+const VOLUME_CURSOR_POSITION: CursorPosition = CursorPosition {
+    row: 0,
+    column: crate::SCREEN_WIDTH - 1,
+};
+
+/// Shows the hardware cursor under the volume while the volume is forced to be shown after changing
+struct VolumeCursor<W> {
+    view: W,
+    style: CursorStyle,
+    show_tics: usize,
+    tics_remaining: usize,
+    /// The style which the cursor should have, as of the last event or update
+    current_style: CursorStyle,
+    /// The style which the cursor was last painted with, or `None` if it needs painting
+    painted_style: Option<CursorStyle>,
+}
+
+impl<W> VolumeCursor<W> {
+    fn cursor_style(&self, state: &PlayerState) -> CursorStyle {
+        // The error banner hides the volume
+        if self.tics_remaining > 0 && state.current_error.is_none() {
+            self.style
+        } else {
+            CursorStyle::Off
+        }
+    }
+}
+
+impl<W: Widget<Data = Arc<PlayerState>>> Widget for VolumeCursor<W> {
+    type Data = Arc<PlayerState>;
+
+    fn event(&mut self, event: &WidgetEvent, state: &Arc<PlayerState>) {
+        match event {
+            WidgetEvent::Tick(..) => self.tics_remaining = self.tics_remaining.saturating_sub(1),
+        }
+
+        self.current_style = self.cursor_style(state);
+
+        self.view.event(event, state)
+    }
+
+    fn update(&mut self, old_state: &Arc<PlayerState>, state: &Arc<PlayerState>) {
+        if old_state.volume != state.volume {
+            self.tics_remaining = self.show_tics;
+        }
+
+        self.current_style = self.cursor_style(state);
+
+        self.view.update(old_state, state);
+    }
+
+    fn force_repaint(&mut self, state: &Arc<PlayerState>) {
+        self.painted_style = None;
+
+        self.view.force_repaint(state)
+    }
+
+    fn paint(&mut self, state: &Arc<PlayerState>, display: &mut impl TextDisplay) {
+        self.view.paint(state, display);
+
+        let style = self.cursor_style(state);
+
+        display.set_cursor(VOLUME_CURSOR_POSITION, style);
+
+        self.current_style = style;
+        self.painted_style = Some(style);
+    }
+
+    fn dirty(&self) -> bool {
+        self.view.dirty() || self.painted_style != Some(self.current_style)
+    }
+
+    fn blank(&self, display: &mut impl TextDisplay) {
+        self.view.blank(display)
+    }
+}
+
+/// Shown in the top right corner when nothing has been received from rradio for a while
+const STALENESS_INDICATOR: char = '?';
+
+/// A dot which bounces between the bottom and top of the character with each event from rradio
+const FRESHNESS_INDICATOR_GLYPHS: [char; 2] = ['.', '\''];
+
+/// Paints a [Blinker] over `view` which alternates with each event from rradio, if the indicator is configured
+struct FreshnessIndicator<W> {
+    view: W,
+    indicator: Option<Blinker>,
+}
+
+impl<W: Widget<Data = Arc<PlayerState>>> Widget for FreshnessIndicator<W> {
+    type Data = Arc<PlayerState>;
+
+    fn event(&mut self, event: &WidgetEvent, state: &Arc<PlayerState>) {
+        self.view.event(event, state)
+    }
+
+    fn update(&mut self, old_state: &Arc<PlayerState>, state: &Arc<PlayerState>) {
+        self.view.update(old_state, state);
+        self.indicator
+            .update(&old_state.rradio_event_count, &state.rradio_event_count);
+    }
+
+    fn force_repaint(&mut self, state: &Arc<PlayerState>) {
+        self.view.force_repaint(state);
+        self.indicator.force_repaint(&state.rradio_event_count);
+    }
+
+    fn paint(&mut self, state: &Arc<PlayerState>, display: &mut impl TextDisplay) {
+        // The indicator is repainted whenever the view paints, as the view might have painted over it
+        if self.view.dirty() {
+            self.indicator.force_repaint(&state.rradio_event_count);
+        }
+
+        self.view.paint(state, display);
+        self.indicator.paint(&state.rradio_event_count, display);
+    }
+
+    fn dirty(&self) -> bool {
+        self.view.dirty() || self.indicator.dirty()
+    }
+
+    fn blank(&self, display: &mut impl TextDisplay) {
+        self.view.blank(display);
+        self.indicator.blank(display);
+    }
+}
+
+/// Paints [STALENESS_INDICATOR] over `view` when rradio has stopped sending updates, so that stale information isn't silently shown
+struct StalenessIndicator<W> {
+    view: W,
+    stale_after: Duration,
+    /// The time since anything was last received from rradio, measured from the time between ticks
+    time_since_last_rradio_event: Duration,
+    /// True if the indicator has become stale, or the view has been forced to repaint, since the indicator was last painted
+    indicator_needs_painting: bool,
+}
+
+impl<W> StalenessIndicator<W> {
+    fn is_stale(&self) -> bool {
+        self.time_since_last_rradio_event >= self.stale_after
+    }
+}
+
+impl<W: Widget<Data = Arc<PlayerState>>> Widget for StalenessIndicator<W> {
+    type Data = Arc<PlayerState>;
+
+    fn event(&mut self, event: &WidgetEvent, state: &Arc<PlayerState>) {
+        match event {
+            WidgetEvent::Tick(_, elapsed) => {
+                let was_stale = self.is_stale();
+
+                self.time_since_last_rradio_event += *elapsed;
+
+                if !was_stale && self.is_stale() {
+                    self.indicator_needs_painting = true;
+                }
+            }
+        }
+
+        self.view.event(event, state)
+    }
+
+    fn update(&mut self, old_state: &Arc<PlayerState>, state: &Arc<PlayerState>) {
+        self.view.update(old_state, state);
+
+        if old_state.rradio_event_count != state.rradio_event_count {
+            if self.is_stale() {
+                // Remove the indicator by repainting whatever it was painted over
+                self.view.force_repaint(state);
+            }
+
+            self.time_since_last_rradio_event = Duration::ZERO;
+        }
+    }
+
+    fn force_repaint(&mut self, state: &Arc<PlayerState>) {
+        self.indicator_needs_painting = true;
+
+        self.view.force_repaint(state)
+    }
+
+    fn paint(&mut self, state: &Arc<PlayerState>, display: &mut impl TextDisplay) {
+        self.view.paint(state, display);
+
+        self.indicator_needs_painting = false;
+
+        // The indicator is painted every time as the view might have painted over it
+        if self.is_stale() {
+            display.write_to(
+                Segment {
+                    position: CursorPosition {
+                        row: 0,
+                        column: crate::SCREEN_WIDTH - 1,
+                    },
+                    length: 1,
+                },
+                STALENESS_INDICATOR,
+            );
+        }
+    }
+
+    fn dirty(&self) -> bool {
+        self.view.dirty() || self.indicator_needs_painting
+    }
+
+    fn blank(&self, display: &mut impl TextDisplay) {
+        self.view.blank(display)
+    }
+}
+
+/// A banner which temporarily replaces the screen when rradio reports an error
+fn error_view() -> impl Widget<Data = ArcStr> {
+    FixedLabel::new("rradio error:", Line(0)).group(ScrollingLabel::new(Lines(1, 3)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        display::{CursorPosition, TextDisplay},
+        SCREEN_HEIGHT, SCREEN_WIDTH,
+    };
+
+    fn tick() -> WidgetEvent {
+        tick_after(Duration::ZERO)
+    }
+
+    /// A tick which comes `elapsed` after the previous tick
+    fn tick_after(elapsed: Duration) -> WidgetEvent {
+        WidgetEvent::Tick(Duration::ZERO, elapsed)
+    }
+
+    /// Records the segments which are written to, so that the layout can be checked
+    #[derive(Default)]
+    struct LayoutRecorder {
+        segments: Vec<Segment>,
+    }
+
+    impl TextDisplay for LayoutRecorder {
+        fn clear(&mut self) {}
+
+        fn write_to(&mut self, segment: impl Into<Segment>, _item: impl fmt::Display) {
+            self.segments.push(segment.into());
+        }
+    }
+
+    /// Assert that each segment lies within the screen, and that no two segments overlap
+    fn assert_valid_layout(segments: &[Segment]) {
+        let cells = |segment: &Segment| {
+            let start = usize::from(segment.position.row) * usize::from(SCREEN_WIDTH)
+                + usize::from(segment.position.column);
+            start..(start + usize::from(segment.length))
+        };
+
+        for segment in segments {
+            assert!(
+                segment.position.column < SCREEN_WIDTH,
+                "{:?} starts off the screen",
+                segment
+            );
+            assert!(
+                cells(segment).end <= usize::from(SCREEN_WIDTH) * usize::from(SCREEN_HEIGHT),
+                "{:?} ends off the screen",
+                segment
+            );
+        }
+
+        for (index, a) in segments.iter().enumerate() {
+            for b in &segments[(index + 1)..] {
+                let (a_cells, b_cells) = (cells(a), cells(b));
+                assert!(
+                    a_cells.end <= b_cells.start || b_cells.end <= a_cells.start,
+                    "{:?} overlaps {:?}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    fn station(source_type: rradio_messages::StationType) -> Arc<Station> {
+        let track = |title: &str| rradio_messages::Track {
+            title: Some(ArcStr::from(title)),
+            album: Some(ArcStr::from("Album")),
+            artist: Some(ArcStr::from("Artist")),
+            url: ArcStr::from("http://example.com/track"),
+            is_notification: false,
+        };
+
+        Arc::new(Station {
+            index: Some(ArcStr::from("12")),
+            source_type,
+            title: Some(ArcStr::from("Station Title")),
+            tracks: vec![
+                track("A Title"),
+                track("A Long Track Title Which Needs Scrolling"),
+            ],
+        })
+    }
+
+    /// Paint the entire application view for `state`, once the new station splash has passed, and return the segments which were painted
+    fn painted_segments(state: &PlayerState) -> Vec<Segment> {
+        painted_segments_with_config(&Config::default(), state)
+    }
+
+    fn painted_segments_with_config(config: &Config, state: &PlayerState) -> Vec<Segment> {
+        let mut view = app(config, "192.168.0.1", Instant::now());
+        let initial_state = Arc::new(PlayerState::default());
+        let state = &Arc::new(state.clone());
+
+        view.update(&initial_state, state);
+        view.force_repaint(state);
+
+        for _ in 0..3 {
+            view.event(&tick(), state);
+        }
+
+        view.force_repaint(state);
+
+        let mut recorder = LayoutRecorder::default();
+        view.paint(state, &mut recorder);
+        recorder.segments
+    }
+
+    #[test]
+    fn layout_fits_screen() {
+        use rradio_messages::StationType;
+
+        let no_station = PlayerState::default();
+
+        let error = PlayerState::default().handle_log_message(pipeline_error());
+
+        let station_not_found = PlayerState {
+            station_not_found: Some(ArcStr::from("42")),
+            ..PlayerState::default()
+        };
+
+        let playing = |source_type, current_track_index| PlayerState {
+            current_station: Some(station(source_type)),
+            current_track_index,
+            pipeline_state: PipelineState::Playing,
+            volume: 100,
+            buffering: 50,
+            track_position: Some(Duration::from_secs(125)),
+            track_duration: Some(Duration::from_secs(3600)),
+            ..PlayerState::default()
+        };
+
+        let stations = [
+            StationType::UrlList,
+            StationType::Samba,
+            StationType::CD,
+            StationType::Usb,
+        ]
+        .iter()
+        .flat_map(|&source_type| {
+            (0..2).flat_map(move |current_track_index| {
+                let playing = playing(source_type, current_track_index);
+
+                vec![
+                    PlayerState {
+                        pipeline_state: PipelineState::Paused,
+                        ..playing.clone()
+                    },
+                    PlayerState {
+                        buffering: 0,
+                        track_position: None,
+                        track_duration: None,
+                        ..playing.clone()
+                    },
+                    playing,
+                ]
+            })
+        })
+        .collect::<Vec<_>>();
+
+        let configs = [
+            TrackPositionStyle::TrackNumberAndSeconds,
+            TrackPositionStyle::TrackNumberAndMinutes,
+            TrackPositionStyle::ElapsedAndRemaining,
+        ]
+        .iter()
+        .flat_map(|&track_position_style| {
+            [VolumeStyle::Raw, VolumeStyle::Percentage]
+                .iter()
+                .flat_map(move |&volume_style| {
+                    [false, true]
+                        .iter()
+                        .map(move |&full_width_progress_bar| Config {
+                            track_position_style,
+                            volume_style,
+                            full_width_progress_bar,
+                            show_buffering_trend: full_width_progress_bar,
+                            show_paint_time: full_width_progress_bar,
+                            ..Config::default()
+                        })
+                })
+        });
+
+        for config in configs {
+            for state in [no_station.clone(), station_not_found.clone(), error.clone()]
+                .iter()
+                .chain(&stations)
+            {
+                let segments = painted_segments_with_config(&config, state);
+                assert!(!segments.is_empty());
+                assert_valid_layout(&segments);
+            }
+        }
+    }
+
+    fn pipeline_error() -> rradio_messages::LogMessage {
+        rradio_messages::LogMessage::Error(rradio_messages::Error::PipelineError(ArcStr::from(
+            "Stream not found",
+        )))
+    }
+
+    #[test]
+    fn error_banner_is_shown_then_cleared() {
+        let mut state = PlayerState::default().handle_log_message(pipeline_error());
+
+        assert!(painted_segments(&state).contains(&Lines(1, 3).into()));
+
+        for _ in 0..4 {
+            state = state.with_error_tick();
+            assert!(state.current_error.is_some());
+        }
+
+        state = state.with_error_tick();
+        assert!(state.current_error.is_none());
+        assert!(!painted_segments(&state).contains(&Lines(1, 3).into()));
+    }
+
+    #[test]
+    fn date_format_falls_back_if_too_long() {
+        let english = Locale::English;
+
+        assert_eq!(validate_date_format("%a %d %b %Y", english), "%a %d %b %Y");
+        assert_eq!(validate_date_format("%m/%d/%Y", english), "%m/%d/%Y");
+
+        // "Wednesday 27 September 2000" is too long
+        assert_eq!(
+            validate_date_format("%A %d %B %Y", english),
+            COMPACT_DATE_FORMAT
+        );
+
+        // "Donnerstag 27.01.2000" is too long, but "Wednesday 26.01.2000" isn't
+        assert_eq!(validate_date_format("%A %d.%m.%Y", english), "%A %d.%m.%Y");
+        assert_eq!(
+            validate_date_format("%A %d.%m.%Y", Locale::German),
+            COMPACT_DATE_FORMAT
+        );
+
+        // Invalid format
+        assert_eq!(validate_date_format("%Q", english), COMPACT_DATE_FORMAT);
+    }
+
+    #[test]
+    fn time_format_falls_back_if_too_long() {
+        assert_eq!(validate_time_format("%l:%M"), "%l:%M");
+        assert_eq!(validate_time_format("%H%M"), "%H%M");
+
+        // "11:59 PM" is too long
+        assert_eq!(validate_time_format("%I:%M %p"), DEFAULT_TIME_FORMAT);
+
+        // Invalid format
+        assert_eq!(validate_time_format("%Q"), DEFAULT_TIME_FORMAT);
+    }
+
+    #[test]
+    fn staleness_indicator_is_shown_then_removed() {
+        use crate::display::{RecordingDisplay, WrappingTextDisplay};
+
+        let config = Config::default();
+        let mut view = app(&config, "192.168.0.1", Instant::now());
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        let fresh_state = Arc::new(PlayerState::default());
+
+        view.force_repaint(&fresh_state);
+        view.paint(&fresh_state, &mut display);
+
+        let last_column = |display: &WrappingTextDisplay<RecordingDisplay>| {
+            display.character_display().snapshot()[0]
+                .chars()
+                .last()
+                .unwrap()
+        };
+
+        assert_ne!(last_column(&display), STALENESS_INDICATOR);
+
+        view.event(&tick_after(config.stale_after), &fresh_state);
+        view.paint(&fresh_state, &mut display);
+
+        assert_eq!(last_column(&display), STALENESS_INDICATOR);
+
+        let updated_state = Arc::new(fresh_state.as_ref().clone().with_rradio_event());
+
+        view.update(&fresh_state, &updated_state);
+        view.paint(&updated_state, &mut display);
+
+        assert_ne!(last_column(&display), STALENESS_INDICATOR);
+    }
+
+    #[test]
+    fn scrolling_follows_scroll_interval() {
+        use crate::display::{RecordingDisplay, WrappingTextDisplay};
+
+        let tags_after_ticks = |scroll_interval: Duration, ticks: usize| {
+            // Without a pause, which lasts the same time whatever the interval, scrolling only depends on the interval
+            let config = Config {
+                scroll_interval,
+                scroll_pause: Duration::ZERO,
+                ..Config::default()
+            };
+
+            let mut view = station_view(&config);
+            let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+            let station = station(rradio_messages::StationType::Samba);
+            let data = (
+                station.clone(),
+                Arc::new(PlayerState {
+                    current_station: Some(station),
+                    ..PlayerState::default()
+                }),
+            );
+
+            view.force_repaint(&data);
+
+            for _ in 0..ticks {
+                view.event(&tick(), &data);
+            }
+
+            view.paint(&data, &mut display);
+
+            display.character_display().snapshot()[1].clone()
+        };
+
+        let second = Duration::from_secs(1);
+
+        assert_ne!(tags_after_ticks(second, 3), tags_after_ticks(second, 0));
+
+        // Scrolling every two ticks takes twice as many ticks to scroll as far
+        assert_eq!(tags_after_ticks(2 * second, 6), tags_after_ticks(second, 3));
+        assert_eq!(tags_after_ticks(2 * second, 3), tags_after_ticks(second, 1));
+    }
+
+    #[test]
+    fn full_width_progress_bar() {
+        use crate::display::{RecordingDisplay, WrappingTextDisplay};
+
+        let config = Config {
+            full_width_progress_bar: true,
+            ..Config::default()
+        };
+
+        let bottom_line = |track_duration: Option<Duration>| {
+            let mut view = station_view(&config);
+            let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+            let state = PlayerState {
+                current_station: Some(station(rradio_messages::StationType::UrlList)),
+                pipeline_state: PipelineState::Playing,
+                track_position: Some(Duration::from_secs(20)),
+                track_duration,
+                ..PlayerState::default()
+            };
+
+            let data = (state.current_station.clone().unwrap(), Arc::new(state));
+
+            view.force_repaint(&data);
+            view.event(&tick_after(Duration::from_secs(30)), &data);
+            view.paint(&data, &mut display);
+
+            display.character_display().snapshot()[3].clone()
+        };
+
+        // Half way through, including the time played since the position was reported, across the whole line
+        assert_eq!(
+            bottom_line(Some(Duration::from_secs(100))),
+            "██████████          "
+        );
+
+        // Without a duration, the line is blank
+        assert_eq!(bottom_line(None), " ".repeat(20));
+    }
+
+    #[test]
+    fn full_width_progress_bar_only_restarts_from_reported_positions() {
+        use crate::display::{RecordingDisplay, WrappingTextDisplay};
+
+        let config = Config {
+            full_width_progress_bar: true,
+            ..Config::default()
+        };
+
+        let mut view = station_view(&config);
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        let station = station(rradio_messages::StationType::UrlList);
+
+        let state = Arc::new(PlayerState {
+            current_station: Some(station.clone()),
+            pipeline_state: PipelineState::Playing,
+            track_position: Some(Duration::from_secs(20)),
+            track_duration: Some(Duration::from_secs(100)),
+            ..PlayerState::default()
+        });
+        let data = (station.clone(), state.clone());
+
+        view.force_repaint(&data);
+        view.event(&tick_after(Duration::from_secs(30)), &data);
+        view.paint(&data, &mut display);
+
+        let bottom_line = |display: &WrappingTextDisplay<RecordingDisplay>| {
+            display.character_display().snapshot()[3].clone()
+        };
+
+        assert_eq!(bottom_line(&display), "██████████          ");
+
+        // An update which doesn't report the position, such as new ping times, leaves the bar where it was
+        let unrelated_update = (
+            station.clone(),
+            Arc::new(PlayerState {
+                volume: 50,
+                ..(*state).clone()
+            }),
+        );
+
+        view.update(&data, &unrelated_update);
+        view.paint(&unrelated_update, &mut display);
+
+        assert_eq!(bottom_line(&display), "██████████          ");
+
+        // A newly reported position restarts the interpolation from that position
+        let reported_position = (
+            station,
+            Arc::new(PlayerState {
+                track_position: Some(Duration::from_secs(60)),
+                ..(*unrelated_update.1).clone()
+            }),
+        );
+
+        view.update(&unrelated_update, &reported_position);
+        view.paint(&reported_position, &mut display);
+
+        assert_eq!(bottom_line(&display), "████████████        ");
+    }
+
+    #[test]
+    fn freshness_indicator_blinks_on_events() {
+        use crate::display::{RecordingDisplay, WrappingTextDisplay};
+
+        let config = Config {
+            freshness_indicator: Some(CursorPosition { row: 3, column: 19 }),
+            ..Config::default()
+        };
+        let mut view = app(&config, "192.168.0.1", Instant::now());
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        let indicator = |display: &WrappingTextDisplay<RecordingDisplay>| {
+            display.character_display().snapshot()[3]
+                .chars()
+                .last()
+                .unwrap()
+        };
+
+        let mut state = Arc::new(PlayerState::default());
+
+        view.force_repaint(&state);
+        view.paint(&state, &mut display);
+
+        let mut shown = vec![indicator(&display)];
+
+        for is_event in [true, false, true, true] {
+            let new_state = if is_event {
+                state.as_ref().clone().with_rradio_event()
+            } else {
+                // Ticks don't count as events
+                view.event(&tick_after(Duration::from_secs(1)), &state);
+                state.as_ref().clone()
+            };
+            let new_state = Arc::new(new_state);
+
+            view.update(&state, &new_state);
+            state = new_state;
+
+            view.paint(&state, &mut display);
+            shown.push(indicator(&display));
+        }
+
+        assert_eq!(shown, ['.', '\'', '\'', '.', '\'']);
+    }
+
+    #[test]
+    fn freshness_indicator_off_the_screen_isnt_shown() {
+        use crate::display::{RecordingDisplay, WrappingTextDisplay};
+
+        let config = Config {
+            freshness_indicator: Some(CursorPosition { row: 4, column: 0 }),
+            ..Config::default()
+        };
+        let mut view = app(&config, "192.168.0.1", Instant::now());
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        let state = Arc::new(PlayerState::default());
+
+        view.force_repaint(&state);
+        view.paint(&state, &mut display);
+
+        assert_eq!(display.character_display().snapshot().len(), 4);
+    }
+
+    #[test]
+    fn volume_is_shown_for_two_ticks_after_changing() {
+        let mut view = volume_and_pipeline_state_view(&Config::default(), Line(0));
+
+        let old_state = (50, PipelineState::Paused);
+        let state = (60, PipelineState::Paused);
+
+        assert_eq!(view.peek(&old_state), Either::B(PipelineState::Paused));
+
+        view.update(&old_state, &state);
+
+        assert_eq!(view.peek(&state), Either::A(60));
+
+        view.event(&tick(), &state);
+
+        assert_eq!(view.peek(&state), Either::A(60));
+
+        view.event(&tick(), &state);
+
+        assert_eq!(view.peek(&state), Either::B(PipelineState::Paused));
+
+        // While playing, the volume is always shown
+        assert_eq!(view.peek(&(60, PipelineState::Playing)), Either::A(60));
+    }
+
+    #[test]
+    fn durations_in_tics() {
+        let second = Duration::from_secs(1);
+        let quarter_second = Duration::from_millis(250);
+
+        assert_eq!(duration_in_tics(2 * second, second), 2);
+        assert_eq!(duration_in_tics(2 * second, quarter_second), 8);
+        assert_eq!(duration_in_tics(Duration::from_millis(1500), second), 2);
+        assert_eq!(duration_in_tics(Duration::ZERO, second), 0);
+    }
+
+    #[test]
+    fn force_show_volume_duration_is_independent_of_tick_rate() {
+        let config = Config {
+            tick_interval: Duration::from_millis(500),
+            ..Config::default()
+        };
+
+        let mut view = volume_and_pipeline_state_view(&config, Line(0));
+
+        let old_state = (50, PipelineState::Paused);
+        let state = (60, PipelineState::Paused);
+
+        view.update(&old_state, &state);
+
+        for _ in 0..3 {
+            view.event(&tick(), &state);
+            assert_eq!(view.peek(&state), Either::A(60));
+        }
+
+        view.event(&tick(), &state);
+        assert_eq!(view.peek(&state), Either::B(PipelineState::Paused));
+    }
+
+    #[test]
+    fn volume_stays_shown_when_stopping_during_sweep() {
+        let mut view = volume_and_pipeline_state_view(&Config::default(), Line(0));
+
+        let states = [
+            (50, PipelineState::Playing),
+            (55, PipelineState::Playing),
+            (60, PipelineState::Playing),
+            (60, PipelineState::Paused),
+            (65, PipelineState::Paused),
+        ];
+
+        for pair in states.windows(2) {
+            view.update(&pair[0], &pair[1]);
+            assert_eq!(view.peek(&pair[1]), Either::A(pair[1].0));
+        }
+
+        let state = (65, PipelineState::Paused);
+
+        view.event(&tick(), &state);
+        assert_eq!(view.peek(&state), Either::A(65));
+
+        view.event(&tick(), &state);
+        assert_eq!(view.peek(&state), Either::B(PipelineState::Paused));
+
+        // Changing volume and state at the same time shows the volume
+        let new_state = (70, PipelineState::Null);
+        view.update(&state, &new_state);
+        assert_eq!(view.peek(&new_state), Either::A(70));
+    }
+
+    #[test]
+    fn state_change_after_sweep_is_shown_immediately() {
+        let mut view = volume_and_pipeline_state_view(&Config::default(), Line(0));
+
+        let playing = (55, PipelineState::Playing);
+        let paused = (55, PipelineState::Paused);
+
+        view.update(&(50, PipelineState::Playing), &playing);
+        view.event(&tick(), &playing);
+
+        view.update(&playing, &paused);
+        assert_eq!(view.peek(&paused), Either::B(PipelineState::Paused));
+    }
+
+    #[test]
+    fn uptime() {
+        let uptime = |seconds| UptimeDisplay(Duration::from_secs(seconds)).to_string();
+
+        assert_eq!(uptime(59), "up 0m");
+        assert_eq!(uptime(12 * 60), "up 12m");
+        assert_eq!(uptime(3 * 3600 + 7 * 60 + 30), "up 3h07m");
+        assert_eq!(uptime(2 * 86400 + 3 * 3600), "up 2d03h");
+    }
+
+    #[test]
+    fn uptime_is_shown_on_idle_screen() {
+        use crate::display::{RecordingDisplay, WrappingTextDisplay};
+
+        let config = Config {
+            show_uptime: true,
+            ..Config::default()
+        };
+
+        let start_time = Instant::now() - Duration::from_secs(3 * 3600 + 7 * 60);
+
+        let mut view = app(&config, "192.168.0.1", start_time);
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        let state = Arc::new(PlayerState::default());
+
+        view.force_repaint(&state);
+        view.paint(&state, &mut display);
+
+        assert!(display.character_display().snapshot()[3].ends_with("up 3h07m     "));
+    }
+
+    #[test]
+    fn station_cleared_leaves_nothing_behind() {
+        use crate::display::{RecordingDisplay, WrappingTextDisplay};
+
+        let start_time = Instant::now();
+
+        let idle_state = Arc::new(PlayerState::default());
+        let station_state = Arc::new(PlayerState {
+            current_station: Some(station(rradio_messages::StationType::UrlList)),
+            pipeline_state: PipelineState::Playing,
+            volume: 50,
+            ..PlayerState::default()
+        });
+
+        let mut view = app(&Config::default(), "192.168.0.1", start_time);
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        view.force_repaint(&station_state);
+        for _ in 0..4 {
+            view.event(&tick(), &station_state);
+        }
+        view.paint(&station_state, &mut display);
+
+        view.update(&station_state, &idle_state);
+        view.paint(&idle_state, &mut display);
+
+        let mut idle_view = app(&Config::default(), "192.168.0.1", start_time);
+        let mut idle_display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        idle_view.force_repaint(&idle_state);
+        idle_view.paint(&idle_state, &mut idle_display);
+
+        assert_eq!(
+            display.character_display().snapshot(),
+            idle_display.character_display().snapshot()
+        );
+    }
+
+    #[test]
+    fn renderable_characters_are_shown_as_they_are() {
+        use crate::display::{RecordingDisplay, WrappingTextDisplay};
+
+        let mut view = app(&Config::default(), "192.168.0.1", Instant::now());
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        let mut station = station(rradio_messages::StationType::UrlList)
+            .as_ref()
+            .clone();
+        station.title = Some(ArcStr::from("Радио Ж"));
+
+        let state = Arc::new(
+            PlayerState::default().apply_diff(rradio_messages::PlayerStateDiff {
+                current_station: rradio_messages::OptionDiff::ChangedToSome(station),
+                ..Default::default()
+            }),
+        );
+
+        view.force_repaint(&state);
+        view.paint(&state, &mut display);
+
+        // The display can render Cyrillic, so the title isn't transliterated
+        assert_eq!(
+            display.character_display().snapshot()[1],
+            "Радио Ж             "
+        );
+    }
+
+    #[test]
+    fn buffering_history_is_reset_on_station_change() {
+        let buffering = |state: PlayerState, buffering| {
+            state.apply_diff(rradio_messages::PlayerStateDiff {
+                buffering: Some(buffering),
+                ..Default::default()
+            })
+        };
+
+        let state = buffering(buffering(PlayerState::default(), 50), 100);
+
+        assert_eq!(state.buffering_history.as_ref(), [0.5, 1.0]);
+
+        let state = state.apply_diff(rradio_messages::PlayerStateDiff {
+            current_station: rradio_messages::OptionDiff::ChangedToSome(
+                station(rradio_messages::StationType::UrlList)
+                    .as_ref()
+                    .clone(),
+            ),
+            ..Default::default()
+        });
+
+        assert!(state.buffering_history.as_ref().is_empty());
+        assert_eq!(buffering(state, 25).buffering_history.as_ref(), [0.25]);
+    }
+
+    #[test]
+    fn station_change_is_shown() {
+        use crate::display::{RecordingDisplay, WrappingTextDisplay};
+
+        let mut view = app(&Config::default(), "192.168.0.1", Instant::now());
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        let mut state = Arc::new(PlayerState::default());
+
+        view.force_repaint(&state);
+        view.paint(&state, &mut display);
+
+        assert!(display.character_display().snapshot()[0].starts_with("192.168.0.1"));
+
+        let new_state = state
+            .as_ref()
+            .clone()
+            .apply_diff(rradio_messages::PlayerStateDiff {
+                pipeline_state: Some(PipelineState::Playing),
+                current_station: rradio_messages::OptionDiff::ChangedToSome(
+                    station(rradio_messages::StationType::UrlList)
+                        .as_ref()
+                        .clone(),
+                ),
+                current_track_index: Some(0),
+                volume: Some(50),
+                ..Default::default()
+            });
+        let new_state = Arc::new(new_state);
+
+        let idle_screen = display.character_display().snapshot();
+
+        view.update(&state, &new_state);
+        state = new_state;
+
+        // The splash wipes over the idle screen, without the screen being cleared
+        view.paint(&state, &mut display);
+
+        assert_eq!(
+            display.character_display().snapshot(),
+            [
+                "12                  ",
+                "Station Title       ",
+                &idle_screen[2],
+                &idle_screen[3],
+            ]
+        );
+
+        let mut tick = |display: &mut WrappingTextDisplay<RecordingDisplay>| {
+            let state = &state;
+            view.event(&tick(), state);
+            view.paint(state, display);
+            display.character_display().snapshot()
+        };
+
+        assert_eq!(
+            tick(&mut display),
+            [
+                "12                  ",
+                "Station Title       ",
+                "                    ",
+                "                    ",
+            ]
+        );
+
+        // Then the station view wipes over the splash
+        assert_eq!(
+            tick(&mut display),
+            [
+                "No Ping TimesVol  50",
+                "Station Title       ",
+                "                    ",
+                "                    ",
+            ]
+        );
+
+        assert_eq!(
+            tick(&mut display),
+            [
+                "No Ping TimesVol  50",
+                "Station Title       ",
+                "A Title             ",
+                "\u{E000}                   ", // The buffering bar, which is empty
+            ]
+        );
+    }
+
+    #[test]
+    fn overlapping_layout_is_detected() {
+        let (a, b) = Line(1).split(10);
+        let overlapping = Segment {
+            position: CursorPosition { row: 1, column: 9 },
+            length: 2,
+        };
+
+        assert_valid_layout(&[a, b]);
+        assert!(std::panic::catch_unwind(|| assert_valid_layout(&[a, overlapping])).is_err());
+    }
+
+    #[test]
+    fn unknown_tags_filtering() {
+        let placeholder_tags = Config::default().placeholder_tags;
+
+        let tags = |placeholder_tags: &[String], unknown: &str| {
+            ConcatenatedTrackTags {
+                sep: ", ",
+                placeholder_tags,
+                tags: vec![
+                    Some(ArcStr::from("Artist")),
+                    Some(ArcStr::from(unknown)),
+                    Some(ArcStr::from("Album")),
+                ],
+            }
+            .to_string()
+        };
+
+        assert_eq!(tags(&placeholder_tags, "unknown"), "Artist, Album");
+        assert_eq!(tags(&placeholder_tags, "Unknown"), "Artist, Album");
+        assert_eq!(tags(&placeholder_tags, "n/a "), "Artist, Album");
+        assert_eq!(tags(&placeholder_tags, ""), "Artist, Album");
+        assert_eq!(tags(&[], "unknown"), "Artist, unknown, Album");
+        assert_eq!(tags(&[], ""), "Artist, , Album");
+    }
+
+    #[test]
+    fn remote_ping_errors_are_hidden_for_non_pingable_stations() {
+        use crate::display::{RecordingDisplay, WrappingTextDisplay};
+
+        let config = Config {
+            non_pingable_stations: vec![String::from("12")],
+            ..Config::default()
+        };
+
+        let state = PlayerState {
+            ping_times: rradio_messages::PingTimes::GatewayAndRemote {
+                gateway_ping: Duration::from_millis(3),
+                remote_ping: Err(rradio_messages::PingError::Timeout),
+                latest: rradio_messages::PingTarget::Remote,
+            },
+            temperature: Some(crate::Temperature(42)),
+            ..PlayerState::default()
+        };
+
+        let ping_line = |index: &str| {
+            let station = Arc::new(Station {
+                index: Some(index.into()),
+                source_type: rradio_messages::StationType::UrlList,
+                title: None,
+                tracks: Vec::new(),
+            });
+
+            let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+            station_view(&config).paint(&(station, Arc::new(state.clone())), &mut display);
+            display.character_display().snapshot()[0][..13].to_owned()
+        };
+
+        assert_eq!(ping_line("12"), "CPU Temp 42C ");
+        assert_eq!(ping_line("13"), "RPing No repl");
+    }
+
+    #[test]
+    fn ping_and_temperature_alternate_on_ticks() {
+        use crate::display::{RecordingDisplay, WrappingTextDisplay};
+
+        let config = Config {
+            ping_and_temperature_alternation: Duration::from_secs(2),
+            ..Config::default()
+        };
+
+        let station = Arc::new(Station {
+            index: Some("12".into()),
+            source_type: rradio_messages::StationType::UrlList,
+            title: None,
+            tracks: Vec::new(),
+        });
+
+        let state = |gateway_ping| PlayerState {
+            ping_times: rradio_messages::PingTimes::FinishedPingingRemote {
+                gateway_ping: Duration::from_millis(gateway_ping),
+            },
+            temperature: Some(crate::Temperature(42)),
+            ..PlayerState::default()
+        };
+
+        let mut view = station_view(&config);
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        let mut ping_line = |old_state: &PlayerState, state: &PlayerState, ticks| {
+            let old_data = (station.clone(), Arc::new(old_state.clone()));
+            let data = (station.clone(), Arc::new(state.clone()));
+
+            view.update(&old_data, &data);
+            for _ in 0..ticks {
+                view.event(&tick(), &data);
+            }
+            view.paint(&data, &mut display);
+            display.character_display().snapshot()[0][..13].to_owned()
+        };
+
+        assert_eq!(ping_line(&state(3), &state(3), 0), "LPing  3.0ms ");
+
+        // Ping updates don't swap to the temperature
+        assert_eq!(ping_line(&state(3), &state(4), 0), "LPing  4.0ms ");
+        assert_eq!(ping_line(&state(4), &state(4), 1), "LPing  4.0ms ");
+
+        assert_eq!(ping_line(&state(4), &state(4), 1), "CPU Temp 42C ");
+        assert_eq!(ping_line(&state(4), &state(5), 1), "CPU Temp 42C ");
+        assert_eq!(ping_line(&state(5), &state(5), 1), "LPing  5.0ms ");
+
+        // Until the temperature is measured, the ping time stays
+        let unmeasured = PlayerState {
+            temperature: None,
+            ..state(5)
+        };
+
+        assert_eq!(ping_line(&state(5), &unmeasured, 2), "LPing  5.0ms ");
+        assert_eq!(ping_line(&unmeasured, &unmeasured, 2), "LPing  5.0ms ");
+    }
+
+    /// The volume part of the top line, while playing
+    fn volume_text(config: &Config, volume: i32) -> String {
+        use crate::display::{RecordingDisplay, WrappingTextDisplay};
+
+        let (_, segment) = Line(0).split(13);
+
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        volume_and_pipeline_state_view(config, segment)
+            .paint(&(volume, PipelineState::Playing), &mut display);
+
+        display.character_display().snapshot()[0][13..].to_owned()
+    }
+
+    #[test]
+    fn unknown_and_muted_volumes() {
+        let config = Config::default();
+
+        // Before rradio reports the volume, it's -1
+        assert_eq!(
+            volume_text(&config, PlayerState::default().volume),
+            "Vol  --"
+        );
+        assert_eq!(volume_text(&config, 0), "   Mute");
+        assert_eq!(volume_text(&config, 1), "Vol   1");
+        assert_eq!(volume_text(&config, 100), "Vol 100");
+    }
+
+    #[test]
+    fn volume_as_percentage() {
+        let config = Config {
+            volume_style: VolumeStyle::Percentage,
+            min_volume: -20,
+            max_volume: 80,
+            muted_volume: None,
+            ..Config::default()
+        };
+
+        assert_eq!(volume_text(&config, -21), "V    --");
+        assert_eq!(volume_text(&config, -20), "V    0%");
+        assert_eq!(volume_text(&config, 0), "V   20%");
+        assert_eq!(volume_text(&config, 33), "V   53%");
+        assert_eq!(volume_text(&config, 80), "V  100%");
+        assert_eq!(volume_text(&config, 90), "V  100%");
+    }
+
+    #[test]
+    fn volume_label_fits_volume_range() {
+        let (_, volume_segment) = Line(0).split(13);
+
+        assert_eq!(volume_width(0, 100), 3);
+        assert_eq!(
+            volume_label_layout(volume_segment.length, volume_width(0, 100)),
+            ("Vol", 4)
+        );
+
+        assert_eq!(volume_width(-100, 100), 4);
+        assert_eq!(
+            volume_label_layout(volume_segment.length, volume_width(-100, 100)),
+            ("V", 3)
+        );
+
+        assert_eq!(volume_width(0, 100_000), 6);
+        assert_eq!(
+            volume_label_layout(volume_segment.length, volume_width(0, 100_000)),
+            ("", 1)
+        );
+    }
+
+    #[test]
+    fn url_list_track_number_of_count() {
+        let tags = |current_track_index| {
+            StationTagsDisplay {
+                station_tags: StationTags::UrlList {
+                    current_track_index,
+                    track_count: 12,
+                    station_title: Some(ArcStr::from("Title")),
+                },
+                placeholder_tags: Config::default().placeholder_tags.into(),
+                tag_separator: ArcStr::from(", "),
+                tag_order: Arc::new(TagOrder::default()),
+            }
+            .to_string()
+        };
+
+        assert_eq!(tags(Some(3)), "3/12, Title");
+        assert_eq!(tags(None), "Title");
+    }
+
+    #[test]
+    fn custom_tag_separator_and_order() {
+        let tags = |artist: &str| {
+            StationTagsDisplay {
+                station_tags: StationTags::Samba {
+                    station_title: Some(ArcStr::from("Share")),
+                    artist: Some(ArcStr::from(artist)),
+                    album: Some(ArcStr::from("Album")),
+                },
+                placeholder_tags: Config::default().placeholder_tags.into(),
+                tag_separator: ArcStr::from(" - "),
+                tag_order: Arc::new(TagOrder {
+                    samba: vec![
+                        StationTag::Album,
+                        StationTag::Artist,
+                        StationTag::TrackNumber,
+                    ],
+                    ..TagOrder::default()
+                }),
+            }
+            .to_string()
+        };
+
+        // The station title isn't in the order, and Samba shares don't have a track number
+        assert_eq!(tags("Artist"), "Album - Artist");
+        assert_eq!(tags("Unknown"), "Album");
+    }
+
+    #[test]
+    fn clock_style_durations() {
+        let clock = |seconds: Option<u64>| {
+            let duration = ClockStyleDuration(seconds.map(Duration::from_secs));
+            let text = duration.to_string();
+            assert_eq!(text.len(), duration.space_required());
+            text
+        };
+
+        assert_eq!(clock(Some(0)), "00:00");
+        assert_eq!(clock(Some(187)), "03:07");
+        assert_eq!(clock(Some(3599)), "59:59");
+        assert_eq!(clock(Some(3600 + 187)), "1:03:07");
+        assert_eq!(clock(Some(36000)), "10:00:00");
+        assert_eq!(clock(None), "--:--");
+    }
+
+    #[test]
+    fn elapsed_and_remaining_track_position() {
+        let position = |track_position: Option<u64>, track_duration: Option<u64>| {
+            TrackPositionDisplay {
+                track_index: 3,
+                track_position: track_position.map(Duration::from_secs),
+                track_duration: track_duration.map(Duration::from_secs),
+                style: TrackPositionStyle::ElapsedAndRemaining,
+            }
+            .to_string()
+        };
+
+        assert_eq!(position(Some(65), Some(200)), "01:05 / 02:15");
+        assert_eq!(position(Some(65), None), "01:05 / --:--");
+        assert_eq!(position(None, None), "--:-- / --:--");
+        assert_eq!(position(Some(3000), Some(4000)), "50:00 / 16:40");
+        assert_eq!(position(Some(600), Some(4200)), "10:00/1:00:00");
+        assert_eq!(position(Some(3700), Some(7500)), "1:01:40");
+    }
+
+    #[test]
+    fn track_position_in_minutes() {
+        let position =
+            |track_index: usize, track_position: Option<u64>, track_duration: Option<u64>| {
+                TrackPositionDisplay {
+                    track_index,
+                    track_position: track_position.map(Duration::from_secs),
+                    track_duration: track_duration.map(Duration::from_secs),
+                    style: TrackPositionStyle::TrackNumberAndMinutes,
+                }
+                .to_string()
+            };
+
+        assert_eq!(position(3, Some(65), None), "3, 1:05 of ?");
+        assert_eq!(position(3, Some(5), Some(200)), "3,0:05of 3:20");
+        assert_eq!(position(12, Some(65), Some(200)), "12,1:05of3:20");
+        assert_eq!(position(12, Some(600), Some(4200)), "12, 10:00");
+
+        // Every layout fits in the ping segment
+        for track_index in [1, 12] {
+            for seconds in [5, 65, 600, 4200] {
+                assert!(position(track_index, Some(seconds), Some(seconds)).len() <= 13);
+            }
+        }
+    }
+
+    #[test]
+    fn up_next_is_shown_near_the_end_of_a_track() {
+        let notification = rradio_messages::Track {
+            title: Some(ArcStr::from("Notification")),
+            album: None,
+            artist: None,
+            url: ArcStr::from("file:///notification.mp3"),
+            is_notification: true,
+        };
+
+        let mut station = (*station(rradio_messages::StationType::UrlList)).clone();
+        station.tracks.insert(1, notification);
+
+        let up_next = |current_track_index, position_seconds| {
+            let state = PlayerState {
+                pipeline_state: PipelineState::Playing,
+                current_track_index,
+                track_position: Some(Duration::from_secs(position_seconds)),
+                track_duration: Some(Duration::from_secs(100)),
+                ..PlayerState::default()
+            };
+
+            up_next_title(
+                &station,
+                &state,
+                Duration::from_secs(2),
+                Duration::from_secs(10),
+            )
+        };
+
+        assert_eq!(up_next(0, 50), None);
+        assert_eq!(up_next(0, 87), None);
+        assert_eq!(
+            up_next(0, 88).as_deref(),
+            Some("Next: A Long Track Title Which Needs Scrolling")
+        );
+        assert_eq!(up_next(2, 95), None);
+    }
+
+    #[test]
+    fn displayed_track_numbers() {
+        let without_notification = station(rradio_messages::StationType::UrlList);
+
+        let mut with_notification = (*without_notification).clone();
+        with_notification.tracks.insert(
+            0,
+            rradio_messages::Track {
+                title: None,
+                album: None,
+                artist: None,
+                url: ArcStr::from("file:///notification.mp3"),
+                is_notification: true,
+            },
+        );
+
+        assert_eq!(displayed_track_number(&without_notification, 0), 1);
+        assert_eq!(displayed_track_number(&without_notification, 1), 2);
+        assert_eq!(displayed_track_number(&with_notification, 1), 1);
+        assert_eq!(displayed_track_number(&with_notification, 2), 2);
+
+        let state = |current_track_index| PlayerState {
+            current_track_index,
+            ..PlayerState::default()
+        };
+
+        assert_eq!(
+            displayed_url_list_track_index(&without_notification, &state(0)),
+            None
+        );
+        assert_eq!(
+            displayed_url_list_track_index(&without_notification, &state(1)),
+            Some(2)
+        );
+        assert_eq!(
+            displayed_url_list_track_index(&with_notification, &state(1)),
+            None
+        );
+        assert_eq!(
+            displayed_url_list_track_index(&with_notification, &state(2)),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn volume_cursor_is_shown_while_changing_volume() {
+        use crate::display::{RecordingDisplay, WrappingTextDisplay};
+
+        let config = Config {
+            volume_cursor: CursorStyle::Blinking,
+            ..Config::default()
+        };
+
+        let mut view = app(&config, "192.168.0.1", Instant::now());
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        let state = Arc::new(PlayerState {
+            volume: 50,
+            ..PlayerState::default()
+        });
+        view.paint(&state, &mut display);
+        display.flush();
+
+        let changed = Arc::new(PlayerState {
+            volume: 55,
+            ..state.as_ref().clone()
+        });
+        view.update(&state, &changed);
+        view.paint(&changed, &mut display);
+        display.flush();
+
+        assert_eq!(
+            display.character_display().cursor(),
+            (VOLUME_CURSOR_POSITION, CursorStyle::Blinking)
+        );
+
+        for _ in 0..duration_in_tics(config.force_show_volume_duration, config.tick_interval) {
+            view.event(&tick(), &changed);
+        }
+        view.paint(&changed, &mut display);
+        display.flush();
+
+        assert_eq!(display.character_display().cursor().1, CursorStyle::Off);
+    }
+
+    #[test]
+    fn hidden_idle_screen_parts_are_blank() {
+        use crate::display::{RecordingDisplay, WrappingTextDisplay};
+
+        let config = Config::default();
+
+        let mut view = ViewBuilder::new(&config)
+            .show_ping(false)
+            .show_date(false)
+            .show_clock(false)
+            .show_temperature(false)
+            .build("192.168.0.1", Instant::now());
+
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        let state = Arc::new(PlayerState {
+            temperature: Some(crate::Temperature(42)),
+            ..PlayerState::default()
+        });
+
+        view.paint(&state, &mut display);
+
+        let snapshot = display.character_display().snapshot();
+
+        assert!(snapshot[0].starts_with("192.168.0.1"), "{:?}", snapshot);
+        assert_eq!(
+            snapshot[1..],
+            [" ".repeat(20), " ".repeat(20), " ".repeat(20)]
+        );
+    }
 }
@@ -0,0 +1,84 @@
+//! Persisting the volume between runs, so that a sensible volume is shown before rradio reports the current volume
+
+use std::path::Path;
+
+use anyhow::Context;
+
+/// Load the volume saved by [save]. If the file is missing or corrupt, returns `None`
+pub async fn load(path: &Path) -> Option<i32> {
+    smol::fs::read_to_string(path)
+        .await
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Save the volume so that it can be restored by [load], creating the directory containing it if needed
+pub async fn save(path: &Path, volume: i32) -> anyhow::Result<()> {
+    if let Some(directory) = path.parent() {
+        smol::fs::create_dir_all(directory)
+            .await
+            .with_context(|| format!("Failed to create {}", directory.display()))?;
+    }
+
+    smol::fs::write(path, volume.to_string())
+        .await
+        .with_context(|| format!("Failed to save volume to {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "rradio_screen_volume_{}_{}",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = test_path("round_trip");
+
+        smol::block_on(async {
+            save(&path, 42).await.unwrap();
+            assert_eq!(load(&path).await, Some(42));
+        });
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn creates_missing_directory() {
+        let directory = test_path("creates_missing_directory");
+        let path = directory.join("volume");
+
+        smol::block_on(async {
+            save(&path, 42).await.unwrap();
+            assert_eq!(load(&path).await, Some(42));
+        });
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn missing_file() {
+        let path = test_path("missing_file");
+
+        assert_eq!(smol::block_on(load(&path)), None);
+    }
+
+    #[test]
+    fn corrupt_file() {
+        let path = test_path("corrupt_file");
+
+        std::fs::write(&path, "not a volume").unwrap();
+
+        assert_eq!(smol::block_on(load(&path)), None);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
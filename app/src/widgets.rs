@@ -1,11 +1,27 @@
-use std::{fmt::Display, marker::PhantomData};
-
-use unidecode::unidecode_char;
-
-use crate::display::{Segment, TextDisplay};
+use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    fmt::{self, Display, Write},
+    marker::PhantomData,
+    ops::Range,
+    time::Duration,
+};
+
+use crate::{
+    display::{BarGlyph, CursorPosition, CursorStyle, Line, Lines, Segment, TextDisplay},
+    SCREEN_WIDTH,
+};
 
+#[derive(Clone, Copy)]
 pub enum WidgetEvent {
-    Tick(std::time::Instant),
+    /// A tick at the given time since an arbitrary epoch, such as startup, as without std there's no standard clock,
+    /// and the time elapsed since the previous tick, which is longer than the tick interval if the tick was delayed
+    Tick(Duration, Duration),
 }
 
 pub trait Widget: Sized {
@@ -18,6 +34,25 @@ pub trait Widget: Sized {
     fn force_repaint(&mut self, data: &Self::Data);
 
     fn paint(&mut self, data: &Self::Data, display: &mut impl TextDisplay);
+
+    /// Returns true if painting would write to the display, so that painting can be skipped entirely when nothing has changed.
+    /// Widgets which can't tell without painting are always dirty
+    fn dirty(&self) -> bool {
+        true
+    }
+
+    /// The segment which this widget paints, if it paints within a single segment, which is what [Widget::blank] blanks by default
+    fn segment(&self) -> Option<Segment> {
+        None
+    }
+
+    /// Blank every segment which this widget paints, e.g. when it's replaced by a widget which covers different segments.
+    /// Widgets which don't know their segments leave the display as it is
+    fn blank(&self, display: &mut impl TextDisplay) {
+        if let Some(segment) = self.segment() {
+            display.write_to(segment, "");
+        }
+    }
 }
 
 pub struct FixedLabel<Data, S: AsRef<str>> {
@@ -56,6 +91,14 @@ impl<Data, S: AsRef<str>> Widget for FixedLabel<Data, S> {
             display.write_to(self.segment, self.string.as_ref());
         }
     }
+
+    fn dirty(&self) -> bool {
+        self.should_paint
+    }
+
+    fn segment(&self) -> Option<Segment> {
+        Some(self.segment)
+    }
 }
 
 pub struct GeneratedLabel<T: Display + PartialEq, G: FnMut() -> T, Data> {
@@ -96,6 +139,15 @@ impl<T: Display + PartialEq, G: FnMut() -> T, Data> Widget for GeneratedLabel<T,
             self.previous_value = Some(new_value);
         }
     }
+
+    fn dirty(&self) -> bool {
+        // The generated value is only known when painting
+        true
+    }
+
+    fn segment(&self) -> Option<Segment> {
+        Some(self.segment)
+    }
 }
 
 enum TextAlignment {
@@ -103,10 +155,28 @@ enum TextAlignment {
     Right,
 }
 
+/// Shorten `text` to at most `length` characters, replacing the end with "..." if it doesn't fit.
+/// If `length` is too short to fit any text as well as the ellipsis, the text is simply cut short
+fn truncate_with_ellipsis(text: &str, length: usize) -> String {
+    const ELLIPSIS: &str = "...";
+
+    if text.chars().count() <= length {
+        text.to_owned()
+    } else if length <= ELLIPSIS.len() {
+        text.chars().take(length).collect()
+    } else {
+        text.chars()
+            .take(length - ELLIPSIS.len())
+            .chain(ELLIPSIS.chars())
+            .collect()
+    }
+}
+
 pub struct Label<T: Display + PartialEq> {
     needs_repainting: bool,
     segment: Segment,
     text_alignment: TextAlignment,
+    truncate_with_ellipsis: bool,
     _data: PhantomData<fn(&T)>,
 }
 
@@ -116,6 +186,7 @@ impl<T: Display + PartialEq> Label<T> {
             needs_repainting: true,
             segment: segment.into(),
             text_alignment: TextAlignment::Left,
+            truncate_with_ellipsis: false,
             _data: PhantomData,
         }
     }
@@ -125,6 +196,26 @@ impl<T: Display + PartialEq> Label<T> {
         self.text_alignment = TextAlignment::Right;
         self
     }
+
+    /// If the data is too long to fit in the segment, end it with "..." rather than cutting it short
+    pub fn truncate_with_ellipsis(mut self) -> Self {
+        self.truncate_with_ellipsis = true;
+        self
+    }
+
+    fn write_aligned(&self, item: impl Display, display: &mut impl TextDisplay) {
+        match self.text_alignment {
+            TextAlignment::Left => display.write_to(self.segment, item),
+            TextAlignment::Right => display.write_to(
+                self.segment,
+                format_args!(
+                    "{:>width$.width$}",
+                    item,
+                    width = self.segment.length as usize
+                ),
+            ),
+        }
+    }
 }
 
 impl<T: Display + PartialEq> Widget for Label<T> {
@@ -143,56 +234,73 @@ impl<T: Display + PartialEq> Widget for Label<T> {
     }
 
     fn paint(&mut self, data: &Self::Data, display: &mut impl TextDisplay) {
+        if self.segment.is_empty() {
+            return;
+        }
+
         if self.needs_repainting {
             self.needs_repainting = false;
 
-            match self.text_alignment {
-                TextAlignment::Left => display.write_to(self.segment, data),
-                TextAlignment::Right => display.write_to(
-                    self.segment,
-                    format_args!(
-                        "{:>width$.width$}",
-                        data,
-                        width = self.segment.length as usize
-                    ),
-                ),
+            if self.truncate_with_ellipsis {
+                let text = truncate_with_ellipsis(&data.to_string(), self.segment.length.into());
+                self.write_aligned(text, display);
+            } else {
+                self.write_aligned(data, display);
             }
         }
     }
+
+    fn dirty(&self) -> bool {
+        self.needs_repainting && !self.segment.is_empty()
+    }
+
+    fn segment(&self) -> Option<Segment> {
+        Some(self.segment)
+    }
 }
 
-struct UniDecode<W: std::fmt::Write>(W);
+/// The text of `data`, which is generated once and kept in `text` until `text` is cleared.
+/// Characters which the display can't render are transliterated by the display, so the text is kept as it is
+fn generate_text<'t>(text: &'t mut Option<String>, data: &impl Display) -> &'t str {
+    text.get_or_insert_with(|| data.to_string()).as_str()
+}
 
-impl<W: std::fmt::Write> std::fmt::Write for UniDecode<W> {
-    fn write_char(&mut self, c: char) -> std::fmt::Result {
-        let handled_chars = ['é', 'è', 'à', 'ä', 'ñ', 'ö', 'ü', 'π', 'µ', '~', ''];
+/// The text starting at the character with index `start_position`, which is always sliced on a character boundary
+fn text_from_character(text: &str, start_position: usize) -> &str {
+    text.char_indices()
+        .nth(start_position)
+        .map_or("", |(byte_index, _)| &text[byte_index..])
+}
 
-        if handled_chars.contains(&c) {
-            self.0.write_char(c)
-        } else {
-            self.0.write_str(match unidecode_char(c) {
-                "[?]" => "�",
-                s => s,
-            })
-        }
-    }
+/// How a [ScrollingLabel] scrolls text which is too long to fit
+#[derive(Clone, Copy, PartialEq)]
+pub enum ScrollMode {
+    /// Jump forward a word at a time, restarting from the beginning when near the end
+    WordJump,
+    /// Scroll forward a character at a time until the end of the text is visible, pause, then scroll back to the beginning
+    PingPong,
+}
 
-    fn write_str(&mut self, s: &str) -> std::fmt::Result {
-        s.chars().try_for_each(|c| self.write_char(c))
-    }
+enum ScrollDirection {
+    Forward,
+    Backward,
 }
 
 pub struct ScrollingLabel<T: Display + PartialEq> {
     needs_repainting: bool,
-    start_position: usize,
+    start_position: usize, // The index of the first visible character, in characters rather than bytes
     wait_ticks_remaining: usize,
+    pause_ticks: usize,
+    scroll_mode: ScrollMode,
+    scroll_direction: ScrollDirection,
+    text_alignment: TextAlignment,
     segment: Segment,
     text: Option<String>,
     _data: PhantomData<fn(&T)>,
 }
 
 impl<T: Display + PartialEq> ScrollingLabel<T> {
-    const WAIT_BEFORE_SCROLLING_TICKS_COUNT: usize = 2; // The number of tics before scrolling begins
+    const WAIT_BEFORE_SCROLLING_TICKS_COUNT: usize = 2; // The default number of tics before scrolling begins
     const MAX_SCROLL: usize = 6; // The furthest distance (in characters) that a label will scroll
     const CHARACTERS_REMAINING_RESET_COUNT: usize = 6; // The number of remaining characters when scrolling restarts from the beginning
 
@@ -201,33 +309,72 @@ impl<T: Display + PartialEq> ScrollingLabel<T> {
             needs_repainting: true,
             start_position: 0,
             wait_ticks_remaining: 0,
+            pause_ticks: Self::WAIT_BEFORE_SCROLLING_TICKS_COUNT,
+            scroll_mode: ScrollMode::WordJump,
+            scroll_direction: ScrollDirection::Forward,
+            text_alignment: TextAlignment::Left,
             segment: segment.into(),
             text: None,
             _data: PhantomData,
         }
     }
 
-    fn generate_text<'t>(text: &'t mut Option<String>, data: &T) -> &'t str {
-        use std::fmt::Write;
+    pub fn with_scroll_mode(mut self, scroll_mode: ScrollMode) -> Self {
+        self.scroll_mode = scroll_mode;
+        self
+    }
 
-        text.get_or_insert_with(|| {
-            let mut buffer = String::new();
-            write!(UniDecode(&mut buffer), "{}", data).unwrap();
-            buffer
-        })
-        .as_str()
+    /// The number of ticks to wait before scrolling begins, and at the end of [ScrollMode::PingPong] scrolling
+    pub fn with_pause_ticks(mut self, pause_ticks: usize) -> Self {
+        self.pause_ticks = pause_ticks;
+        self
+    }
+
+    /// If the text fits in the segment, and so isn't scrolling, align it to the right of the segment
+    pub fn align_right(mut self) -> Self {
+        self.text_alignment = TextAlignment::Right;
+        self
     }
 
     fn reset_scroll(&mut self) {
         self.needs_repainting = true;
         self.start_position = 0;
-        self.wait_ticks_remaining = Self::WAIT_BEFORE_SCROLLING_TICKS_COUNT;
+        self.wait_ticks_remaining = self.pause_ticks;
+        self.scroll_direction = ScrollDirection::Forward;
+    }
+
+    fn update_ping_pong_scroll(&mut self, text_length: usize) {
+        let max_start_position = text_length.saturating_sub(self.segment.length.into());
+
+        match self.scroll_direction {
+            ScrollDirection::Forward => {
+                self.start_position = (self.start_position + 1).min(max_start_position);
+
+                if self.start_position == max_start_position {
+                    self.scroll_direction = ScrollDirection::Backward;
+                    self.wait_ticks_remaining = self.pause_ticks;
+                }
+            }
+            ScrollDirection::Backward => {
+                self.start_position = self.start_position.saturating_sub(1);
+
+                if self.start_position == 0 {
+                    self.scroll_direction = ScrollDirection::Forward;
+                    self.wait_ticks_remaining = self.pause_ticks;
+                }
+            }
+        }
     }
 
     fn update_scroll(&mut self, data: &T) {
-        let text = Self::generate_text(&mut self.text, data);
+        if self.segment.is_empty() {
+            return;
+        }
+
+        let text = generate_text(&mut self.text, data);
+        let text_length = text.chars().count();
 
-        if text.chars().count() <= self.segment.length.into() {
+        if text_length <= self.segment.length.into() {
             return;
         }
 
@@ -238,21 +385,26 @@ impl<T: Display + PartialEq> ScrollingLabel<T> {
 
         self.needs_repainting = true;
 
-        let visible_text = &text[self.start_position..];
+        if let ScrollMode::PingPong = self.scroll_mode {
+            self.update_ping_pong_scroll(text_length);
+            return;
+        }
+
+        let visible_text = text_from_character(text, self.start_position);
 
         if visible_text.chars().count() <= Self::CHARACTERS_REMAINING_RESET_COUNT {
             self.reset_scroll();
             return;
         }
 
-        if let Some((_n, (i, _c))) = visible_text
-            .char_indices()
+        if let Some((n, _c)) = visible_text
+            .chars()
             .enumerate()
-            .skip_while(|&(n, (_i, c))| (n < (Self::MAX_SCROLL - 1)) && !c.is_whitespace())
+            .skip_while(|&(n, c)| (n < (Self::MAX_SCROLL - 1)) && !c.is_whitespace())
             .skip(1)
-            .find(|&(_n, (_i, c))| !c.is_whitespace())
+            .find(|&(_n, c)| !c.is_whitespace())
         {
-            self.start_position += i;
+            self.start_position += n;
         } else {
             self.reset_scroll();
         }
@@ -280,311 +432,2111 @@ impl<T: Display + PartialEq> Widget for ScrollingLabel<T> {
     }
 
     fn paint(&mut self, data: &Self::Data, display: &mut impl TextDisplay) {
+        if self.segment.is_empty() {
+            return;
+        }
+
         if self.needs_repainting {
             self.needs_repainting = false;
 
-            display.write_to(
-                self.segment,
-                &Self::generate_text(&mut self.text, data)[self.start_position..],
-            );
+            let text = generate_text(&mut self.text, data);
+            let width = self.segment.length as usize;
+
+            match self.text_alignment {
+                TextAlignment::Right if text.chars().count() <= width => display.write_to(
+                    self.segment,
+                    format_args!("{:>width$}", text, width = width),
+                ),
+                _ => display.write_to(self.segment, text_from_character(text, self.start_position)),
+            }
         }
     }
+
+    fn dirty(&self) -> bool {
+        self.needs_repainting && !self.segment.is_empty()
+    }
+
+    fn segment(&self) -> Option<Segment> {
+        Some(self.segment)
+    }
 }
 
-#[derive(Clone, Copy)]
-pub enum Either<A, B> {
-    A(A),
-    B(B),
+/// Treats several whole lines as one continuous field, filling each line before moving onto the next.
+/// Text which doesn't fit crawls through the field a character each tick, like a news ticker, and loops around to the beginning.
+/// Alternatively, with [MultiLineScrollingLabel::scroll_by_line], the text moves up a whole line each tick, which is easier to read
+pub struct MultiLineScrollingLabel<T: Display + PartialEq> {
+    needs_repainting: bool,
+    start_position: usize, // The index of the first visible character, in characters rather than bytes
+    wait_ticks_remaining: usize,
+    pause_ticks: usize,
+    scroll_by_line: bool,
+    first_line: u8,
+    last_line: u8,
+    text: Option<String>,
+    _data: PhantomData<fn(&T)>,
 }
 
-pub trait IntoEither {
-    type A;
-    type B;
+impl<T: Display + PartialEq> MultiLineScrollingLabel<T> {
+    const WAIT_BEFORE_SCROLLING_TICKS_COUNT: usize = 2; // The default number of tics before scrolling begins
+    const SEPARATOR: &'static str = " *** "; // Shown between the end of the text and the beginning when looping around
 
-    fn into_either(self) -> Either<Self::A, Self::B>;
-}
+    pub fn new(Lines(first_line, last_line): Lines) -> Self {
+        Self {
+            needs_repainting: true,
+            start_position: 0,
+            wait_ticks_remaining: Self::WAIT_BEFORE_SCROLLING_TICKS_COUNT,
+            pause_ticks: Self::WAIT_BEFORE_SCROLLING_TICKS_COUNT,
+            scroll_by_line: false,
+            first_line,
+            last_line,
+            text: None,
+            _data: PhantomData,
+        }
+    }
 
-impl<A, B> IntoEither for Either<A, B> {
-    type A = A;
-    type B = B;
+    /// The number of ticks to wait before scrolling begins
+    pub fn with_pause_ticks(mut self, pause_ticks: usize) -> Self {
+        self.pause_ticks = pause_ticks;
+        self.wait_ticks_remaining = pause_ticks;
+        self
+    }
 
-    fn into_either(self) -> Either<A, B> {
+    /// Move the text up a whole line each tick rather than a character. A blank line is shown between the end of the text and the beginning
+    pub fn scroll_by_line(mut self) -> Self {
+        self.scroll_by_line = true;
         self
     }
-}
 
-impl<T> IntoEither for Option<T> {
-    type A = T;
-    type B = ();
+    /// The number of ticks taken to scroll through the whole of the text and back to the beginning, or zero if the text doesn't scroll
+    pub fn ticks_per_loop(&mut self, data: &T) -> usize {
+        let text_length = generate_text(&mut self.text, data).chars().count();
 
-    fn into_either(self) -> Either<Self::A, Self::B> {
-        match self {
-            Some(some) => Either::A(some),
-            None => Either::B(()),
+        if text_length <= self.field_length() {
+            0
+        } else {
+            self.pause_ticks + self.loop_length(text_length) / self.step()
         }
     }
-}
 
-pub struct EitherWidget<T, A, B> {
-    a: A,
-    b: B,
-    _data: PhantomData<fn(&T)>,
-}
+    fn field_length(&self) -> usize {
+        usize::from(crate::SCREEN_WIDTH) * usize::from(1 + self.last_line - self.first_line)
+    }
 
-impl<T, A, B> EitherWidget<T, A, B>
-where
-    T: Clone + IntoEither,
-    A: Widget<Data = <T as IntoEither>::A>,
-    B: Widget<Data = <T as IntoEither>::B>,
-{
-    pub fn new(a: A, b: B) -> Self {
-        Self {
-            a,
-            b,
-            _data: PhantomData,
+    fn step(&self) -> usize {
+        if self.scroll_by_line {
+            usize::from(crate::SCREEN_WIDTH)
+        } else {
+            1
+        }
+    }
+
+    /// The length of the text and the separator which follows it
+    fn loop_length(&self, text_length: usize) -> usize {
+        if self.scroll_by_line {
+            let line_length = usize::from(crate::SCREEN_WIDTH);
+            (text_length + 2 * line_length - 1) / line_length * line_length
+        } else {
+            text_length + Self::SEPARATOR.len()
         }
     }
+
+    fn update_scroll(&mut self, data: &T) {
+        let field_length = self.field_length();
+        let text_length = generate_text(&mut self.text, data).chars().count();
+
+        if text_length <= field_length {
+            return;
+        }
+
+        if self.wait_ticks_remaining > 0 {
+            self.wait_ticks_remaining -= 1;
+            return;
+        }
+
+        self.needs_repainting = true;
+        self.start_position = (self.start_position + self.step()) % self.loop_length(text_length);
+    }
 }
 
-impl<T, A, B> Widget for EitherWidget<T, A, B>
-where
-    T: Clone + IntoEither,
-    A: Widget<Data = <T as IntoEither>::A>,
-    B: Widget<Data = <T as IntoEither>::B>,
-{
+impl<T: Display + PartialEq> Widget for MultiLineScrollingLabel<T> {
     type Data = T;
 
     fn event(&mut self, event: &WidgetEvent, data: &Self::Data) {
-        match data.clone().into_either() {
-            Either::A(data) => self.a.event(event, &data),
-            Either::B(data) => self.b.event(event, &data),
+        match event {
+            WidgetEvent::Tick(..) => self.update_scroll(data),
         }
     }
 
     fn update(&mut self, old_data: &Self::Data, data: &Self::Data) {
-        match (old_data.clone().into_either(), data.clone().into_either()) {
-            (Either::A(old_data), Either::A(data)) => self.a.update(&old_data, &data),
-            (Either::B(old_data), Either::B(data)) => self.b.update(&old_data, &data),
-            (Either::B(_), Either::A(data)) => self.a.force_repaint(&data),
-            (Either::A(_), Either::B(data)) => self.b.force_repaint(&data),
+        if old_data != data {
+            self.force_repaint(data);
         }
     }
 
-    fn force_repaint(&mut self, data: &Self::Data) {
-        match data.clone().into_either() {
-            Either::A(data) => self.a.force_repaint(&data),
-            Either::B(data) => self.b.force_repaint(&data),
-        }
+    fn force_repaint(&mut self, _data: &Self::Data) {
+        self.needs_repainting = true;
+        self.text = None;
+        self.start_position = 0;
+        self.wait_ticks_remaining = self.pause_ticks;
     }
 
     fn paint(&mut self, data: &Self::Data, display: &mut impl TextDisplay) {
-        match data.clone().into_either() {
-            Either::A(data) => self.a.paint(&data, display),
-            Either::B(data) => self.b.paint(&data, display),
+        if !self.needs_repainting {
+            return;
         }
-    }
-}
 
-pub trait Scope {
-    type In;
-    type Out;
+        self.needs_repainting = false;
 
-    fn event(&mut self, event: &WidgetEvent, data: &Self::In);
-    fn update(&mut self, old_data: &Self::In, data: &Self::In);
+        let field_length = self.field_length();
+        let text_length = generate_text(&mut self.text, data).chars().count();
+        let loop_length = self.loop_length(text_length);
+        let separator = if self.scroll_by_line {
+            " "
+        } else {
+            Self::SEPARATOR
+        };
+        let text = generate_text(&mut self.text, data);
 
-    fn reset(&mut self);
+        let field = if text_length <= field_length {
+            String::from(text)
+        } else {
+            text.chars()
+                .chain(separator.chars().cycle())
+                .take(loop_length)
+                .cycle()
+                .skip(self.start_position)
+                .take(field_length)
+                .collect()
+        };
+
+        // Each line is written separately, so the layout doesn't depend on how the display wraps text
+        let mut field = field.chars();
+
+        for row in self.first_line..=self.last_line {
+            display.write_to(
+                Line(row),
+                field
+                    .by_ref()
+                    .take(usize::from(crate::SCREEN_WIDTH))
+                    .collect::<String>(),
+            );
+        }
+    }
 
-    fn data(&self, data: &Self::In) -> Self::Out;
+    fn dirty(&self) -> bool {
+        self.needs_repainting
+    }
+
+    fn segment(&self) -> Option<Segment> {
+        Some(Lines(self.first_line, self.last_line).into())
+    }
 }
 
-pub struct FunctionScope<Data: Clone, In, Out, E, U, D>
-where
-    E: Fn(&mut Data, &WidgetEvent, &In),
-    U: Fn(&mut Data, &In, &In),
-    D: Fn(&Data, &In) -> Out,
-{
-    initial_data: Data,
-    data: Data,
-    handle_event: E,
-    handle_update: U,
-    produce_data: D,
-    _data: PhantomData<fn(&In) -> Out>,
+struct ProgressBarText {
+    filled_units: usize,
 }
 
-impl<Data: Clone, In, Out, E, U, D> FunctionScope<Data, In, Out, E, U, D>
-where
-    E: Fn(&mut Data, &WidgetEvent, &In),
-    U: Fn(&mut Data, &In, &In),
-    D: Fn(&Data, &In) -> Out,
-{
-    pub fn new(data: Data, handle_event: E, handle_update: U, produce_data: D) -> Self {
-        Self {
-            initial_data: data.clone(),
-            data,
-            handle_event,
-            handle_update,
-            produce_data,
-            _data: PhantomData,
+impl Display for ProgressBarText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let full_characters = self.filled_units / ProgressBar::UNITS_PER_CHARACTER;
+        let partial_units = self.filled_units % ProgressBar::UNITS_PER_CHARACTER;
+
+        for _ in 0..full_characters {
+            f.write_char('█')?;
         }
+
+        if partial_units > 0 {
+            BarGlyph::ALL[partial_units - 1].fmt(f)?;
+        }
+
+        Ok(())
     }
 }
 
-impl<Data: Clone, In, Out, E, U, D> Scope for FunctionScope<Data, In, Out, E, U, D>
-where
-    E: Fn(&mut Data, &WidgetEvent, &In),
-    U: Fn(&mut Data, &In, &In),
-    D: Fn(&Data, &In) -> Out,
-{
-    type In = In;
-    type Out = Out;
+/// A bar which fills a [Segment] in proportion to a value between 0.0 and 1.0.
+/// The last character of the bar is drawn using a [BarGlyph] to give sub-character resolution
+pub struct ProgressBar {
+    needs_repainting: bool,
+    segment: Segment,
+}
 
-    fn event(&mut self, event: &WidgetEvent, data: &Self::In) {
-        (self.handle_event)(&mut self.data, event, data)
-    }
+impl ProgressBar {
+    const UNITS_PER_CHARACTER: usize = 5; // The number of pixel columns per character
 
-    fn update(&mut self, old_data: &Self::In, data: &Self::In) {
-        (self.handle_update)(&mut self.data, old_data, data)
+    pub fn new(segment: impl Into<Segment>) -> Self {
+        Self {
+            needs_repainting: true,
+            segment: segment.into(),
+        }
     }
 
-    fn reset(&mut self) {
-        self.data = self.initial_data.clone();
+    fn filled_units(&self, fraction: f32) -> usize {
+        let total_units = usize::from(self.segment.length) * Self::UNITS_PER_CHARACTER;
+
+        // Rounded to the nearest unit, without f32::round, which needs std
+        (fraction.clamp(0.0, 1.0) * total_units as f32 + 0.5) as usize
     }
+}
 
-    fn data(&self, data: &Self::In) -> Self::Out {
-        (self.produce_data)(&self.data, data)
+impl Widget for ProgressBar {
+    type Data = f32;
+
+    fn event(&mut self, _event: &WidgetEvent, _data: &Self::Data) {}
+
+    fn update(&mut self, old_data: &Self::Data, data: &Self::Data) {
+        if self.filled_units(*old_data) != self.filled_units(*data) {
+            self.needs_repainting = true;
+        }
+    }
+
+    fn force_repaint(&mut self, _data: &Self::Data) {
+        self.needs_repainting = true;
+    }
+
+    fn paint(&mut self, data: &Self::Data, display: &mut impl TextDisplay) {
+        if self.needs_repainting {
+            self.needs_repainting = false;
+
+            display.write_to(
+                self.segment,
+                ProgressBarText {
+                    filled_units: self.filled_units(*data),
+                },
+            );
+        }
+    }
+
+    fn dirty(&self) -> bool {
+        self.needs_repainting
+    }
+
+    fn segment(&self) -> Option<Segment> {
+        Some(self.segment)
     }
 }
 
-pub struct ScopeWidget<Data, W: Widget, S: Scope<In = Data, Out = W::Data>> {
-    inner: W,
-    scope: S,
+/// The columns of the [BarGlyph]s which draw the most recent `values` which fit in `length` characters
+fn sparkline_columns(values: &[f32], length: u8) -> impl Iterator<Item = usize> + '_ {
+    let highest_column = BarGlyph::ALL.len() - 1;
+
+    values[values.len().saturating_sub(usize::from(length))..]
+        .iter()
+        .map(move |value| (value.clamp(0.0, 1.0) * highest_column as f32 + 0.5) as usize)
 }
 
-impl<Data, W: Widget, S: Scope<In = Data, Out = W::Data>> Widget for ScopeWidget<Data, W, S> {
-    type Data = Data;
+struct SparklineText<'a> {
+    values: &'a [f32],
+    length: u8,
+}
 
-    fn event(&mut self, event: &WidgetEvent, data: &Self::Data) {
-        let old_combined_data = self.scope.data(data);
-        self.scope.event(event, data);
-        let combined_data = self.scope.data(data);
+impl<'a> Display for SparklineText<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for column in sparkline_columns(self.values, self.length) {
+            BarGlyph::ALL[column].fmt(f)?;
+        }
 
-        self.inner.update(&old_combined_data, &combined_data);
+        Ok(())
+    }
+}
 
-        self.inner.event(event, &combined_data);
+/// Recent values between 0.0 and 1.0, oldest first, drawn one per character so that their trend can be seen.
+/// Each value is drawn as a [BarGlyph], further to the right the higher the value.
+/// Only the most recent values which fit in the [Segment] are drawn
+pub struct Sparkline<Values> {
+    needs_repainting: bool,
+    segment: Segment,
+    _values: PhantomData<fn(&Values)>,
+}
+
+impl<Values: AsRef<[f32]>> Sparkline<Values> {
+    pub fn new(segment: impl Into<Segment>) -> Self {
+        Self {
+            needs_repainting: true,
+            segment: segment.into(),
+            _values: PhantomData,
+        }
     }
+}
+
+impl<Values: AsRef<[f32]>> Widget for Sparkline<Values> {
+    type Data = Values;
+
+    fn event(&mut self, _event: &WidgetEvent, _data: &Self::Data) {}
 
     fn update(&mut self, old_data: &Self::Data, data: &Self::Data) {
-        let old_combined_data = self.scope.data(old_data);
-        self.scope.update(old_data, data);
-        let combined_data = self.scope.data(data);
+        let length = self.segment.length;
 
-        self.inner.update(&old_combined_data, &combined_data);
+        if !sparkline_columns(old_data.as_ref(), length)
+            .eq(sparkline_columns(data.as_ref(), length))
+        {
+            self.needs_repainting = true;
+        }
     }
 
-    fn force_repaint(&mut self, data: &Self::Data) {
-        self.scope.reset();
-        self.inner.force_repaint(&self.scope.data(data))
+    fn force_repaint(&mut self, _data: &Self::Data) {
+        self.needs_repainting = true;
     }
 
     fn paint(&mut self, data: &Self::Data, display: &mut impl TextDisplay) {
-        self.inner.paint(&self.scope.data(data), display)
+        if self.needs_repainting {
+            self.needs_repainting = false;
+
+            display.write_to(
+                self.segment,
+                SparklineText {
+                    values: data.as_ref(),
+                    length: self.segment.length,
+                },
+            );
+        }
+    }
+
+    fn dirty(&self) -> bool {
+        self.needs_repainting
+    }
+
+    fn segment(&self) -> Option<Segment> {
+        Some(self.segment)
     }
 }
 
-pub struct LensWidget<Data, W: Widget, L: Fn(&Data) -> W::Data> {
-    lens: L,
-    inner: W,
-    _phantom_data: PhantomData<fn(&Data)>,
+/// A single character which alternates between two glyphs each time the count given as data changes,
+/// so that a steady blink shows that something is still happening, and a frozen glyph shows that it has stopped
+pub struct Blinker {
+    needs_repainting: bool,
+    glyphs: [char; 2],
+    segment: Segment,
 }
 
-impl<Data, W: Widget, L: Fn(&Data) -> W::Data> Widget for LensWidget<Data, W, L> {
-    type Data = Data;
+impl Blinker {
+    pub fn new(glyphs: [char; 2], segment: impl Into<Segment>) -> Self {
+        Self {
+            needs_repainting: true,
+            glyphs,
+            segment: segment.into(),
+        }
+    }
+}
 
-    fn event(&mut self, event: &WidgetEvent, data: &Data) {
-        self.inner.event(event, &(self.lens)(data))
+impl Widget for Blinker {
+    type Data = usize;
+
+    fn event(&mut self, _event: &WidgetEvent, _data: &Self::Data) {}
+
+    fn update(&mut self, old_count: &Self::Data, count: &Self::Data) {
+        if old_count % 2 != count % 2 {
+            self.needs_repainting = true;
+        }
     }
 
-    fn update(&mut self, old_data: &Data, data: &Data) {
-        self.inner
-            .update(&(self.lens)(old_data), &(self.lens)(data))
+    fn force_repaint(&mut self, _data: &Self::Data) {
+        self.needs_repainting = true;
     }
 
-    fn force_repaint(&mut self, data: &Data) {
-        self.inner.force_repaint(&(self.lens)(data))
+    fn paint(&mut self, count: &Self::Data, display: &mut impl TextDisplay) {
+        if self.needs_repainting {
+            self.needs_repainting = false;
+
+            display.write_to(self.segment, self.glyphs[count % 2]);
+        }
     }
 
-    fn paint(&mut self, data: &Data, display: &mut impl TextDisplay) {
-        self.inner.paint(&(self.lens)(data), display)
+    fn dirty(&self) -> bool {
+        self.needs_repainting
+    }
+
+    fn segment(&self) -> Option<Segment> {
+        Some(self.segment)
     }
 }
 
-pub struct WidgetGroup<T, W1, W2>(W1, W2, PhantomData<fn(&T)>);
+/// A single character animation which advances one frame each tick while active, and is blank while inactive
+pub struct Spinner {
+    needs_repainting: bool,
+    frame: usize,
+    segment: Segment,
+}
 
-impl<T, W1: Widget<Data = T>, W2: Widget<Data = T>> Widget for WidgetGroup<T, W1, W2> {
-    type Data = T;
+impl Spinner {
+    // The vertical line sweeps across the character
+    const FRAMES: [BarGlyph; 5] = BarGlyph::ALL;
 
-    fn event(&mut self, event: &WidgetEvent, data: &Self::Data) {
-        self.0.event(event, data);
-        self.1.event(event, data);
+    pub fn new(segment: impl Into<Segment>) -> Self {
+        Self {
+            needs_repainting: true,
+            frame: 0,
+            segment: segment.into(),
+        }
+    }
+}
+
+impl Widget for Spinner {
+    type Data = bool;
+
+    fn event(&mut self, event: &WidgetEvent, &is_active: &Self::Data) {
+        match event {
+            WidgetEvent::Tick(..) => {
+                if is_active {
+                    self.frame = (self.frame + 1) % Self::FRAMES.len();
+                    self.needs_repainting = true;
+                }
+            }
+        }
     }
 
     fn update(&mut self, old_data: &Self::Data, data: &Self::Data) {
-        self.0.update(old_data, data);
-        self.1.update(old_data, data);
+        if old_data != data {
+            self.needs_repainting = true;
+        }
     }
 
-    fn force_repaint(&mut self, data: &Self::Data) {
-        self.0.force_repaint(data);
-        self.1.force_repaint(data);
+    fn force_repaint(&mut self, _data: &Self::Data) {
+        self.frame = 0;
+        self.needs_repainting = true;
     }
 
-    fn paint(&mut self, data: &Self::Data, display: &mut impl TextDisplay) {
-        self.0.paint(data, display);
-        self.1.paint(data, display);
+    fn paint(&mut self, &is_active: &Self::Data, display: &mut impl TextDisplay) {
+        if self.needs_repainting {
+            self.needs_repainting = false;
+
+            if is_active {
+                display.write_to(self.segment, Self::FRAMES[self.frame]);
+            } else {
+                display.write_to(self.segment, "");
+            }
+        }
+    }
+
+    fn dirty(&self) -> bool {
+        self.needs_repainting
+    }
+
+    fn segment(&self) -> Option<Segment> {
+        Some(self.segment)
     }
 }
 
-pub trait WidgetExt: Widget {
-    /// Wrap this widget in a [LensWidget] widget for the provided lens
-    fn with_lens<Data, M: Fn(&Data) -> Self::Data>(self, lens: M) -> LensWidget<Data, Self, M> {
-        LensWidget {
-            lens,
-            inner: self,
-            _phantom_data: PhantomData,
+type RotatingItem<Data, T> = Box<dyn Fn(&Data) -> Option<T>>;
+
+/// Cycles through a list of items, showing each for a fixed number of ticks.
+/// Items which produce `None` are skipped.
+/// Items may depend on more than the data, e.g. the current time, so the shown item is repainted whenever its value changes
+pub struct Rotating<Data, T: Display + PartialEq> {
+    needs_repainting: bool,
+    painted_value: Option<T>,
+    segment: Segment,
+    ticks_per_item: usize,
+    ticks_remaining: usize,
+    current_item: usize,
+    items: Vec<RotatingItem<Data, T>>,
+}
+
+impl<Data, T: Display + PartialEq> Rotating<Data, T> {
+    pub fn new(segment: impl Into<Segment>, ticks_per_item: usize) -> Self {
+        Self {
+            needs_repainting: true,
+            painted_value: None,
+            segment: segment.into(),
+            ticks_per_item,
+            ticks_remaining: ticks_per_item,
+            current_item: 0,
+            items: Vec::new(),
         }
     }
 
-    /// Wrap this widget in a [ScopeWidget] widget with the provided scope
-    fn with_scope<S: Scope<Out = Self::Data>>(self, scope: S) -> ScopeWidget<S::In, Self, S> {
-        ScopeWidget { inner: self, scope }
+    /// Add an item to the end of the rotation
+    pub fn with_item(mut self, item: impl Fn(&Data) -> Option<T> + 'static) -> Self {
+        self.items.push(Box::new(item));
+        self
     }
 
-    /// Group this widget with the provided widget
-    fn group<W: Widget<Data = Self::Data>>(self, widget: W) -> WidgetGroup<Self::Data, Self, W> {
-        WidgetGroup(self, widget, PhantomData)
+    /// The index and value of the item being shown, skipping items which produce `None`
+    fn current_value(&self, data: &Data) -> Option<(usize, T)> {
+        (0..self.items.len())
+            .map(|offset| (self.current_item + offset) % self.items.len())
+            .find_map(|index| (self.items[index])(data).map(|value| (index, value)))
     }
 }
 
-impl<W: Widget> WidgetExt for W {}
+impl<Data, T: Display + PartialEq> Widget for Rotating<Data, T> {
+    type Data = Data;
 
-pub struct PassThrough<W>(pub W);
+    fn event(&mut self, event: &WidgetEvent, data: &Self::Data) {
+        match event {
+            WidgetEvent::Tick(..) => {
+                self.ticks_remaining = self.ticks_remaining.saturating_sub(1);
 
-impl<T, W: Widget<Data = T>> Widget for PassThrough<W> {
-    type Data = T;
+                if self.ticks_remaining == 0 {
+                    self.ticks_remaining = self.ticks_per_item;
 
-    fn event(&mut self, event: &WidgetEvent, data: &Self::Data) {
-        self.0.event(event, data)
+                    let shown_item = self
+                        .current_value(data)
+                        .map_or(self.current_item, |(index, _)| index);
+
+                    self.current_item = (shown_item + 1) % self.items.len().max(1);
+                    self.needs_repainting = true;
+                }
+            }
+        }
     }
 
     fn update(&mut self, old_data: &Self::Data, data: &Self::Data) {
-        self.0.update(old_data, data)
+        if self.current_value(old_data) != self.current_value(data) {
+            self.needs_repainting = true;
+        }
     }
 
-    fn force_repaint(&mut self, data: &Self::Data) {
-        self.0.force_repaint(data)
+    fn force_repaint(&mut self, _data: &Self::Data) {
+        self.needs_repainting = true;
+        self.current_item = 0;
+        self.ticks_remaining = self.ticks_per_item;
     }
 
     fn paint(&mut self, data: &Self::Data, display: &mut impl TextDisplay) {
-        self.0.paint(data, display)
+        let value = self.current_value(data).map(|(_, value)| value);
+
+        if self.needs_repainting || value != self.painted_value {
+            self.needs_repainting = false;
+
+            match &value {
+                Some(value) => display.write_to(self.segment, value),
+                None => display.write_to(self.segment, ""),
+            }
+
+            self.painted_value = value;
+        }
+    }
+
+    fn dirty(&self) -> bool {
+        // Items may depend on more than the data, so whether the shown value has changed is only known when painting
+        true
+    }
+
+    fn segment(&self) -> Option<Segment> {
+        Some(self.segment)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Either<A, B> {
+    A(A),
+    B(B),
+}
+
+pub trait IntoEither {
+    type A;
+    type B;
+
+    fn into_either(self) -> Either<Self::A, Self::B>;
+}
+
+impl<A, B> IntoEither for Either<A, B> {
+    type A = A;
+    type B = B;
+
+    fn into_either(self) -> Either<A, B> {
+        self
+    }
+}
+
+impl<T> IntoEither for Option<T> {
+    type A = T;
+    type B = ();
+
+    fn into_either(self) -> Either<Self::A, Self::B> {
+        match self {
+            Some(some) => Either::A(some),
+            None => Either::B(()),
+        }
+    }
+}
+
+pub struct EitherWidget<T, A, B> {
+    a: A,
+    b: B,
+    /// Whether `a` or `b` was last given data, or `None` if neither has been. Only the widget being shown can be dirty
+    showing_a: Option<bool>,
+    /// True if the widget which is no longer shown hasn't yet been blanked, so that it doesn't leave characters where the shown widget doesn't paint
+    blank_hidden: bool,
+    _data: PhantomData<fn(&T)>,
+}
+
+impl<T, A, B> EitherWidget<T, A, B>
+where
+    T: Clone + IntoEither,
+    A: Widget<Data = <T as IntoEither>::A>,
+    B: Widget<Data = <T as IntoEither>::B>,
+{
+    pub fn new(a: A, b: B) -> Self {
+        Self {
+            a,
+            b,
+            showing_a: None,
+            blank_hidden: false,
+            _data: PhantomData,
+        }
+    }
+}
+
+impl<T, A, B> Widget for EitherWidget<T, A, B>
+where
+    T: Clone + IntoEither,
+    A: Widget<Data = <T as IntoEither>::A>,
+    B: Widget<Data = <T as IntoEither>::B>,
+{
+    type Data = T;
+
+    fn event(&mut self, event: &WidgetEvent, data: &Self::Data) {
+        match data.clone().into_either() {
+            Either::A(data) => self.a.event(event, &data),
+            Either::B(data) => self.b.event(event, &data),
+        }
+    }
+
+    fn update(&mut self, old_data: &Self::Data, data: &Self::Data) {
+        let data = data.clone().into_either();
+        self.showing_a = Some(matches!(data, Either::A(_)));
+
+        match (old_data.clone().into_either(), data) {
+            (Either::A(old_data), Either::A(data)) => self.a.update(&old_data, &data),
+            (Either::B(old_data), Either::B(data)) => self.b.update(&old_data, &data),
+            (Either::B(_), Either::A(data)) => {
+                self.blank_hidden = true;
+                self.a.force_repaint(&data)
+            }
+            (Either::A(_), Either::B(data)) => {
+                self.blank_hidden = true;
+                self.b.force_repaint(&data)
+            }
+        }
+    }
+
+    fn force_repaint(&mut self, data: &Self::Data) {
+        let data = data.clone().into_either();
+        self.showing_a = Some(matches!(data, Either::A(_)));
+
+        match data {
+            Either::A(data) => self.a.force_repaint(&data),
+            Either::B(data) => self.b.force_repaint(&data),
+        }
+    }
+
+    fn paint(&mut self, data: &Self::Data, display: &mut impl TextDisplay) {
+        let data = data.clone().into_either();
+        self.showing_a = Some(matches!(data, Either::A(_)));
+
+        if !self.blank_hidden {
+            match data {
+                Either::A(data) => self.a.paint(&data, display),
+                Either::B(data) => self.b.paint(&data, display),
+            }
+            return;
+        }
+
+        self.blank_hidden = false;
+
+        // The shown widget has been forced to repaint, so paints all of its segments, and only what's left of the hidden widget needs blanking
+        let mut display = CoveringDisplay {
+            display,
+            covered: Vec::new(),
+        };
+
+        match data {
+            Either::A(data) => {
+                self.a.paint(&data, &mut display);
+                self.b.blank(&mut UncoveredDisplay::from(display));
+            }
+            Either::B(data) => {
+                self.b.paint(&data, &mut display);
+                self.a.blank(&mut UncoveredDisplay::from(display));
+            }
+        }
+    }
+
+    fn dirty(&self) -> bool {
+        self.blank_hidden
+            || match self.showing_a {
+                Some(true) => self.a.dirty(),
+                Some(false) => self.b.dirty(),
+                None => true,
+            }
+    }
+
+    fn blank(&self, display: &mut impl TextDisplay) {
+        // Only the widget last shown has painted anything, unless the widget shown before it is yet to be blanked
+        match self.showing_a {
+            Some(true) => self.a.blank(display),
+            Some(false) => self.b.blank(display),
+            None => {}
+        }
+
+        if self.blank_hidden {
+            match self.showing_a {
+                Some(true) => self.b.blank(display),
+                Some(false) => self.a.blank(display),
+                None => {}
+            }
+        }
+    }
+}
+
+/// Paints the inner widget if the data is `Some`, otherwise blanks the segment. Created by [WidgetExt::or_blank]
+pub type OrBlank<W> = EitherWidget<Option<<W as Widget>::Data>, W, FixedLabel<(), &'static str>>;
+
+/// The screen cells which a segment covers, counting along each row and then down the screen
+fn segment_cells(segment: Segment) -> Range<usize> {
+    let start = usize::from(segment.position.row) * usize::from(SCREEN_WIDTH)
+        + usize::from(segment.position.column);
+
+    start..(start + usize::from(segment.length))
+}
+
+/// Forwards writes to the display, noting which cells have been painted
+struct CoveringDisplay<'d, D: TextDisplay> {
+    display: &'d mut D,
+    covered: Vec<Range<usize>>,
+}
+
+impl<'d, D: TextDisplay> TextDisplay for CoveringDisplay<'d, D> {
+    fn clear(&mut self) {
+        self.display.clear();
+    }
+
+    fn write_to(&mut self, segment: impl Into<Segment>, item: impl Display) {
+        let segment = segment.into();
+        self.covered.push(segment_cells(segment));
+        self.display.write_to(segment, item);
+    }
+
+    fn skip(&mut self, segment: impl Into<Segment>) {
+        let segment = segment.into();
+        self.covered.push(segment_cells(segment));
+        self.display.skip(segment);
+    }
+
+    fn set_cursor(&mut self, position: CursorPosition, style: CursorStyle) {
+        self.display.set_cursor(position, style);
+    }
+
+    fn flush(&mut self) {
+        self.display.flush();
+    }
+}
+
+/// Blanks the parts of each segment written to which weren't covered by a [CoveringDisplay], which are what's left of a hidden widget
+struct UncoveredDisplay<'d, D: TextDisplay> {
+    display: &'d mut D,
+    covered: Vec<Range<usize>>,
+}
+
+impl<'d, D: TextDisplay> From<CoveringDisplay<'d, D>> for UncoveredDisplay<'d, D> {
+    fn from(CoveringDisplay { display, covered }: CoveringDisplay<'d, D>) -> Self {
+        Self { display, covered }
+    }
+}
+
+impl<'d, D: TextDisplay> TextDisplay for UncoveredDisplay<'d, D> {
+    fn clear(&mut self) {
+        self.display.clear();
+    }
+
+    fn write_to(&mut self, segment: impl Into<Segment>, _item: impl Display) {
+        let mut uncovered = vec![segment_cells(segment.into())];
+
+        for covered in &self.covered {
+            uncovered = uncovered
+                .into_iter()
+                .flat_map(|cells| {
+                    [
+                        cells.start..cells.end.min(covered.start),
+                        cells.start.max(covered.end)..cells.end,
+                    ]
+                })
+                .filter(|cells| !cells.is_empty())
+                .collect();
+        }
+
+        let width = usize::from(SCREEN_WIDTH);
+
+        for cells in uncovered {
+            let segment = Segment {
+                position: CursorPosition {
+                    row: (cells.start / width) as u8,
+                    column: (cells.start % width) as u8,
+                },
+                length: cells.len() as u8,
+            };
+
+            self.display.write_to(segment, "");
+        }
+    }
+
+    fn set_cursor(&mut self, position: CursorPosition, style: CursorStyle) {
+        self.display.set_cursor(position, style);
+    }
+
+    fn flush(&mut self) {
+        self.display.flush();
+    }
+}
+
+/// A [TextDisplay] which only writes segments which start in the first `rows` rows, leaving the rest of the screen as it was
+struct RevealedRows<'d, D: TextDisplay> {
+    display: &'d mut D,
+    rows: u8,
+}
+
+impl<'d, D: TextDisplay> TextDisplay for RevealedRows<'d, D> {
+    fn clear(&mut self) {
+        self.display.clear();
+    }
+
+    fn write_to(&mut self, segment: impl Into<Segment>, item: impl Display) {
+        let segment = segment.into();
+
+        if segment.position.row < self.rows {
+            self.display.write_to(segment, item);
+        } else {
+            self.display.skip(segment);
+        }
+    }
+
+    fn skip(&mut self, segment: impl Into<Segment>) {
+        self.display.skip(segment);
+    }
+
+    fn set_cursor(&mut self, position: CursorPosition, style: CursorStyle) {
+        self.display.set_cursor(position, style);
+    }
+
+    fn flush(&mut self) {
+        self.display.flush();
+    }
+}
+
+/// Wipes the inner widget onto the screen from the top down. The data is the number of rows which have been revealed, and the data of the inner widget.
+/// The rows which haven't been revealed are left as they were, so the inner widget replaces the previous view a few rows at a time rather than after the screen is cleared
+pub struct WipeTransition<W>(pub W);
+
+impl<W: Widget> Widget for WipeTransition<W> {
+    type Data = (u8, W::Data);
+
+    fn event(&mut self, event: &WidgetEvent, (_, data): &Self::Data) {
+        self.0.event(event, data)
+    }
+
+    fn update(&mut self, (old_rows, old_data): &Self::Data, (rows, data): &Self::Data) {
+        self.0.update(old_data, data);
+
+        // Segments in newly revealed rows were skipped when last painted
+        if old_rows != rows {
+            self.0.force_repaint(data);
+        }
+    }
+
+    fn force_repaint(&mut self, (_, data): &Self::Data) {
+        self.0.force_repaint(data)
+    }
+
+    fn paint(&mut self, &(rows, ref data): &Self::Data, display: &mut impl TextDisplay) {
+        self.0.paint(data, &mut RevealedRows { display, rows })
+    }
+
+    fn dirty(&self) -> bool {
+        self.0.dirty()
+    }
+
+    fn blank(&self, display: &mut impl TextDisplay) {
+        self.0.blank(display)
+    }
+}
+
+pub trait Scope {
+    type In;
+    type Out;
+
+    fn event(&mut self, event: &WidgetEvent, data: &Self::In);
+    fn update(&mut self, old_data: &Self::In, data: &Self::In);
+
+    fn reset(&mut self);
+
+    fn data(&self, data: &Self::In) -> Self::Out;
+}
+
+pub struct FunctionScope<Data: Clone, In, Out, E, U, D>
+where
+    E: Fn(&mut Data, &WidgetEvent, &In),
+    U: Fn(&mut Data, &In, &In),
+    D: Fn(&Data, &In) -> Out,
+{
+    initial_data: Data,
+    data: Data,
+    handle_event: E,
+    handle_update: U,
+    produce_data: D,
+    _data: PhantomData<fn(&In) -> Out>,
+}
+
+impl<Data: Clone, In, Out, E, U, D> FunctionScope<Data, In, Out, E, U, D>
+where
+    E: Fn(&mut Data, &WidgetEvent, &In),
+    U: Fn(&mut Data, &In, &In),
+    D: Fn(&Data, &In) -> Out,
+{
+    pub fn new(data: Data, handle_event: E, handle_update: U, produce_data: D) -> Self {
+        Self {
+            initial_data: data.clone(),
+            data,
+            handle_event,
+            handle_update,
+            produce_data,
+            _data: PhantomData,
+        }
+    }
+}
+
+impl<Data: Clone, In, Out, E, U, D> Scope for FunctionScope<Data, In, Out, E, U, D>
+where
+    E: Fn(&mut Data, &WidgetEvent, &In),
+    U: Fn(&mut Data, &In, &In),
+    D: Fn(&Data, &In) -> Out,
+{
+    type In = In;
+    type Out = Out;
+
+    fn event(&mut self, event: &WidgetEvent, data: &Self::In) {
+        (self.handle_event)(&mut self.data, event, data)
+    }
+
+    fn update(&mut self, old_data: &Self::In, data: &Self::In) {
+        (self.handle_update)(&mut self.data, old_data, data)
+    }
+
+    fn reset(&mut self) {
+        self.data = self.initial_data.clone();
+    }
+
+    fn data(&self, data: &Self::In) -> Self::Out {
+        (self.produce_data)(&self.data, data)
+    }
+}
+
+pub struct ScopeWidget<Data, W: Widget, S: Scope<In = Data, Out = W::Data>> {
+    inner: W,
+    scope: S,
+}
+
+impl<Data, W: Widget, S: Scope<In = Data, Out = W::Data>> ScopeWidget<Data, W, S> {
+    /// The data which the inner widget would currently be given for `data`, so that tests can check the scope's accumulated state
+    #[cfg(test)]
+    pub fn peek(&self, data: &Data) -> S::Out {
+        self.scope.data(data)
+    }
+}
+
+impl<Data, W: Widget, S: Scope<In = Data, Out = W::Data>> Widget for ScopeWidget<Data, W, S> {
+    type Data = Data;
+
+    fn event(&mut self, event: &WidgetEvent, data: &Self::Data) {
+        let old_combined_data = self.scope.data(data);
+        self.scope.event(event, data);
+        let combined_data = self.scope.data(data);
+
+        self.inner.update(&old_combined_data, &combined_data);
+
+        self.inner.event(event, &combined_data);
+    }
+
+    fn update(&mut self, old_data: &Self::Data, data: &Self::Data) {
+        let old_combined_data = self.scope.data(old_data);
+        self.scope.update(old_data, data);
+        let combined_data = self.scope.data(data);
+
+        self.inner.update(&old_combined_data, &combined_data);
+    }
+
+    fn force_repaint(&mut self, data: &Self::Data) {
+        self.scope.reset();
+        self.inner.force_repaint(&self.scope.data(data))
+    }
+
+    fn paint(&mut self, data: &Self::Data, display: &mut impl TextDisplay) {
+        self.inner.paint(&self.scope.data(data), display)
+    }
+
+    fn dirty(&self) -> bool {
+        self.inner.dirty()
+    }
+
+    fn blank(&self, display: &mut impl TextDisplay) {
+        self.inner.blank(display)
+    }
+}
+
+pub struct LensWidget<Data, W: Widget, L: Fn(&Data) -> W::Data> {
+    lens: L,
+    inner: W,
+    _phantom_data: PhantomData<fn(&Data)>,
+}
+
+impl<Data, W: Widget, L: Fn(&Data) -> W::Data> Widget for LensWidget<Data, W, L> {
+    type Data = Data;
+
+    fn event(&mut self, event: &WidgetEvent, data: &Data) {
+        self.inner.event(event, &(self.lens)(data))
+    }
+
+    fn update(&mut self, old_data: &Data, data: &Data) {
+        self.inner
+            .update(&(self.lens)(old_data), &(self.lens)(data))
+    }
+
+    fn force_repaint(&mut self, data: &Data) {
+        self.inner.force_repaint(&(self.lens)(data))
+    }
+
+    fn paint(&mut self, data: &Data, display: &mut impl TextDisplay) {
+        self.inner.paint(&(self.lens)(data), display)
+    }
+
+    fn dirty(&self) -> bool {
+        self.inner.dirty()
+    }
+
+    fn blank(&self, display: &mut impl TextDisplay) {
+        self.inner.blank(display)
+    }
+}
+
+/// As [LensWidget], but the lensed data is only derived when the data changes, in [Widget::update] and [Widget::force_repaint],
+/// and is reused by [Widget::event] and [Widget::paint], which saves deriving, and cloning, the same data every tick.
+/// This relies on the widget being told of every change of data, as every widget must be
+pub struct CachingLensWidget<Data, W: Widget, L: Fn(&Data) -> W::Data> {
+    lens: L,
+    inner: W,
+    /// The lensed data, derived when the data last changed, or `None` if it hasn't been derived yet
+    cache: Option<W::Data>,
+    _phantom_data: PhantomData<fn(&Data)>,
+}
+
+impl<Data, W: Widget, L: Fn(&Data) -> W::Data> Widget for CachingLensWidget<Data, W, L> {
+    type Data = Data;
+
+    fn event(&mut self, event: &WidgetEvent, data: &Data) {
+        let lens = &self.lens;
+        let lensed_data = self.cache.get_or_insert_with(|| lens(data));
+        self.inner.event(event, lensed_data)
+    }
+
+    fn update(&mut self, old_data: &Data, data: &Data) {
+        let lensed_data = (self.lens)(data);
+
+        match &self.cache {
+            Some(old_lensed_data) => self.inner.update(old_lensed_data, &lensed_data),
+            None => self.inner.update(&(self.lens)(old_data), &lensed_data),
+        }
+
+        self.cache = Some(lensed_data);
+    }
+
+    fn force_repaint(&mut self, data: &Data) {
+        let lensed_data = (self.lens)(data);
+        self.inner.force_repaint(&lensed_data);
+        self.cache = Some(lensed_data);
+    }
+
+    fn paint(&mut self, data: &Data, display: &mut impl TextDisplay) {
+        let lens = &self.lens;
+        let lensed_data = self.cache.get_or_insert_with(|| lens(data));
+        self.inner.paint(lensed_data, display)
+    }
+
+    fn dirty(&self) -> bool {
+        self.inner.dirty()
+    }
+
+    fn blank(&self, display: &mut impl TextDisplay) {
+        self.inner.blank(display)
+    }
+}
+
+/// Transforms or suppresses the events sent to the inner widget, e.g. to slow down scrolling, or to ignore ticks while hidden
+pub struct EventMapWidget<W: Widget, F: FnMut(&WidgetEvent, &W::Data) -> Option<WidgetEvent>> {
+    event_filter: F,
+    inner: W,
+}
+
+impl<W: Widget, F: FnMut(&WidgetEvent, &W::Data) -> Option<WidgetEvent>> Widget
+    for EventMapWidget<W, F>
+{
+    type Data = W::Data;
+
+    fn event(&mut self, event: &WidgetEvent, data: &Self::Data) {
+        if let Some(event) = (self.event_filter)(event, data) {
+            self.inner.event(&event, data)
+        }
+    }
+
+    fn update(&mut self, old_data: &Self::Data, data: &Self::Data) {
+        self.inner.update(old_data, data)
+    }
+
+    fn force_repaint(&mut self, data: &Self::Data) {
+        self.inner.force_repaint(data)
+    }
+
+    fn paint(&mut self, data: &Self::Data, display: &mut impl TextDisplay) {
+        self.inner.paint(data, display)
+    }
+
+    fn dirty(&self) -> bool {
+        self.inner.dirty()
+    }
+
+    fn blank(&self, display: &mut impl TextDisplay) {
+        self.inner.blank(display)
+    }
+}
+
+pub struct WidgetGroup<T, W1, W2>(W1, W2, PhantomData<fn(&T)>);
+
+impl<T, W1: Widget<Data = T>, W2: Widget<Data = T>> Widget for WidgetGroup<T, W1, W2> {
+    type Data = T;
+
+    fn event(&mut self, event: &WidgetEvent, data: &Self::Data) {
+        self.0.event(event, data);
+        self.1.event(event, data);
+    }
+
+    fn update(&mut self, old_data: &Self::Data, data: &Self::Data) {
+        self.0.update(old_data, data);
+        self.1.update(old_data, data);
+    }
+
+    fn force_repaint(&mut self, data: &Self::Data) {
+        self.0.force_repaint(data);
+        self.1.force_repaint(data);
+    }
+
+    fn paint(&mut self, data: &Self::Data, display: &mut impl TextDisplay) {
+        self.0.paint(data, display);
+        self.1.paint(data, display);
+    }
+
+    fn dirty(&self) -> bool {
+        self.0.dirty() || self.1.dirty()
+    }
+
+    fn blank(&self, display: &mut impl TextDisplay) {
+        self.0.blank(display);
+        self.1.blank(display);
+    }
+}
+
+/// Implement [Widget] for a tuple of widgets which share the same data, so that many widgets can be grouped without deeply nesting [WidgetGroup]s
+macro_rules! impl_widget_for_tuple {
+    ($first:ident $(, $rest:ident)*) => {
+        #[allow(non_snake_case)]
+        impl<$first: Widget $(, $rest: Widget<Data = $first::Data>)*> Widget for ($first, $($rest,)*) {
+            type Data = $first::Data;
+
+            fn event(&mut self, event: &WidgetEvent, data: &Self::Data) {
+                let ($first, $($rest,)*) = self;
+                $first.event(event, data);
+                $($rest.event(event, data);)*
+            }
+
+            fn update(&mut self, old_data: &Self::Data, data: &Self::Data) {
+                let ($first, $($rest,)*) = self;
+                $first.update(old_data, data);
+                $($rest.update(old_data, data);)*
+            }
+
+            fn force_repaint(&mut self, data: &Self::Data) {
+                let ($first, $($rest,)*) = self;
+                $first.force_repaint(data);
+                $($rest.force_repaint(data);)*
+            }
+
+            fn paint(&mut self, data: &Self::Data, display: &mut impl TextDisplay) {
+                let ($first, $($rest,)*) = self;
+                $first.paint(data, display);
+                $($rest.paint(data, display);)*
+            }
+
+            fn dirty(&self) -> bool {
+                let ($first, $($rest,)*) = self;
+                $first.dirty() $(|| $rest.dirty())*
+            }
+
+            fn blank(&self, display: &mut impl TextDisplay) {
+                let ($first, $($rest,)*) = self;
+                $first.blank(display);
+                $($rest.blank(display);)*
+            }
+        }
+    };
+}
+
+impl_widget_for_tuple!(W1);
+impl_widget_for_tuple!(W1, W2);
+impl_widget_for_tuple!(W1, W2, W3);
+impl_widget_for_tuple!(W1, W2, W3, W4);
+impl_widget_for_tuple!(W1, W2, W3, W4, W5);
+impl_widget_for_tuple!(W1, W2, W3, W4, W5, W6);
+impl_widget_for_tuple!(W1, W2, W3, W4, W5, W6, W7);
+impl_widget_for_tuple!(W1, W2, W3, W4, W5, W6, W7, W8);
+
+/// An optional widget, e.g. one which is only included in a layout if configured. If `None`, nothing is painted
+impl<W: Widget> Widget for Option<W> {
+    type Data = W::Data;
+
+    fn event(&mut self, event: &WidgetEvent, data: &Self::Data) {
+        if let Some(widget) = self {
+            widget.event(event, data);
+        }
+    }
+
+    fn update(&mut self, old_data: &Self::Data, data: &Self::Data) {
+        if let Some(widget) = self {
+            widget.update(old_data, data);
+        }
+    }
+
+    fn force_repaint(&mut self, data: &Self::Data) {
+        if let Some(widget) = self {
+            widget.force_repaint(data);
+        }
+    }
+
+    fn paint(&mut self, data: &Self::Data, display: &mut impl TextDisplay) {
+        if let Some(widget) = self {
+            widget.paint(data, display);
+        }
+    }
+
+    fn dirty(&self) -> bool {
+        match self {
+            Some(widget) => widget.dirty(),
+            None => false,
+        }
+    }
+
+    fn blank(&self, display: &mut impl TextDisplay) {
+        if let Some(widget) = self {
+            widget.blank(display);
+        }
+    }
+}
+
+pub trait WidgetExt: Widget {
+    /// Wrap this widget in a [LensWidget] widget for the provided lens
+    fn with_lens<Data, M: Fn(&Data) -> Self::Data>(self, lens: M) -> LensWidget<Data, Self, M> {
+        LensWidget {
+            lens,
+            inner: self,
+            _phantom_data: PhantomData,
+        }
+    }
+
+    /// Wrap this widget in a [CachingLensWidget] widget for the provided lens, which is only called when the data changes.
+    /// Prefer this to [WidgetExt::with_lens] for lenses which build owned data, such as formatted text
+    fn map_data<Data, M: Fn(&Data) -> Self::Data>(
+        self,
+        lens: M,
+    ) -> CachingLensWidget<Data, Self, M> {
+        CachingLensWidget {
+            lens,
+            inner: self,
+            cache: None,
+            _phantom_data: PhantomData,
+        }
+    }
+
+    /// Wrap this widget in a [ScopeWidget] widget with the provided scope
+    fn with_scope<S: Scope<Out = Self::Data>>(self, scope: S) -> ScopeWidget<S::In, Self, S> {
+        ScopeWidget { inner: self, scope }
+    }
+
+    /// Wrap this widget in an [EventMapWidget] widget, which forwards the event returned by `event_filter`, or nothing if it returns `None`
+    fn with_event_filter<F: FnMut(&WidgetEvent, &Self::Data) -> Option<WidgetEvent>>(
+        self,
+        event_filter: F,
+    ) -> EventMapWidget<Self, F> {
+        EventMapWidget {
+            event_filter,
+            inner: self,
+        }
+    }
+
+    /// Show this widget when the data is `Some`, and blank `segment` when the data is `None`
+    fn or_blank(self, segment: impl Into<Segment>) -> OrBlank<Self>
+    where
+        Self::Data: Clone,
+    {
+        EitherWidget::new(self, FixedLabel::new("", segment))
+    }
+
+    /// Show this widget only while `predicate` is true of the data, blanking it otherwise
+    fn when<P: Fn(&Self::Data) -> bool>(self, predicate: P) -> When<Self, P> {
+        When {
+            inner: self,
+            predicate,
+            shown: None,
+            needs_blanking: true,
+        }
+    }
+
+    /// Group this widget with the provided widget
+    fn group<W: Widget<Data = Self::Data>>(self, widget: W) -> WidgetGroup<Self::Data, Self, W> {
+        WidgetGroup(self, widget, PhantomData)
+    }
+}
+
+impl<W: Widget> WidgetExt for W {}
+
+/// Shows the inner widget while the predicate is true of the data, and blanks it otherwise. Created by [WidgetExt::when]
+pub struct When<W: Widget, P: Fn(&W::Data) -> bool> {
+    inner: W,
+    predicate: P,
+    /// Whether the inner widget was shown as of the last data given, or `None` if no data has been given
+    shown: Option<bool>,
+    /// True if the inner widget has been hidden since it was last painted, or has never been painted
+    needs_blanking: bool,
+}
+
+impl<W: Widget, P: Fn(&W::Data) -> bool> Widget for When<W, P> {
+    type Data = W::Data;
+
+    fn event(&mut self, event: &WidgetEvent, data: &Self::Data) {
+        if (self.predicate)(data) {
+            self.inner.event(event, data)
+        }
+    }
+
+    fn update(&mut self, old_data: &Self::Data, data: &Self::Data) {
+        let is_shown = (self.predicate)(data);
+        self.shown = Some(is_shown);
+
+        match ((self.predicate)(old_data), is_shown) {
+            (true, true) => self.inner.update(old_data, data),
+            (false, true) => self.inner.force_repaint(data),
+            (true, false) => self.needs_blanking = true,
+            (false, false) => {}
+        }
+    }
+
+    fn force_repaint(&mut self, data: &Self::Data) {
+        let is_shown = (self.predicate)(data);
+        self.shown = Some(is_shown);
+
+        if is_shown {
+            self.inner.force_repaint(data)
+        } else {
+            self.needs_blanking = true;
+        }
+    }
+
+    fn paint(&mut self, data: &Self::Data, display: &mut impl TextDisplay) {
+        let is_shown = (self.predicate)(data);
+        self.shown = Some(is_shown);
+
+        if is_shown {
+            self.inner.paint(data, display);
+        } else if self.needs_blanking {
+            self.inner.blank(display);
+        }
+
+        self.needs_blanking = false;
+    }
+
+    fn dirty(&self) -> bool {
+        match self.shown {
+            Some(true) => self.inner.dirty(),
+            Some(false) => self.needs_blanking,
+            None => true,
+        }
+    }
+
+    fn blank(&self, display: &mut impl TextDisplay) {
+        if self.shown != Some(false) {
+            self.inner.blank(display)
+        }
+    }
+}
+
+pub struct PassThrough<W>(pub W);
+
+impl<T, W: Widget<Data = T>> Widget for PassThrough<W> {
+    type Data = T;
+
+    fn event(&mut self, event: &WidgetEvent, data: &Self::Data) {
+        self.0.event(event, data)
+    }
+
+    fn update(&mut self, old_data: &Self::Data, data: &Self::Data) {
+        self.0.update(old_data, data)
+    }
+
+    fn force_repaint(&mut self, data: &Self::Data) {
+        self.0.force_repaint(data)
+    }
+
+    fn paint(&mut self, data: &Self::Data, display: &mut impl TextDisplay) {
+        self.0.paint(data, display)
+    }
+
+    fn dirty(&self) -> bool {
+        self.0.dirty()
+    }
+
+    fn blank(&self, display: &mut impl TextDisplay) {
+        self.0.blank(display)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::display::{CursorPosition, Line};
+
+    /// Records the text written to the display
+    #[derive(Default)]
+    struct TextRecorder {
+        writes: Vec<String>,
+        segments: Vec<Segment>,
+    }
+
+    impl TextDisplay for TextRecorder {
+        fn clear(&mut self) {}
+
+        fn write_to(&mut self, segment: impl Into<Segment>, item: impl Display) {
+            self.segments.push(segment.into());
+            self.writes.push(item.to_string());
+        }
+    }
+
+    fn tick() -> WidgetEvent {
+        WidgetEvent::Tick(Duration::ZERO, Duration::ZERO)
+    }
+
+    #[test]
+    fn blinker_alternates_when_count_changes() {
+        let mut blinker = Blinker::new(['.', '\''], Line(0).split(19).1);
+
+        let mut display = TextRecorder::default();
+
+        blinker.paint(&0, &mut display);
+        blinker.event(&tick(), &0);
+        assert!(!blinker.dirty());
+
+        for (old_count, count) in [(0, 1), (1, 1), (1, 2), (2, usize::MAX), (usize::MAX, 0)] {
+            blinker.update(&old_count, &count);
+            blinker.paint(&count, &mut display);
+        }
+
+        // The count wraps around without the blinking stalling
+        assert_eq!(display.writes, [".", "'", ".", "'", "."]);
+    }
+
+    #[test]
+    fn wipe_transition_reveals_rows() {
+        let mut view = WipeTransition((
+            FixedLabel::new("zero", Line(0)),
+            FixedLabel::new("one", Line(1)),
+            Label::new(Line(2)),
+        ));
+
+        let mut display = TextRecorder::default();
+
+        view.paint(&(1, "two"), &mut display);
+        assert_eq!(display.writes, ["zero"]);
+
+        view.update(&(1, "two"), &(4, "two"));
+        view.paint(&(4, "two"), &mut display);
+        assert_eq!(display.writes, ["zero", "zero", "one", "two"]);
+
+        view.update(&(4, "two"), &(4, "three"));
+        view.paint(&(4, "three"), &mut display);
+        assert_eq!(display.writes[4..], ["three"]);
+    }
+
+    #[test]
+    fn tuple_group_paints_in_order() {
+        let (a, b) = Line(0).split(10);
+
+        let mut group = (
+            FixedLabel::new("first", a),
+            FixedLabel::new("second", b),
+            Label::new(Line(1)),
+        );
+
+        let mut display = TextRecorder::default();
+
+        group.paint(&"third", &mut display);
+        group.paint(&"third", &mut display);
+
+        assert_eq!(display.writes, ["first", "second", "third"]);
+
+        group.update(&"third", &"fourth");
+        group.paint(&"fourth", &mut display);
+
+        assert_eq!(display.writes, ["first", "second", "third", "fourth"]);
+
+        group.force_repaint(&"fourth");
+        group.paint(&"fourth", &mut display);
+
+        assert_eq!(display.writes.len(), 7);
+    }
+
+    #[test]
+    fn caching_lens_only_derives_changed_data() {
+        let derivations = core::cell::Cell::new(0);
+
+        let mut label = Label::new(Line(0)).map_data(|&number: &u32| {
+            derivations.set(derivations.get() + 1);
+            format!("Number {}", number)
+        });
+
+        let mut display = TextRecorder::default();
+
+        label.paint(&1, &mut display);
+        label.event(&tick(), &1);
+        label.paint(&1, &mut display);
+
+        assert_eq!(derivations.get(), 1);
+
+        label.update(&1, &2);
+        label.event(&tick(), &2);
+        label.paint(&2, &mut display);
+
+        assert_eq!(derivations.get(), 2);
+
+        label.force_repaint(&2);
+        label.paint(&2, &mut display);
+
+        assert_eq!(derivations.get(), 3);
+
+        assert_eq!(display.writes, ["Number 1", "Number 2", "Number 2"]);
+    }
+
+    #[test]
+    fn when_clears_then_repaints() {
+        let mut label = Label::new(Line(1))
+            .with_lens(|&value: &i32| value)
+            .when(|&value| value > 0);
+
+        let mut display = TextRecorder::default();
+
+        label.paint(&1, &mut display);
+        label.update(&1, &2);
+        label.paint(&2, &mut display);
+
+        // Hiding the label blanks its segment, once
+        label.update(&2, &-1);
+        label.paint(&-1, &mut display);
+        label.update(&-1, &-2);
+        label.paint(&-2, &mut display);
+        assert!(!label.dirty());
+
+        // Showing it again repaints it, even though the value hasn't changed since it was last shown
+        label.update(&-2, &2);
+        label.paint(&2, &mut display);
+
+        assert_eq!(display.writes, ["1", "2", "", "2"]);
+    }
+
+    #[test]
+    fn only_changed_widgets_are_dirty() {
+        let mut label = Label::new(Line(0)).or_blank(Line(0));
+
+        let mut display = TextRecorder::default();
+
+        assert!(label.dirty());
+        label.paint(&Some("a"), &mut display);
+        assert!(!label.dirty());
+
+        label.update(&Some("a"), &Some("a"));
+        assert!(!label.dirty());
+
+        // The blank label has never been painted, but isn't shown, so doesn't make the widget dirty
+        label.update(&Some("a"), &Some("b"));
+        assert!(label.dirty());
+        label.paint(&Some("b"), &mut display);
+        assert!(!label.dirty());
+
+        label.update(&Some("b"), &None);
+        assert!(label.dirty());
+        label.paint(&None, &mut display);
+        assert!(!label.dirty());
+
+        assert_eq!(display.writes, ["a", "b", ""]);
+    }
+
+    #[test]
+    fn event_filter_slows_scrolling() {
+        let text = "a b c d e f g h i j";
+        let segment = Segment {
+            position: CursorPosition { row: 1, column: 0 },
+            length: 5,
+        };
+
+        let mut full_speed = ScrollingLabel::new(segment);
+
+        let mut is_odd_tick = false;
+        let mut half_speed = ScrollingLabel::new(segment).with_event_filter(move |event, _| {
+            is_odd_tick = !is_odd_tick;
+            is_odd_tick.then_some(*event)
+        });
+
+        let mut full_speed_display = TextRecorder::default();
+        let mut half_speed_display = TextRecorder::default();
+
+        full_speed.paint(&text, &mut full_speed_display);
+        half_speed.paint(&text, &mut half_speed_display);
+
+        for _ in 0..6 {
+            full_speed.event(&tick(), &text);
+            full_speed.paint(&text, &mut full_speed_display);
+            half_speed.event(&tick(), &text);
+            half_speed.paint(&text, &mut half_speed_display);
+        }
+
+        assert_eq!(full_speed_display.writes.len(), 7);
+        assert_eq!(half_speed_display.writes.len(), 4);
+        assert_eq!(half_speed_display.writes, full_speed_display.writes[..4]);
+    }
+
+    #[test]
+    fn or_blank_clears_segment() {
+        let mut label = Label::new(Line(0)).or_blank(Line(0));
+
+        let mut display = TextRecorder::default();
+
+        label.paint(&Some("text"), &mut display);
+        label.update(&Some("text"), &None);
+        label.paint(&None, &mut display);
+        label.update(&None, &Some("text"));
+        label.paint(&Some("text"), &mut display);
+
+        assert_eq!(display.writes, ["text", "", "text"]);
+    }
+
+    #[test]
+    fn switching_branches_leaves_no_stale_characters() {
+        use crate::display::{RecordingDisplay, WrappingTextDisplay};
+
+        let (left, right) = Line(1).split(7);
+
+        let mut view = EitherWidget::new(
+            Label::new(Line(1)),
+            (FixedLabel::new("", left), Label::new(right)),
+        );
+
+        let mut display = WrappingTextDisplay::new(RecordingDisplay::default());
+
+        let long = Either::A("A long line of text!");
+        let short = Either::B("Short");
+
+        view.paint(&long, &mut display);
+        assert_eq!(
+            display.character_display().snapshot()[1],
+            "A long line of text!"
+        );
+
+        view.update(&long, &short);
+        view.paint(&short, &mut display);
+        assert_eq!(
+            display.character_display().snapshot()[1],
+            "       Short        "
+        );
+
+        view.update(&short, &long);
+        view.paint(&long, &mut display);
+        assert_eq!(
+            display.character_display().snapshot()[1],
+            "A long line of text!"
+        );
+    }
+
+    #[test]
+    fn hidden_branch_is_only_blanked_where_uncovered() {
+        let (left, right) = Line(0).split(10);
+
+        let mut view = EitherWidget::new(
+            FixedLabel::new("whole line", Line(0)),
+            WipeTransition((FixedLabel::new("left", left), FixedLabel::new("", Line(1)))),
+        );
+
+        let mut display = TextRecorder::default();
+
+        let hidden = Either::B((1, ()));
+
+        view.paint(&Either::A(()), &mut display);
+        view.update(&Either::A(()), &hidden);
+        view.paint(&hidden, &mut display);
+
+        // The right of the line is blanked, but not the unrevealed line below, which the transition will paint
+        assert_eq!(display.writes, ["whole line", "left", ""]);
+        assert_eq!(display.segments[2], right);
+    }
+
+    #[test]
+    fn only_the_shown_branch_is_blanked() {
+        let (left, right) = Line(0).split(10);
+
+        let mut nested = EitherWidget::new(
+            FixedLabel::new("left", left),
+            FixedLabel::new("right", right),
+        );
+
+        let mut display = TextRecorder::default();
+
+        nested.paint(&Either::<(), ()>::A(()), &mut display);
+        nested.blank(&mut display);
+
+        // The right of the line was never painted, so might belong to another widget
+        assert_eq!(display.writes, ["left", ""]);
+        assert_eq!(display.segments[1], left);
+    }
+
+    #[test]
+    fn zero_length_segment() {
+        let (segment, _) = Line(0).split(0);
+
+        let mut label = Label::new(segment);
+        let mut scrolling_label = ScrollingLabel::new(segment);
+
+        let text = "Some text which would usually scroll";
+
+        let mut display = TextRecorder::default();
+
+        for _ in 0..10 {
+            label.event(&tick(), &text);
+            label.paint(&text, &mut display);
+            scrolling_label.event(&tick(), &text);
+            scrolling_label.paint(&text, &mut display);
+        }
+
+        assert!(display.writes.is_empty());
+    }
+
+    #[test]
+    fn scrolling_multibyte_text() {
+        let text = "é è à ä ñ ö ü π µ é è à ä ñ ö ü";
+
+        let mut scrolling_label = ScrollingLabel::new(Segment {
+            position: CursorPosition { row: 1, column: 0 },
+            length: 10,
+        });
+
+        let mut display = TextRecorder::default();
+
+        scrolling_label.paint(&text, &mut display);
+
+        for _ in 0..40 {
+            scrolling_label.event(&tick(), &text);
+            scrolling_label.paint(&text, &mut display);
+        }
+
+        assert_eq!(display.writes[0], text);
+        assert_eq!(display.writes[1], "è à ä ñ ö ü π µ é è à ä ñ ö ü");
+        assert_eq!(display.writes[2], "à ä ñ ö ü π µ é è à ä ñ ö ü");
+        assert!(display.writes.contains(&String::from("ñ ö ü")));
+    }
+
+    #[test]
+    fn multi_line_scrolling_label_crawls_across_lines() {
+        let short_text = "Short";
+        let long_text = "A title which is much too long to fit on two lines";
+
+        let mut label = MultiLineScrollingLabel::new(Lines(2, 3));
+
+        let mut display = TextRecorder::default();
+
+        label.paint(&short_text, &mut display);
+        assert_eq!(display.writes, ["Short", ""]);
+
+        label.update(&short_text, &long_text);
+        label.paint(&long_text, &mut display);
+        assert_eq!(
+            display.writes[2..],
+            ["A title which is muc", "h too long to fit on"]
+        );
+
+        // Scrolling waits before starting
+        for _ in 0..3 {
+            label.event(&tick(), &long_text);
+            label.paint(&long_text, &mut display);
+        }
+
+        assert_eq!(
+            display.writes[4..],
+            [" title which is much", " too long to fit on "]
+        );
+
+        // The text loops around after the separator
+        for _ in 0..40 {
+            label.event(&tick(), &long_text);
+        }
+
+        label.paint(&long_text, &mut display);
+
+        assert_eq!(
+            display.writes[6..],
+            ["two lines *** A titl", "e which is much too "]
+        );
+    }
+
+    #[test]
+    fn multi_line_scrolling_label_scrolls_by_line() {
+        let text = "An error which is far too long to fit on two lines";
+
+        let mut label = MultiLineScrollingLabel::new(Lines(0, 1)).scroll_by_line();
+
+        // Two ticks of waiting, then the three lines of text and the blank line
+        assert_eq!(label.ticks_per_loop(&text), 6);
+
+        let mut display = TextRecorder::default();
+
+        for _ in 0..label.ticks_per_loop(&text) {
+            label.paint(&text, &mut display);
+            label.event(&tick(), &text);
+        }
+
+        label.paint(&text, &mut display);
+
+        assert_eq!(
+            display.writes,
+            [
+                "An error which is fa",
+                "r too long to fit on",
+                "r too long to fit on",
+                " two lines          ",
+                " two lines          ",
+                "                    ",
+                "                    ",
+                "An error which is fa",
+                "An error which is fa",
+                "r too long to fit on",
+            ]
+        );
+    }
+
+    #[test]
+    fn ping_pong_scrolling() {
+        let text = "abcdefgh";
+
+        let mut scrolling_label = ScrollingLabel::new(Segment {
+            position: CursorPosition { row: 1, column: 0 },
+            length: 5,
+        })
+        .with_scroll_mode(ScrollMode::PingPong);
+
+        let mut display = TextRecorder::default();
+
+        scrolling_label.paint(&text, &mut display);
+
+        for _ in 0..11 {
+            scrolling_label.event(&tick(), &text);
+            scrolling_label.paint(&text, &mut display);
+        }
+
+        assert_eq!(
+            display.writes,
+            [
+                "abcdefgh", // Start
+                "bcdefgh", "cdefgh",
+                "defgh", // Reached the end, so pause and then change direction
+                "cdefgh", "bcdefgh",
+                "abcdefgh", // Reached the start, so pause and then change direction
+                "bcdefgh",
+            ]
+        );
+    }
+
+    #[test]
+    fn shorter_pauses() {
+        let text = "abcdefgh";
+
+        let mut scrolling_label = ScrollingLabel::new(Segment {
+            position: CursorPosition { row: 1, column: 0 },
+            length: 5,
+        })
+        .with_scroll_mode(ScrollMode::PingPong)
+        .with_pause_ticks(1);
+
+        let mut display = TextRecorder::default();
+
+        scrolling_label.paint(&text, &mut display);
+
+        for _ in 0..9 {
+            scrolling_label.event(&tick(), &text);
+            scrolling_label.paint(&text, &mut display);
+        }
+
+        // As with the default pauses, but two ticks sooner
+        assert_eq!(
+            display.writes,
+            [
+                "abcdefgh", "bcdefgh", "cdefgh", "defgh", "cdefgh", "bcdefgh", "abcdefgh",
+                "bcdefgh",
+            ]
+        );
+    }
+
+    #[test]
+    fn right_aligned_scrolling_label() {
+        let segment = Segment {
+            position: CursorPosition { row: 0, column: 0 },
+            length: 5,
+        };
+
+        let mut display = TextRecorder::default();
+
+        ScrollingLabel::new(segment)
+            .align_right()
+            .paint(&"abc", &mut display);
+
+        let mut scrolling_label = ScrollingLabel::new(segment).align_right();
+
+        let text = "ab cd efgh";
+
+        scrolling_label.paint(&text, &mut display);
+        scrolling_label.event(&tick(), &text);
+        scrolling_label.paint(&text, &mut display);
+
+        assert_eq!(display.writes, ["  abc", "ab cd efgh", "cd efgh"]);
+    }
+
+    #[test]
+    fn ellipsis_truncation() {
+        let text = "Hello World";
+
+        for &(length, expected) in &[
+            (20, "Hello World"),
+            (11, "Hello World"),
+            (10, "Hello W..."),
+            (4, "H..."),
+            (3, "Hel"),
+            (2, "He"),
+            (1, "H"),
+            (0, ""),
+        ] {
+            assert_eq!(truncate_with_ellipsis(text, length), expected);
+        }
+
+        assert_eq!(truncate_with_ellipsis("Café Olé", 7), "Café...");
+    }
+
+    #[test]
+    fn rotating_items() {
+        let mut rotating = Rotating::new(Line(0), 2)
+            .with_item(|_: &bool| Some("a"))
+            .with_item(|&show_b| if show_b { Some("b") } else { None })
+            .with_item(|_| Some("c"));
+
+        let mut display = TextRecorder::default();
+
+        let mut tick_and_paint = |rotating: &mut Rotating<bool, &str>, show_b| {
+            rotating.event(&tick(), &show_b);
+            rotating.paint(&show_b, &mut display);
+        };
+
+        for _ in 0..6 {
+            tick_and_paint(&mut rotating, true);
+        }
+
+        for _ in 0..4 {
+            tick_and_paint(&mut rotating, false);
+        }
+
+        rotating.force_repaint(&false);
+        rotating.paint(&false, &mut display);
+
+        assert_eq!(display.writes, ["a", "b", "c", "a", "c", "a", "a"]);
+    }
+
+    #[test]
+    fn rotating_item_repaints_when_its_value_changes() {
+        use alloc::rc::Rc;
+        use core::cell::Cell;
+
+        let uptime = Rc::new(Cell::new(1));
+
+        let mut rotating = Rotating::new(Line(0), 10).with_item({
+            let uptime = uptime.clone();
+            move |_: &()| Some(uptime.get())
+        });
+
+        let mut display = TextRecorder::default();
+
+        rotating.paint(&(), &mut display);
+
+        // Without any events, the changed value is still painted
+        uptime.set(2);
+        assert!(rotating.dirty());
+        rotating.paint(&(), &mut display);
+
+        // An unchanged value isn't painted again
+        rotating.paint(&(), &mut display);
+
+        assert_eq!(display.writes, ["1", "2"]);
+    }
+
+    #[test]
+    fn progress_bar_glyphs() {
+        let progress_bar = ProgressBar::new(Segment {
+            position: CursorPosition { row: 3, column: 0 },
+            length: 4,
+        });
+
+        let glyphs = |fraction: f32| {
+            ProgressBarText {
+                filled_units: progress_bar.filled_units(fraction),
+            }
+            .to_string()
+        };
+
+        assert_eq!(glyphs(0.0), "");
+        assert_eq!(glyphs(0.05), "\u{E000}");
+        assert_eq!(glyphs(0.2), "\u{E003}");
+        assert_eq!(glyphs(0.25), "█");
+        assert_eq!(glyphs(0.3), "█\u{E000}");
+        assert_eq!(glyphs(0.5), "██");
+        assert_eq!(glyphs(0.9), "███\u{E002}");
+        assert_eq!(glyphs(1.0), "████");
+        assert_eq!(glyphs(1.5), "████");
+        assert_eq!(glyphs(-0.5), "");
+    }
+
+    #[test]
+    fn sparkline_glyphs() {
+        let mut sparkline = Sparkline::new(Segment {
+            position: CursorPosition { row: 3, column: 0 },
+            length: 4,
+        });
+
+        let mut display = TextRecorder::default();
+
+        sparkline.paint(&vec![0.0, 1.0, 0.5], &mut display);
+
+        // Only the most recent values which fit are shown
+        let values = vec![0.2, 0.0, 0.3, 0.6, 1.5];
+        sparkline.update(&vec![0.0, 1.0, 0.5], &values);
+        sparkline.paint(&values, &mut display);
+
+        // Values which don't change the glyphs don't repaint
+        sparkline.update(&values, &vec![0.0, 0.05, 0.25, 0.55, 1.0]);
+        sparkline.paint(&values, &mut display);
+
+        assert_eq!(
+            display.writes,
+            [
+                "\u{E000}\u{E004}\u{E002}",
+                "\u{E000}\u{E001}\u{E002}\u{E004}"
+            ]
+        );
     }
 }
@@ -0,0 +1,240 @@
+//! The configuration file of the screen driver, which configures both the application and the parts of the screen driver outside of it
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+/// Set to the path of the configuration file, e.g. on systems where `/boot` is absent or read-only
+const CONFIG_FILE_VARIABLE: &str = "SCREEN_CONFIG_FILE";
+const DEFAULT_CONFIG_FILE: &str = "/boot/config.toml";
+
+/// The configuration of the screen driver. Every field has a default, so an empty or missing file is valid
+#[derive(Debug, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// The configuration of the application, whose fields are at the top level of the file. See [app::Config]
+    #[serde(flatten)]
+    pub app: app::Config,
+    /// The pseudo-file from which the CPU temperature is read, in thousandths of a degree Celsius
+    pub thermal_zone: PathBuf,
+    /// If set, and an SSD1306 screen is configured, the SSD1306 screen shows the state alongside the LCD screen rather than instead of it,
+    /// with the parts of its idle screen chosen by this table
+    pub secondary_screen: Option<app::view::IdleScreenOptions>,
+    /// The file declaring the GPIO pins wired to the LCD screen, which may be overridden by the `WIRING_PINS_FILE` environment variable
+    pub wiring_pins_file: PathBuf,
+    /// The file choosing the ROM variant of the LCD screen and mapping additional characters to its character codes
+    pub character_map_file: PathBuf,
+    /// The file declaring the bespoke character patterns of the LCD screen
+    pub character_patterns_file: PathBuf,
+    /// The file configuring the SSD1306 screen. If it doesn't exist, there's no SSD1306 screen
+    pub ssd1306_file: PathBuf,
+    // The terminal driver ignores the screen driver's keys, so new keys must be added to its `SCREEN_DRIVER_KEYS` too
+    /// Keys which neither the application nor the screen driver know, which are rejected when loading
+    #[serde(flatten)]
+    unknown_keys: app::UnknownKeys,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            app: app::Config::default(),
+            thermal_zone: PathBuf::from("/sys/class/thermal/thermal_zone0/temp"),
+            secondary_screen: None,
+            wiring_pins_file: PathBuf::from("/boot/wiring_pins.toml"),
+            character_map_file: PathBuf::from("/boot/character_map.toml"),
+            character_patterns_file: PathBuf::from("/boot/character_patterns.toml"),
+            ssd1306_file: PathBuf::from("/boot/ssd1306.toml"),
+            unknown_keys: app::UnknownKeys::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Load `config_file` if given, which must exist.
+    /// Otherwise load the file named by the `SCREEN_CONFIG_FILE` environment variable, or `/boot/config.toml` if not set, or use the default configuration if it doesn't exist
+    pub fn load(config_file: Option<&Path>) -> anyhow::Result<Self> {
+        match config_file {
+            Some(config_file) => Self::load_from(config_file, false),
+            None => {
+                let config_file = std::env::var_os(CONFIG_FILE_VARIABLE)
+                    .map_or_else(|| DEFAULT_CONFIG_FILE.into(), PathBuf::from);
+
+                Self::load_from(&config_file, true)
+            }
+        }
+    }
+
+    /// Load `config_file`. If it doesn't exist and `may_be_missing` is true, the default configuration is used
+    fn load_from(config_file: &Path, may_be_missing: bool) -> anyhow::Result<Self> {
+        let config_src = match std::fs::read_to_string(config_file) {
+            Ok(config_src) => config_src,
+            Err(err) if may_be_missing && err.kind() == std::io::ErrorKind::NotFound => {
+                log::info!("No config file at {}", config_file.display());
+                return Ok(Self::default());
+            }
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("Failed to read config file {}", config_file.display())
+                })
+            }
+        };
+
+        Self::parse(&config_src)
+            .with_context(|| format!("Failed to parse config file {}", config_file.display()))
+    }
+
+    fn parse(config_src: &str) -> anyhow::Result<Self> {
+        let config: Self = toml::from_str(config_src)?;
+
+        config.unknown_keys.check()?;
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_file_is_default() {
+        let config: Config = toml::from_str("").unwrap();
+
+        assert_eq!(config.thermal_zone, Config::default().thermal_zone);
+        assert_eq!(config.secondary_screen, None);
+        assert_eq!(
+            config.app.tick_interval,
+            app::Config::default().tick_interval
+        );
+        assert_eq!(
+            config.app.rradio_address,
+            app::Config::default().rradio_address
+        );
+    }
+
+    #[test]
+    fn fields_are_read() {
+        let config: Config = toml::from_str(
+            r#"
+            thermal_zone = "/sys/class/thermal/thermal_zone1/temp"
+            wiring_pins_file = "/etc/rradio/wiring_pins.toml"
+            ssd1306_file = "/etc/rradio/ssd1306.toml"
+            rradio_address = "192.168.0.20:8002"
+            date_format = "%d/%m/%Y"
+            temperature_unit = "Fahrenheit"
+            volume_cursor = "Blinking"
+            tick_interval = 0.5
+            read_timeout = 60
+
+            [messages]
+            no_connection = ["Waiting for", "the radio"]
+
+            [tag_order]
+            url_list = ["StationTitle"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.thermal_zone,
+            Path::new("/sys/class/thermal/thermal_zone1/temp")
+        );
+        assert_eq!(
+            config.wiring_pins_file,
+            Path::new("/etc/rradio/wiring_pins.toml")
+        );
+        assert_eq!(config.ssd1306_file, Path::new("/etc/rradio/ssd1306.toml"));
+        // Files which aren't mentioned keep their defaults
+        assert_eq!(
+            config.character_map_file,
+            Config::default().character_map_file
+        );
+        assert_eq!(
+            config.app.rradio_address,
+            "192.168.0.20:8002".parse().unwrap()
+        );
+        assert_eq!(config.app.date_format, "%d/%m/%Y");
+        assert_eq!(
+            config.app.temperature_unit,
+            app::TemperatureUnit::Fahrenheit
+        );
+        assert_eq!(config.app.volume_cursor, app::CursorStyle::Blinking);
+        assert_eq!(
+            config.app.tick_interval,
+            std::time::Duration::from_millis(500)
+        );
+        assert_eq!(config.app.read_timeout, std::time::Duration::from_secs(60));
+        assert_eq!(
+            config.app.messages.no_connection,
+            ["Waiting for", "the radio"]
+        );
+        // Fields missing from a table keep their defaults
+        assert_eq!(config.app.messages.ending, app::Messages::default().ending);
+        assert_eq!(
+            config.app.tag_order.url_list,
+            [app::StationTag::StationTitle]
+        );
+        assert_eq!(config.app.tag_order.cd, app::TagOrder::default().cd);
+    }
+
+    #[test]
+    fn secondary_screen_is_read() {
+        let config: Config = toml::from_str(
+            r#"
+            [secondary_screen]
+            date = false
+            temperature = false
+            "#,
+        )
+        .unwrap();
+
+        // Parts of the idle screen which aren't mentioned are shown
+        assert_eq!(
+            config.secondary_screen,
+            Some(app::view::IdleScreenOptions {
+                ping: true,
+                date: false,
+                clock: true,
+                temperature: false,
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let err = Config::parse(
+            r#"
+            tick_interval = 0.5
+            tick_intreval = 0.5
+            thermal_zone = "/sys/class/thermal/thermal_zone1/temp"
+            thermal_zoen = "/sys/class/thermal/thermal_zone1/temp"
+            "#,
+        )
+        .unwrap_err();
+
+        assert_eq!(err.to_string(), "Unknown keys: thermal_zoen, tick_intreval");
+
+        // Tables which belong to the application deny unknown keys themselves
+        assert!(Config::parse("[messages]\nnoconnection = [\"\", \"\"]").is_err());
+
+        assert!(Config::parse("tick_interval = 0.5").is_ok());
+    }
+
+    #[test]
+    fn missing_file_is_default() {
+        let config_file = Path::new("/nonexistent/config.toml");
+
+        let config = Config::load_from(config_file, true).unwrap();
+
+        assert_eq!(config.thermal_zone, Config::default().thermal_zone);
+
+        // A file given on the command line must exist
+        assert!(Config::load(Some(config_file)).is_err());
+    }
+
+    #[test]
+    fn invalid_durations_are_rejected() {
+        assert!(toml::from_str::<Config>("tick_interval = -1").is_err());
+        assert!(toml::from_str::<Config>("tick_interval = \"1s\"").is_err());
+    }
+}
@@ -0,0 +1,234 @@
+//! Choose which of the machine's IP addresses to show on the screen
+
+use std::net::IpAddr;
+
+use pnet::datalink::NetworkInterface;
+
+/// Set to the name of a network interface, e.g. "wlan0", to show its address rather than choosing one
+const INTERFACE_VARIABLE: &str = "RRADIO_SCREEN_INTERFACE";
+
+/// Interfaces created for containers and virtual machines, which aren't reachable from the LAN
+const VIRTUAL_INTERFACE_PREFIXES: &[&str] = &["docker", "veth", "br-", "virbr"];
+
+/// LAN interfaces, in order of preference
+const PREFERRED_INTERFACE_PREFIXES: &[&str] = &["eth", "en", "wlan", "wl"];
+
+/// Lower is better, and `None` means that the interface shouldn't be used
+fn interface_priority(name: &str) -> Option<usize> {
+    if VIRTUAL_INTERFACE_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+    {
+        return None;
+    }
+
+    Some(
+        PREFERRED_INTERFACE_PREFIXES
+            .iter()
+            .position(|prefix| name.starts_with(prefix))
+            .unwrap_or(PREFERRED_INTERFACE_PREFIXES.len()),
+    )
+}
+
+/// The width of the segment in which the IP address is shown on the idle screen
+const MAX_ADDRESS_LENGTH: usize = 13;
+
+/// Prefixes IPv6 addresses which have been shortened
+const SHORTENED_PREFIX: &str = "..";
+
+fn ipv4_address(interface: &NetworkInterface) -> Option<IpAddr> {
+    interface
+        .ips
+        .iter()
+        .find_map(|ip_network| match ip_network {
+            pnet::ipnetwork::IpNetwork::V4(addr) => Some(IpAddr::V4(addr.ip())),
+            pnet::ipnetwork::IpNetwork::V6(_) => None,
+        })
+}
+
+/// Link local addresses aren't reachable from other networks, so aren't shown
+fn is_link_local(addr: &std::net::Ipv6Addr) -> bool {
+    addr.segments()[0] & 0xFFC0 == 0xFE80
+}
+
+fn ipv6_address(interface: &NetworkInterface) -> Option<IpAddr> {
+    interface
+        .ips
+        .iter()
+        .find_map(|ip_network| match ip_network {
+            pnet::ipnetwork::IpNetwork::V4(_) => None,
+            pnet::ipnetwork::IpNetwork::V6(addr) => {
+                let addr = addr.ip();
+                (!is_link_local(&addr)).then_some(IpAddr::V6(addr))
+            }
+        })
+}
+
+/// Choose an IPv4 address if there is one, otherwise an IPv6 address, e.g. on an IPv6 only network
+fn choose_ip_address(
+    interfaces: &[NetworkInterface],
+    interface_override: Option<&str>,
+) -> Option<IpAddr> {
+    choose_interface_address(interfaces, interface_override, ipv4_address)
+        .or_else(|| choose_interface_address(interfaces, interface_override, ipv6_address))
+}
+
+/// Choose the address of the interface named `interface_override` if given,
+/// otherwise of the most preferred LAN interface, breaking ties by name so that the choice doesn't depend on the order of `interfaces`
+fn choose_interface_address(
+    interfaces: &[NetworkInterface],
+    interface_override: Option<&str>,
+    interface_address: fn(&NetworkInterface) -> Option<IpAddr>,
+) -> Option<IpAddr> {
+    let candidates = interfaces
+        .iter()
+        .filter(|interface| interface.is_up() && !interface.is_loopback())
+        .filter_map(|interface| Some((interface, interface_address(interface)?)));
+
+    match interface_override {
+        Some(name) => candidates
+            .filter(|(interface, _)| interface.name == name)
+            .map(|(_, address)| address)
+            .next(),
+        None => candidates
+            .filter_map(|(interface, address)| {
+                Some((
+                    interface_priority(&interface.name)?,
+                    &interface.name,
+                    address,
+                ))
+            })
+            .min()
+            .map(|(_, _, address)| address),
+    }
+}
+
+/// Format `addr` to fit on the idle screen. IPv4 addresses are shown as they were before IPv6 support,
+/// but long IPv6 addresses are shortened to their last groups, e.g. "..8a2e:7334"
+fn format_address(addr: IpAddr) -> String {
+    let text = addr.to_string();
+
+    if addr.is_ipv4() || text.len() <= MAX_ADDRESS_LENGTH {
+        return text;
+    }
+
+    let max_suffix_length = MAX_ADDRESS_LENGTH - SHORTENED_PREFIX.len();
+
+    let suffix = text
+        .match_indices(':')
+        .map(|(index, _)| &text[index + 1..])
+        .find(|suffix| suffix.len() <= max_suffix_length)
+        .unwrap_or(&text[text.len() - max_suffix_length..]);
+
+    format!("{}{}", SHORTENED_PREFIX, suffix)
+}
+
+pub fn local_ip_address() -> String {
+    let interface_override = std::env::var(INTERFACE_VARIABLE).ok();
+
+    choose_ip_address(&pnet::datalink::interfaces(), interface_override.as_deref())
+        .map_or_else(|| String::from("No IP Address"), format_address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interface(name: &str, address: &str) -> NetworkInterface {
+        NetworkInterface {
+            name: String::from(name),
+            description: String::new(),
+            index: 0,
+            mac: None,
+            ips: vec![address.parse().unwrap()],
+            flags: libc::IFF_UP as u32,
+        }
+    }
+
+    #[test]
+    fn ipv6_only_network() {
+        let mut eth0 = interface("eth0", "fe80::1/64");
+        eth0.ips.push("2001:db8::1234/64".parse().unwrap());
+
+        assert_eq!(
+            choose_ip_address(&[eth0.clone()], None),
+            Some("2001:db8::1234".parse().unwrap())
+        );
+
+        // IPv4 addresses are preferred
+        let wlan0 = interface("wlan0", "192.168.0.3/24");
+
+        assert_eq!(
+            choose_ip_address(&[eth0, wlan0], None),
+            Some("192.168.0.3".parse().unwrap())
+        );
+
+        // Link local addresses aren't shown
+        assert_eq!(
+            choose_ip_address(&[interface("eth0", "fe80::1/64")], None),
+            None
+        );
+    }
+
+    #[test]
+    fn address_formats() {
+        let format = |addr: &str| format_address(addr.parse().unwrap());
+
+        assert_eq!(format("192.168.100.200"), "192.168.100.200");
+        assert_eq!(format("2001:db8::12"), "2001:db8::12");
+        assert_eq!(format("2001:db8::1234"), "..db8::1234");
+        assert_eq!(format("2001:db8:85a3::8a2e:370:7334"), "..370:7334");
+        assert_eq!(
+            format("2001:db8:85a3:1234:5678:8a2e:370:7334"),
+            "..370:7334"
+        );
+    }
+
+    #[test]
+    fn prefers_lan_interfaces() {
+        let interfaces = [
+            interface("wlan0", "192.168.0.3/24"),
+            interface("docker0", "172.17.0.1/16"),
+            interface("eth0", "192.168.0.2/24"),
+            interface("veth1234", "172.17.0.2/16"),
+        ];
+
+        assert_eq!(
+            choose_ip_address(&interfaces, None),
+            Some("192.168.0.2".parse().unwrap())
+        );
+
+        assert_eq!(
+            choose_ip_address(&interfaces[..2], None),
+            Some("192.168.0.3".parse().unwrap())
+        );
+
+        assert_eq!(choose_ip_address(&interfaces[1..2], None), None);
+    }
+
+    #[test]
+    fn interface_override() {
+        let interfaces = [
+            interface("eth0", "192.168.0.2/24"),
+            interface("wlan0", "192.168.0.3/24"),
+        ];
+
+        assert_eq!(
+            choose_ip_address(&interfaces, Some("wlan0")),
+            Some("192.168.0.3".parse().unwrap())
+        );
+
+        assert_eq!(choose_ip_address(&interfaces, Some("eth1")), None);
+    }
+
+    #[test]
+    fn skips_down_and_loopback_interfaces() {
+        let mut down = interface("eth0", "192.168.0.2/24");
+        down.flags = 0;
+
+        let mut loopback = interface("lo", "127.0.0.1/8");
+        loopback.flags |= libc::IFF_LOOPBACK as u32;
+
+        assert_eq!(choose_ip_address(&[down, loopback], None), None);
+    }
+}
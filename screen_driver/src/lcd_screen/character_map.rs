@@ -0,0 +1,454 @@
+//! The mapping from characters to the character codes of the LCD screen's ROM and bespoke characters
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Context;
+
+/// The bespoke character which is a full block on the A02 ROM, which has no full block of its own.
+/// The A02 ROM has 'é', so the bespoke 'é' isn't needed
+const A02_FULL_BLOCK: u8 = 5;
+
+const FULL_BLOCK_PATTERN: [u8; 8] = [0b11111; 8];
+
+/// The character ROM of the display. See the look up tables in the HD44780U datasheet
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+pub enum RomVariant {
+    /// Japanese standard font, which includes katakana and a few accented Latin and Greek characters
+    #[default]
+    A00,
+    /// European standard font, which includes the Cyrillic capitals which don't resemble Latin capitals
+    A02,
+}
+
+impl RomVariant {
+    /// The character code of a full block, which is also used as a splodge for characters which can't be displayed
+    fn full_block(self) -> u8 {
+        match self {
+            RomVariant::A00 => 0xFF,
+            RomVariant::A02 => A02_FULL_BLOCK,
+        }
+    }
+}
+
+/// Cyrillic capitals which resemble Latin capitals, and those which are in the A02 ROM.
+/// As the ROM has no lowercase Cyrillic, lowercase letters are shown as capitals
+fn a02_cyrillic_character_code(c: char) -> Option<u8> {
+    let code = match c {
+        'А' | 'а' => b'A',
+        'Б' | 'б' => 0x80,
+        'В' | 'в' => b'B',
+        'Г' | 'г' => 0x92, // Shared with the Greek Gamma
+        'Д' | 'д' => 0x81,
+        'Е' | 'е' | 'Ё' | 'ё' => b'E',
+        'Ж' | 'ж' => 0x82,
+        'З' | 'з' => 0x83,
+        'И' | 'и' => 0x84,
+        'Й' | 'й' => 0x85,
+        'К' | 'к' => b'K',
+        'Л' | 'л' => 0x86,
+        'М' | 'м' => b'M',
+        'Н' | 'н' => b'H',
+        'О' | 'о' => b'O',
+        'П' | 'п' => 0x87,
+        'Р' | 'р' => b'P',
+        'С' | 'с' => b'C',
+        'Т' | 'т' => b'T',
+        'У' | 'у' => 0x88,
+        'Х' | 'х' => b'X',
+        'Ц' | 'ц' => 0x89,
+        'Ч' | 'ч' => 0x8A,
+        'Ш' | 'ш' => 0x8B,
+        'Щ' | 'щ' => 0x8C,
+        'Ъ' | 'ъ' => 0x8D,
+        'Ы' | 'ы' => 0x8E,
+        'Э' | 'э' => 0x8F,
+        'Ю' | 'ю' => 0xAC,
+        'Я' | 'я' => 0xAD,
+        _ => return None,
+    };
+
+    Some(code)
+}
+
+/// The A02 ROM has most of the Latin-1 supplement at the same codes as Latin-1.
+/// The exceptions are the symbols which it replaces with Cyrillic and Greek characters, and 'Ø' and 'ø', whose codes are 'Φ' and 'φ'
+fn a02_latin_1_character_code(c: char) -> Option<u8> {
+    match c {
+        '¡' | '¢' | '£' | '¤' | '¥' | '¦' | '§' | '©' | 'ª' | '«' | '®' | '°' | '±' | '²' | '³'
+        | 'µ' | '¶' | '·' | '¹' | 'º' | '»' | '¼' | '½' | '¾' | '¿' => Some(c as u8),
+        'Ø' | 'ø' => None,
+        '\u{C0}'..='\u{FF}' => Some(c as u8),
+        _ => None,
+    }
+}
+
+fn builtin_character_code(rom_variant: RomVariant, c: char) -> Option<u8> {
+    // The bar glyphs are the zeroeth to fourth bespoke characters
+    if let Some(glyph) = app::BarGlyph::from_char(c) {
+        return Some(glyph.column());
+    }
+
+    if c == '█' {
+        return Some(rom_variant.full_block()); // full block, as drawn by progress bars
+    }
+
+    let rom_character_code = match rom_variant {
+        RomVariant::A00 => match c {
+            'é' => Some(5), // e accute fifth bespoke character defined starting with the zeroeth bespoke character
+            'è' => Some(6), // e grave
+            'à' => Some(7), // a grave
+            'ä' => Some(0xE1), // a umlaut        // see look up table in GDM2004D.pdf page 9/9
+            'ñ' => Some(0xEE), // n tilde
+            'ö' => Some(0xEF), // o umlaut++
+            'ü' => Some(0xF5), // u umlaut
+            'π' => Some(0xE4), // pi
+            'µ' => Some(0xF7), // mu
+            '~' => Some(0xF3), // cannot display tilde using the standard character set in GDM2004D.pdf. This is the best we can do.
+            '' => Some(0xFF), // <Control>  = 0x80 replaced by splodge
+            _ => None,
+        },
+        RomVariant::A02 => a02_cyrillic_character_code(c).or_else(|| a02_latin_1_character_code(c)),
+    };
+
+    rom_character_code.or(match c {
+        '\x00'..='\x7F' => Some(c as u8),
+        _ => None,
+    })
+}
+
+/// The base ASCII letter of common accented Latin letters, so that text without a character code is still readable
+fn transliterate(c: char) -> Option<char> {
+    let base = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ą' => 'a',
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' | 'Ą' => 'A',
+        'ç' | 'ć' | 'č' => 'c',
+        'Ç' | 'Ć' | 'Č' => 'C',
+        'ď' | 'đ' => 'd',
+        'Ď' | 'Đ' => 'D',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' | 'ě' => 'e',
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ę' | 'Ě' => 'E',
+        'ğ' => 'g',
+        'Ğ' => 'G',
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'ı' => 'i',
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ī' | 'İ' => 'I',
+        'ł' => 'l',
+        'Ł' => 'L',
+        'ñ' | 'ń' | 'ň' => 'n',
+        'Ñ' | 'Ń' | 'Ň' => 'N',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => 'o',
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' | 'Ő' => 'O',
+        'ř' => 'r',
+        'Ř' => 'R',
+        'ś' | 'š' | 'ş' => 's',
+        'Ś' | 'Š' | 'Ş' => 'S',
+        'ť' | 'ţ' => 't',
+        'Ť' | 'Ţ' => 'T',
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' => 'u',
+        'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ū' | 'Ů' | 'Ű' => 'U',
+        'ý' | 'ÿ' => 'y',
+        'Ý' | 'Ÿ' => 'Y',
+        'ź' | 'ż' | 'ž' => 'z',
+        'Ź' | 'Ż' | 'Ž' => 'Z',
+        _ => return None,
+    };
+
+    Some(base)
+}
+
+#[derive(Default, serde::Deserialize)]
+struct CharacterMapFile {
+    #[serde(default)]
+    rom_variant: RomVariant,
+    #[serde(default)]
+    characters: HashMap<String, u8>,
+    /// Character codes given at the top level, as in files written before the ROM variant could be chosen
+    #[serde(flatten)]
+    top_level_characters: HashMap<String, u8>,
+}
+
+/// Parse a TOML file of the ROM variant and additional mappings from single characters to character codes, e.g.
+///
+/// ```toml
+/// rom_variant = "A02"
+///
+/// [characters]
+/// "ß" = 0xE2
+/// ```
+///
+/// Files which only map characters, without a `[characters]` table, such as `"ß" = 0xE2`, are also accepted
+fn parse_character_map_file(src: &str) -> anyhow::Result<(RomVariant, HashMap<char, u8>)> {
+    let CharacterMapFile {
+        rom_variant,
+        characters,
+        top_level_characters,
+    } = toml::from_str(src).context(
+        "Expected an optional rom_variant of \"A00\" or \"A02\", and a [characters] table mapping single characters to character codes",
+    )?;
+
+    let additional_character_codes = top_level_characters
+        .into_iter()
+        .chain(characters)
+        .map(|(key, code)| {
+            let mut chars = key.chars();
+
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok((c, code)),
+                _ => Err(anyhow::anyhow!("{:?} is not a single character", key)),
+            }
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok((rom_variant, additional_character_codes))
+}
+
+pub struct CharacterMap {
+    rom_variant: RomVariant,
+    additional_character_codes: HashMap<char, u8>,
+    unmapped_characters: HashSet<char>,
+}
+
+impl CharacterMap {
+    /// Load the ROM variant and additional character codes from `path`, which take precedence over the built-in character codes.
+    /// If `path` doesn't exist, only the built-in character codes of the A00 ROM are used
+    pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        let (rom_variant, additional_character_codes) = match std::fs::read_to_string(path) {
+            Ok(src) => parse_character_map_file(&src).with_context(|| {
+                format!("Failed to parse character map file {}", path.display())
+            })?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Default::default(),
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("Failed to read character map file {}", path.display())
+                })
+            }
+        };
+
+        log::info!(
+            "ROM variant {:?}, {} additional character codes",
+            rom_variant,
+            additional_character_codes.len()
+        );
+
+        Ok(Self {
+            rom_variant,
+            additional_character_codes,
+            unmapped_characters: HashSet::new(),
+        })
+    }
+
+    /// The character code of `c`, or of its transliteration, or `None` if it has neither
+    fn mapped_character_code(&self, c: char) -> Option<u8> {
+        if let Some(&code) = self.additional_character_codes.get(&c) {
+            return Some(code);
+        }
+
+        builtin_character_code(self.rom_variant, c).or_else(|| {
+            transliterate(c).and_then(|base| builtin_character_code(self.rom_variant, base))
+        })
+    }
+
+    /// The bespoke character patterns to use in place of `patterns`, which are the patterns for the A00 ROM.
+    /// The A02 ROM has no full block, so a bespoke character is used, in place of the bespoke 'é' which the ROM doesn't need
+    pub fn character_patterns(&self, mut patterns: [[u8; 8]; 8]) -> [[u8; 8]; 8] {
+        if self.rom_variant == RomVariant::A02 {
+            patterns[usize::from(A02_FULL_BLOCK)] = FULL_BLOCK_PATTERN;
+        }
+
+        patterns
+    }
+
+    /// Whether `c` is shown as itself or its transliteration, rather than as a splodge
+    pub fn can_render(&self, c: char) -> bool {
+        self.mapped_character_code(c).is_some()
+    }
+
+    pub fn character_code(&mut self, c: char) -> u8 {
+        self.mapped_character_code(c).unwrap_or_else(|| {
+            if self.unmapped_characters.insert(c) {
+                log::warn!("No character code for {:?} ({:?})", c, c.escape_unicode());
+            }
+
+            self.rom_variant.full_block()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn character_map(src: &str) -> CharacterMap {
+        let (rom_variant, additional_character_codes) = parse_character_map_file(src).unwrap();
+
+        CharacterMap {
+            rom_variant,
+            additional_character_codes,
+            unmapped_characters: HashSet::new(),
+        }
+    }
+
+    #[test]
+    fn additional_character_codes_override_builtin() {
+        let mut character_map = character_map("[characters]\n\"ß\" = 0xE2\n\"~\" = 0x2D\n");
+
+        assert_eq!(character_map.character_code('ß'), 0xE2);
+        assert_eq!(character_map.character_code('~'), 0x2D);
+        assert_eq!(character_map.character_code('é'), 5);
+        assert_eq!(character_map.character_code('A'), b'A');
+        assert_eq!(character_map.character_code('€'), 0xFF);
+        assert!(character_map.unmapped_characters.contains(&'€'));
+    }
+
+    #[test]
+    fn keys_must_be_single_characters() {
+        assert!(parse_character_map_file("[characters]\n\"ab\" = 0x41").is_err());
+        assert!(parse_character_map_file("[characters]\n\"a\" = 256").is_err());
+    }
+
+    #[test]
+    fn cyrillic_requires_a02_rom() {
+        let mut a00 = character_map("");
+        let mut a02 = character_map("rom_variant = \"A02\"");
+
+        assert_eq!(a00.character_code('Ж'), 0xFF);
+        assert_eq!(a00.character_code('ö'), 0xEF);
+
+        assert_eq!(a02.character_code('Ж'), 0x82);
+        assert_eq!(a02.character_code('ж'), 0x82);
+        assert_eq!(a02.character_code('Р'), b'P');
+        assert_eq!(a02.character_code('é'), 0xE9);
+        assert_eq!(a02.character_code('~'), b'~');
+        assert_eq!(a02.character_code('ö'), 0xF6);
+        assert_eq!(a02.character_code('Ü'), 0xDC);
+        assert_eq!(a02.character_code('ß'), 0xDF);
+        assert_eq!(a02.character_code('ø'), b'o');
+    }
+
+    #[test]
+    fn top_level_character_codes_are_accepted() {
+        let mut character_map = character_map("\"ß\" = 0xE2\n\"~\" = 0x2D\n");
+
+        assert_eq!(character_map.rom_variant, RomVariant::A00);
+        assert_eq!(character_map.character_code('ß'), 0xE2);
+        assert_eq!(character_map.character_code('~'), 0x2D);
+
+        let error = parse_character_map_file("rom_variant = \"A01\"").unwrap_err();
+        assert!(
+            format!("{:#}", error).contains("[characters]"),
+            "{:#}",
+            error
+        );
+    }
+
+    #[test]
+    fn transliteration() {
+        let mut character_map = character_map("");
+
+        for &(c, code) in &[
+            ('ø', b'o'),
+            ('ç', b'c'),
+            ('Å', b'A'),
+            ('ł', b'l'),
+            ('Ž', b'Z'),
+            ('ö', 0xEF), // Has a character code, so isn't transliterated
+            ('é', 5),    // Bespoke character, so isn't transliterated
+            ('ß', 0xFF),
+        ] {
+            assert_eq!(character_map.character_code(c), code, "{:?}", c);
+        }
+
+        assert!(!character_map.unmapped_characters.contains(&'ø'));
+        assert!(character_map.unmapped_characters.contains(&'ß'));
+    }
+
+    #[test]
+    fn a00_transliterates_cyrillic() {
+        use app::{CharacterDisplay, CursorPosition, Line, TextDisplay, WrappingTextDisplay};
+
+        /// Records the character codes written, as an LCD screen with the character map would
+        struct CodeRecorder {
+            character_map: CharacterMap,
+            codes: Vec<u8>,
+        }
+
+        impl CharacterDisplay for CodeRecorder {
+            fn clear(&mut self) {}
+
+            fn move_cursor(&mut self, _position: CursorPosition) {}
+
+            fn write_char(&mut self, c: char) {
+                let code = self.character_map.character_code(c);
+                self.codes.push(code);
+            }
+
+            fn can_render(&self, c: char) -> bool {
+                self.character_map.can_render(c)
+            }
+        }
+
+        let mut display = WrappingTextDisplay::new(CodeRecorder {
+            character_map: character_map(""),
+            codes: Vec::new(),
+        });
+
+        display.write_to(Line(0), "Жанна");
+
+        assert_eq!(
+            display.character_display().codes,
+            format!("{:20}", "Zhanna").as_bytes()
+        );
+    }
+
+    #[test]
+    fn can_render() {
+        let a00 = character_map("[characters]\n\"ß\" = 0xE2\n");
+        let a02 = character_map("rom_variant = \"A02\"");
+
+        for c in [
+            'A',
+            '~',
+            'é',
+            'ö',
+            'ø',
+            'ß',
+            '█',
+            app::BarGlyph::ALL[0].to_char(),
+        ] {
+            assert!(a00.can_render(c), "{:?}", c);
+        }
+
+        assert!(!a00.can_render('€'));
+        assert!(!a00.can_render('Ж'));
+
+        assert!(a02.can_render('Ж'));
+        assert!(a02.can_render('ß'));
+        assert!(a02.can_render('█'));
+        assert!(!a02.can_render('€'));
+    }
+
+    #[test]
+    fn a02_full_block_is_bespoke() {
+        use super::super::character_pattern::BITMAPS;
+
+        let mut a00 = character_map("");
+        let mut a02 = character_map("rom_variant = \"A02\"");
+
+        assert_eq!(a00.character_code('█'), 0xFF);
+        assert_eq!(a00.character_code('€'), 0xFF);
+
+        // 0xFF is 'ÿ' on the A02 ROM, so the full block, and the splodge, are the bespoke full block
+        assert_eq!(a02.character_code('ÿ'), 0xFF);
+        assert_eq!(a02.character_code('█'), A02_FULL_BLOCK);
+        assert_eq!(a02.character_code('€'), A02_FULL_BLOCK);
+
+        assert_eq!(a00.character_patterns(BITMAPS), BITMAPS);
+
+        let a02_patterns = a02.character_patterns(BITMAPS);
+
+        assert_eq!(
+            a02_patterns[usize::from(A02_FULL_BLOCK)],
+            FULL_BLOCK_PATTERN
+        );
+        assert_eq!(a02_patterns[..5], BITMAPS[..5]);
+    }
+}
@@ -1,3 +1,5 @@
+use anyhow::Context;
+
 pub const BITMAPS: [[u8; 8]; 8] = [
     [
         0b10000, //pattern for topmost row. the 1 specifies that the top left pixel is on, the other zeroes specify that the other topmost pixels are off
@@ -80,3 +82,75 @@ pub const BITMAPS: [[u8; 8]; 8] = [
         0b00000,
     ],
 ];
+
+/// The largest value of a row of a character pattern, as characters are 5 pixels wide
+const MAX_ROW: u8 = 0b11111;
+
+#[derive(serde::Deserialize)]
+struct CharacterPatternsFile {
+    bitmaps: Vec<[u8; 8]>,
+}
+
+/// Parse a TOML file of bitmaps, each of which is 8 rows of 5 bit patterns, e.g. `bitmaps = [[0b10000, 0b10000, ...]]`.
+/// The parsed bitmaps replace the built-in bitmaps, starting with the zeroeth bespoke character
+fn parse_bitmaps(src: &str) -> anyhow::Result<[[u8; 8]; 8]> {
+    let CharacterPatternsFile { bitmaps: patterns } = toml::from_str(src)?;
+
+    if patterns.len() > BITMAPS.len() {
+        anyhow::bail!(
+            "{} character patterns given, but only {} are supported",
+            patterns.len(),
+            BITMAPS.len()
+        );
+    }
+
+    for (index, pattern) in patterns.iter().enumerate() {
+        if let Some(row) = pattern.iter().find(|&&row| row > MAX_ROW) {
+            anyhow::bail!(
+                "Row {:#b} of character pattern {} is wider than 5 pixels",
+                row,
+                index
+            );
+        }
+    }
+
+    let mut bitmaps = BITMAPS;
+
+    bitmaps[..patterns.len()].copy_from_slice(&patterns);
+
+    Ok(bitmaps)
+}
+
+/// Load the character patterns from `path`. If `path` doesn't exist, the built-in [BITMAPS] are used
+pub fn load(path: &std::path::Path) -> anyhow::Result<[[u8; 8]; 8]> {
+    match std::fs::read_to_string(path) {
+        Ok(src) => parse_bitmaps(&src)
+            .with_context(|| format!("Failed to parse character patterns file {}", path.display())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(BITMAPS),
+        Err(err) => Err(err)
+            .with_context(|| format!("Failed to read character patterns file {}", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configured_bitmaps_replace_builtin_bitmaps() {
+        let bitmaps = parse_bitmaps("bitmaps = [[0, 1, 2, 3, 4, 5, 6, 0b11111]]").unwrap();
+
+        assert_eq!(bitmaps[0], [0, 1, 2, 3, 4, 5, 6, 0b11111]);
+        assert_eq!(bitmaps[1..], BITMAPS[1..]);
+    }
+
+    #[test]
+    fn invalid_bitmaps_are_rejected() {
+        assert!(parse_bitmaps("bitmaps = [[0, 0, 0, 0, 0, 0, 0, 0b100000]]").is_err());
+        assert!(parse_bitmaps(&format!(
+            "bitmaps = [{}]",
+            ["[0,0,0,0,0,0,0,0]"; 9].join(",")
+        ))
+        .is_err());
+    }
+}
@@ -0,0 +1,240 @@
+//! The mapping from rows of the screen to addresses in display data RAM, which differs between models of screen.
+//! Large screens, such as 40x4 screens, chain several controllers, each with its own enable pin, so each row also has a controller
+
+/// In 2 line mode, display data RAM is two lines of 40 characters, the second starting at 0x40
+const DDRAM_LINE_LENGTH: u8 = 40;
+const SECOND_DDRAM_LINE_ADDRESS: u8 = 0x40;
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(default)]
+pub struct DdramLayout {
+    /// The address in display data RAM of the start of each row. Screens with more rows just have more entries
+    pub row_offsets: Vec<u8>,
+    /// The controller which drives each row, as an index into the enable pins.
+    /// Rows without an entry are driven by the first controller
+    pub row_controllers: Vec<usize>,
+    pub characters_per_line: u8,
+}
+
+impl Default for DdramLayout {
+    fn default() -> Self {
+        const NUM_CHARACTERS_PER_LINE: u8 = app::SCREEN_WIDTH;
+        const ROW_OFFSET: u8 = 0x40;
+
+        Self {
+            row_offsets: vec![
+                0,
+                ROW_OFFSET,
+                NUM_CHARACTERS_PER_LINE,
+                ROW_OFFSET + NUM_CHARACTERS_PER_LINE,
+            ],
+            row_controllers: Vec::new(),
+            characters_per_line: NUM_CHARACTERS_PER_LINE,
+        }
+    }
+}
+
+/// A position in the display data RAM of one of the controllers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DdramAddress {
+    pub controller: usize,
+    pub address: u8,
+}
+
+impl DdramLayout {
+    fn row_controller(&self, row: usize) -> usize {
+        self.row_controllers.get(row).copied().unwrap_or(0)
+    }
+
+    /// Check that each row is wide enough for the application, that each row lies within one of the lines of display data RAM
+    /// of one of the `controller_count` controllers, and that no two rows of the same controller overlap
+    pub fn validate(&self, controller_count: usize) -> anyhow::Result<()> {
+        anyhow::ensure!(!self.row_offsets.is_empty(), "No rows are declared");
+
+        // Otherwise the application's last columns would be written into the display data RAM of other rows
+        anyhow::ensure!(
+            self.characters_per_line >= app::SCREEN_WIDTH,
+            "{} characters per line is fewer than the {} columns which the application writes",
+            self.characters_per_line,
+            app::SCREEN_WIDTH
+        );
+
+        anyhow::ensure!(
+            self.row_controllers.len() <= self.row_offsets.len(),
+            "{} row controllers are declared for {} rows",
+            self.row_controllers.len(),
+            self.row_offsets.len()
+        );
+
+        let rows = self
+            .row_offsets
+            .iter()
+            .enumerate()
+            .map(|(index, &offset)| {
+                let end = offset
+                    .checked_add(self.characters_per_line)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Row {} ({:#04X} plus {}) overflows display data RAM",
+                            index,
+                            offset,
+                            self.characters_per_line
+                        )
+                    })?;
+
+                Ok((self.row_controller(index), offset..end))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        for (index, (controller, row)) in rows.iter().cloned().enumerate() {
+            if controller >= controller_count {
+                anyhow::bail!(
+                    "Row {} is driven by controller {}, but only {} controllers have enable pins",
+                    index,
+                    controller,
+                    controller_count
+                );
+            }
+
+            let line_start = if row.start >= SECOND_DDRAM_LINE_ADDRESS {
+                SECOND_DDRAM_LINE_ADDRESS
+            } else {
+                0
+            };
+
+            if row.end > line_start + DDRAM_LINE_LENGTH {
+                anyhow::bail!(
+                    "Row {} ({:#04X}..{:#04X}) doesn't fit in display data RAM",
+                    index,
+                    row.start,
+                    row.end
+                );
+            }
+
+            if let Some(other_index) = rows[..index].iter().position(|(other_controller, other)| {
+                *other_controller == controller && other.start < row.end && row.start < other.end
+            }) {
+                anyhow::bail!("Rows {} and {} overlap", other_index, index);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The address in display data RAM of the given position. Rows past the last are treated as the last row
+    pub fn address(&self, row: u8, column: u8) -> DdramAddress {
+        let row = usize::from(row).min(self.row_offsets.len() - 1);
+
+        DdramAddress {
+            controller: self.row_controller(row),
+            address: self.row_offsets[row] + column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(controller: usize, address: u8) -> DdramAddress {
+        DdramAddress {
+            controller,
+            address,
+        }
+    }
+
+    #[test]
+    fn default_layout() {
+        let layout = DdramLayout::default();
+
+        layout.validate(1).unwrap();
+
+        assert_eq!(layout.address(0, 0), address(0, 0x00));
+        assert_eq!(layout.address(1, 5), address(0, 0x45));
+        assert_eq!(layout.address(2, 0), address(0, 0x14));
+        assert_eq!(layout.address(3, 19), address(0, 0x67));
+    }
+
+    #[test]
+    fn chained_controllers() {
+        // A 40x4 screen, where each controller drives two rows
+        let layout = DdramLayout {
+            row_offsets: vec![0x00, 0x40, 0x00, 0x40],
+            row_controllers: vec![0, 0, 1, 1],
+            characters_per_line: 40,
+        };
+
+        layout.validate(2).unwrap();
+        assert!(layout.validate(1).is_err());
+
+        assert_eq!(layout.address(1, 39), address(0, 0x67));
+        assert_eq!(layout.address(2, 0), address(1, 0x00));
+        assert_eq!(layout.address(3, 5), address(1, 0x45));
+    }
+
+    #[test]
+    fn more_rows() {
+        let layout = DdramLayout {
+            row_offsets: vec![0x00, 0x40, 0x14, 0x54, 0x00, 0x40, 0x14, 0x54],
+            row_controllers: vec![0, 0, 0, 0, 1, 1, 1, 1],
+            characters_per_line: 20,
+        };
+
+        layout.validate(2).unwrap();
+
+        assert_eq!(layout.address(7, 1), address(1, 0x55));
+        assert_eq!(layout.address(8, 1), address(1, 0x55));
+    }
+
+    #[test]
+    fn invalid_layouts() {
+        let overlapping = DdramLayout {
+            row_offsets: vec![0x00, 0x40, 0x10, 0x54],
+            row_controllers: Vec::new(),
+            characters_per_line: 20,
+        };
+
+        let too_long = DdramLayout {
+            row_offsets: vec![0x00, 0x40, 0x14, 0x54],
+            row_controllers: Vec::new(),
+            characters_per_line: 21,
+        };
+
+        let empty = DdramLayout {
+            row_offsets: Vec::new(),
+            row_controllers: Vec::new(),
+            characters_per_line: 20,
+        };
+
+        assert!(overlapping.validate(1).is_err());
+        assert!(too_long.validate(1).is_err());
+        assert!(empty.validate(1).is_err());
+    }
+
+    #[test]
+    fn narrow_layout_is_rejected() {
+        // A 16x4 screen
+        let layout = DdramLayout {
+            row_offsets: vec![0x00, 0x40, 0x10, 0x50],
+            row_controllers: Vec::new(),
+            characters_per_line: 16,
+        };
+
+        let error = layout.validate(1).unwrap_err();
+
+        assert!(error.to_string().contains("16 characters"), "{}", error);
+    }
+
+    #[test]
+    fn overflowing_offset_is_rejected() {
+        let layout = DdramLayout {
+            row_offsets: vec![0x00, 0xE0],
+            row_controllers: Vec::new(),
+            characters_per_line: 40,
+        };
+
+        let error = layout.validate(1).unwrap_err();
+
+        assert!(error.to_string().contains("Row 1"), "{}", error);
+    }
+}
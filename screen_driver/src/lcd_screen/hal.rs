@@ -34,6 +34,78 @@ impl clerk::DisplayHardwareLayer for Line {
     }
 }
 
+/// Selects which of several chained controllers receives commands and data, or all of them if `None`
+#[derive(Clone, Default)]
+pub struct EnableSelector(std::rc::Rc<std::cell::Cell<Option<usize>>>);
+
+impl EnableSelector {
+    pub fn select(&self, controller: Option<usize>) {
+        self.0.set(controller);
+    }
+
+    /// The selected controller, or `None` if all controllers are selected
+    pub fn selected(&self) -> Option<usize> {
+        self.0.get()
+    }
+}
+
+/// The enable pins of each controller. Only the selected controller is enabled, so that the others ignore what's sent
+pub struct EnableLines {
+    lines: Vec<Line>,
+    selector: EnableSelector,
+}
+
+impl EnableLines {
+    /// Create the enable pins, initially with all controllers selected so that they can be initialised together
+    pub fn new(lines: Vec<Line>) -> (Self, EnableSelector) {
+        let selector = EnableSelector::default();
+
+        (
+            Self {
+                lines,
+                selector: selector.clone(),
+            },
+            selector,
+        )
+    }
+
+    pub fn count(&self) -> usize {
+        self.lines.len()
+    }
+}
+
+impl clerk::DisplayHardwareLayer for EnableLines {
+    fn set_level(&self, level: clerk::Level) {
+        // clerk::Level isn't Copy, so recreate it for each line
+        let is_high = matches!(level, clerk::Level::High);
+        let level = || {
+            if is_high {
+                clerk::Level::High
+            } else {
+                clerk::Level::Low
+            }
+        };
+
+        match self.selector.0.get() {
+            Some(controller) => {
+                if let Some(line) = self.lines.get(controller) {
+                    line.set_level(level());
+                }
+            }
+            None => {
+                for line in &self.lines {
+                    line.set_level(level());
+                }
+            }
+        }
+    }
+    fn set_direction(&self, _direction: clerk::Direction) {}
+
+    fn get_value(&self) -> u8 {
+        0
+    }
+}
+
 pub struct Delay;
 
 impl clerk::Delay for Delay {
@@ -46,3 +118,46 @@ impl clerk::Delay for Delay {
         std::thread::sleep(std::time::Duration::from_nanos(u64::from(ns)));
     }
 }
+
+/// A connection which uses all 8 data lines.
+/// clerk's connection with 8 data lines doesn't support reading, which clerk::Display requires, so this is used instead.
+/// As with the 4 data line connection, the read line isn't wired, so nothing can be read
+pub struct EightBitConnection {
+    pub register_select: Line,
+    pub enable: EnableLines,
+    pub data: [Line; 8],
+}
+
+impl clerk::Init for EightBitConnection {
+    fn init(&self) {}
+}
+
+impl clerk::Send for EightBitConnection {
+    fn send(&self, mode: clerk::WriteMode) {
+        use clerk::{Delay as _, DisplayHardwareLayer};
+
+        let (level, byte) = mode.into();
+        self.register_select.set_level(level);
+
+        Delay::delay_ns(Delay::ADDRESS_SETUP_TIME);
+        self.enable.set_level(clerk::Level::High);
+
+        for (bit, line) in self.data.iter().enumerate() {
+            line.set_level(if byte & (1 << bit) == 0 {
+                clerk::Level::Low
+            } else {
+                clerk::Level::High
+            });
+        }
+
+        Delay::delay_ns(Delay::ENABLE_PULSE_WIDTH);
+        self.enable.set_level(clerk::Level::Low);
+        Delay::delay_ns(Delay::DATA_HOLD_TIME);
+    }
+}
+
+impl clerk::Receive for EightBitConnection {
+    fn receive(&self, _mode: clerk::ReadMode) -> u8 {
+        0
+    }
+}
@@ -1,150 +1,435 @@
 use anyhow::Context;
 
+mod character_map;
 mod character_pattern;
+mod ddram_layout;
 mod hal;
+mod pwm;
 
-pub type ClerkDisplay = clerk::Display<
+pub type ClerkDisplay4Bit = clerk::Display<
     clerk::ParallelConnection<
         hal::Line,
         hal::FakeLine,
-        hal::Line,
+        hal::EnableLines,
         clerk::DataPins4Lines<hal::Line, hal::Line, hal::Line, hal::Line>,
         hal::Delay,
     >,
     clerk::DefaultLines,
 >;
 
+pub type ClerkDisplay8Bit = clerk::Display<hal::EightBitConnection, clerk::DefaultLines>;
+
+/// The display, wired with either 4 or 8 data lines
+pub enum ClerkDisplay {
+    FourBit(ClerkDisplay4Bit),
+    EightBit(ClerkDisplay8Bit),
+}
+
+impl ClerkDisplay {
+    fn clear(&self) {
+        match self {
+            ClerkDisplay::FourBit(lcd) => lcd.clear(),
+            ClerkDisplay::EightBit(lcd) => lcd.clear(),
+        }
+    }
+
+    fn seek(&mut self, position: clerk::SeekFrom<clerk::DefaultLines>) {
+        match self {
+            ClerkDisplay::FourBit(lcd) => lcd.seek(position),
+            ClerkDisplay::EightBit(lcd) => lcd.seek(position),
+        }
+    }
+
+    fn seek_cgram(&mut self, position: clerk::SeekFrom<clerk::DefaultLines>) {
+        match self {
+            ClerkDisplay::FourBit(lcd) => lcd.seek_cgram(position),
+            ClerkDisplay::EightBit(lcd) => lcd.seek_cgram(position),
+        }
+    }
+
+    fn set_display_control(&self, builder: &mut clerk::DisplayControlBuilder) {
+        match self {
+            ClerkDisplay::FourBit(lcd) => lcd.set_display_control(builder),
+            ClerkDisplay::EightBit(lcd) => lcd.set_display_control(builder),
+        }
+    }
+
+    fn write(&mut self, code: u8) {
+        match self {
+            ClerkDisplay::FourBit(lcd) => lcd.write(code),
+            ClerkDisplay::EightBit(lcd) => lcd.write(code),
+        }
+    }
+}
+
+/// Set to the path of the GPIO pin declarations file, e.g. on systems where `/boot` is absent or read-only
+const WIRING_PINS_FILE_VARIABLE: &str = "WIRING_PINS_FILE";
+
 pub struct LcdScreen {
     lcd: ClerkDisplay,
+    /// Selects the controller which drives the row being written to
+    controller_selector: hal::EnableSelector,
+    character_map: character_map::CharacterMap,
+    ddram_layout: ddram_layout::DdramLayout,
+    brightness: Option<pwm::PwmChannel>,
+    contrast: Option<pwm::PwmChannel>,
 }
 
 impl LcdScreen {
-    pub fn new() -> anyhow::Result<Self> {
-        let wiring_pins_file = "/boot/wiring_pins.toml";
-        let pins_src = std::fs::read_to_string(wiring_pins_file).with_context(|| {
-            format!(
-                "Failed to read GPIO pin declarations file {}",
-                wiring_pins_file
-            )
-        })?;
+    /// Create the screen wired as declared in the configured wiring pins file, or the file named by the `WIRING_PINS_FILE` environment variable if set
+    pub fn new(config: &crate::config::Config) -> anyhow::Result<Self> {
+        let wiring_pins_file = std::env::var_os(WIRING_PINS_FILE_VARIABLE)
+            .map_or_else(|| config.wiring_pins_file.clone(), std::path::PathBuf::from);
+
+        let pins = PinDeclarations::load(&wiring_pins_file)?;
+
+        let character_map = character_map::CharacterMap::load(&config.character_map_file)?;
+
+        let character_bitmaps = character_map
+            .character_patterns(character_pattern::load(&config.character_patterns_file)?);
 
-        let pins: PinDeclarations =
-            toml::from_str(&pins_src).context("Failed to parse GPIO pin declarations file")?;
         log::info!("GPIO pins {:?}", pins);
-        let mut chip = gpio_cdev::Chip::new("/dev/gpiochip0")
-            .context("Failed to open GPIO character device")?; // no delay needed here
-        let mut lcd = pins
+        let mut chip = gpio_cdev::Chip::new(&pins.chip).with_context(|| {
+            format!(
+                "Failed to open GPIO character device {}",
+                pins.chip.display()
+            )
+        })?; // no delay needed here
+        pins.validate_line_count(chip.num_lines())
+            .with_context(|| format!("Invalid GPIO pins for {}", pins.chip.display()))?;
+        let (mut lcd, controller_selector) = pins
             .create_display(&mut chip)
             .context("Could not create display")?;
 
+        // All controllers are selected, so every controller gets the same character patterns
         lcd.seek_cgram(clerk::SeekFrom::Home(0)); // Seek to character generator RAM, i.e. update the character patterns
-        for character_bitmap in &character_pattern::BITMAPS {
+        for character_bitmap in &character_bitmaps {
             for row in character_bitmap {
                 lcd.write(*row);
             }
         }
         lcd.seek(clerk::SeekFrom::Home(0)); // Seek to display data RAM, i.e. reset the cursor
 
-        Ok(Self { lcd })
+        let brightness = pins
+            .brightness_pwm
+            .as_ref()
+            .map(pwm::PwmChannel::new)
+            .transpose()
+            .context("Failed to set up brightness PWM")?;
+
+        let contrast = pins
+            .contrast_pwm
+            .as_ref()
+            .map(pwm::PwmChannel::new)
+            .transpose()
+            .context("Failed to set up contrast PWM")?;
+
+        Ok(Self {
+            lcd,
+            controller_selector,
+            character_map,
+            ddram_layout: pins.ddram_layout,
+            brightness,
+            contrast,
+        })
     }
 }
 
 impl app::CharacterDisplay for LcdScreen {
     fn clear(&mut self) {
+        self.controller_selector.select(None);
         self.lcd.clear();
         std::thread::sleep(std::time::Duration::from_millis(3));
     }
 
     fn move_cursor(&mut self, app::CursorPosition { row, column }: app::CursorPosition) {
-        const NUM_CHARACTERS_PER_LINE: u8 = 20;
-        const ROW_OFFSET: u8 = 0x40;
-
-        let line_start = match row {
-            0 => 0,
-            1 => ROW_OFFSET,
-            2 => NUM_CHARACTERS_PER_LINE,
-            _ => ROW_OFFSET + NUM_CHARACTERS_PER_LINE,
-        };
+        let ddram_layout::DdramAddress {
+            controller,
+            address,
+        } = self.ddram_layout.address(row, column);
 
-        self.lcd.seek(clerk::SeekFrom::Home(line_start + column));
+        self.controller_selector.select(Some(controller));
+        self.lcd.seek(clerk::SeekFrom::Home(address));
     }
 
     fn write_char(&mut self, c: char) {
-        let code = match c {
-            '\u{E000}' => 0,
-            '\u{E001}' => 1,
-            '\u{E002}' => 2,
-            '\u{E003}' => 3,
-            '\u{E004}' => 4,
-            'é' => 5, // e accute fifth bespoke character defined starting with the zeroeth bespoke character
-            'è' => 6, // e grave
-            'à' => 7, // a grave
-            'ä' => 0xE1, // a umlaut            // see look up table in GDM2004D.pdf page 9/9
-            'ñ' => 0xEE, // n tilde
-            'ö' => 0xEF, // o umlaut++
-            'ü' => 0xF5, // u umlaut
-            'π' => 0xE4, // pi
-            'µ' => 0xF7, // mu
-            '~' => 0xF3, // cannot display tilde using the standard character set in GDM2004D.pdf. This is the best we can do.
-            '' => 0xFF, // <Control>  = 0x80 replaced by splodge
-            '\x00'..='\x7F' => c as u8,
-            _ => 0xFF,
-        };
+        let code = self.character_map.character_code(c);
 
         self.lcd.write(code);
     }
+
+    fn can_render(&self, c: char) -> bool {
+        self.character_map.can_render(c)
+    }
+
+    fn set_brightness(&mut self, level: u8) {
+        set_pwm_level("brightness", self.brightness.as_ref(), level);
+    }
+
+    fn set_contrast(&mut self, level: u8) {
+        set_pwm_level("contrast", self.contrast.as_ref(), level);
+    }
+
+    fn set_cursor_style(&mut self, style: app::CursorStyle) {
+        let (cursor, blinking) = match style {
+            app::CursorStyle::Off => (clerk::CursorState::Off, clerk::CursorBlinking::Off),
+            app::CursorStyle::Underline => (clerk::CursorState::On, clerk::CursorBlinking::Off),
+            app::CursorStyle::Blinking => (clerk::CursorState::On, clerk::CursorBlinking::On),
+        };
+
+        // All controllers are selected, as only the controller with the cursor shows it.
+        // The controller which was being written to is then selected again, so that later writes don't go to every controller
+        let selected_controller = self.controller_selector.selected();
+
+        self.controller_selector.select(None);
+        self.lcd.set_display_control(
+            clerk::DisplayControlBuilder::default()
+                .set_cursor(cursor)
+                .set_cursor_blinking(blinking),
+        );
+
+        self.controller_selector.select(selected_controller);
+    }
+}
+
+fn set_pwm_level(name: &str, channel: Option<&pwm::PwmChannel>, level: u8) {
+    match channel {
+        Some(channel) => {
+            if let Err(err) = channel.set_level(level) {
+                log::error!("Failed to set {}: {:#}", name, err);
+            }
+        }
+        None => log::warn!("Cannot set {} as no PWM channel is declared", name),
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+enum DataMode {
+    #[default]
+    #[serde(rename = "4bit")]
+    FourBit,
+    #[serde(rename = "8bit")]
+    EightBit,
+}
+
+fn default_chip() -> std::path::PathBuf {
+    std::path::PathBuf::from("/dev/gpiochip0")
 }
 
 #[derive(Debug, serde::Deserialize)]
 struct PinDeclarations {
+    /// The GPIO character device which the pins are lines of, e.g. "/dev/gpiochip4" on a Raspberry Pi 5 with older kernels
+    #[serde(default = "default_chip")]
+    chip: std::path::PathBuf,
+    #[serde(default)]
+    mode: DataMode,
     rs: u32,     // Register Select
     enable: u32, // Also known as strobe and clock
+    #[serde(default)]
+    additional_enables: Vec<u32>, // The enable pins of chained controllers, e.g. the second controller of a 40x4 screen
+    data0: Option<u32>, // Only used in 8 bit mode
+    data1: Option<u32>,
+    data2: Option<u32>,
+    data3: Option<u32>,
     data4: u32,
     data5: u32,
     data6: u32,
     data7: u32,
+    #[serde(default)]
+    ddram_layout: ddram_layout::DdramLayout,
+    brightness_pwm: Option<pwm::PwmDeclaration>, // Drives the backlight
+    contrast_pwm: Option<pwm::PwmDeclaration>,   // Drives V0, via a low pass filter
 }
 impl PinDeclarations {
-    fn create_display(self, chip: &mut gpio_cdev::Chip) -> Result<ClerkDisplay, anyhow::Error> {
+    fn load(wiring_pins_file: &std::path::Path) -> anyhow::Result<Self> {
+        let pins_src = std::fs::read_to_string(wiring_pins_file).with_context(|| {
+            format!(
+                "Failed to read GPIO pin declarations file {}",
+                wiring_pins_file.display()
+            )
+        })?;
+
+        let pins: PinDeclarations =
+            toml::from_str(&pins_src).context("Failed to parse GPIO pin declarations file")?;
+
+        pins.validate().context("Invalid GPIO pin declarations")?;
+
+        pins.ddram_layout
+            .validate(1 + pins.additional_enables.len())
+            .context("Invalid display data RAM layout")?;
+
+        Ok(pins)
+    }
+
+    /// The name and number of each pin which is used in the declared mode
+    fn used_pins(&self) -> Vec<(String, u32)> {
+        let mut pins = vec![
+            (String::from("rs"), self.rs),
+            (String::from("enable"), self.enable),
+        ];
+
+        pins.extend(
+            self.additional_enables
+                .iter()
+                .enumerate()
+                .map(|(index, &pin)| (format!("additional_enables[{}]", index), pin)),
+        );
+
+        if let DataMode::EightBit = self.mode {
+            pins.extend(
+                [self.data0, self.data1, self.data2, self.data3]
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(index, pin)| Some((format!("data{}", index), pin?))),
+            );
+        }
+
+        pins.extend([
+            (String::from("data4"), self.data4),
+            (String::from("data5"), self.data5),
+            (String::from("data6"), self.data6),
+            (String::from("data7"), self.data7),
+        ]);
+
+        pins
+    }
+
+    /// Check that no two uses share a pin
+    fn validate(&self) -> anyhow::Result<()> {
+        let pins = self.used_pins();
+
+        for (index, (name, pin)) in pins.iter().enumerate() {
+            if let Some((other_name, _)) =
+                pins[..index].iter().find(|(_, other_pin)| other_pin == pin)
+            {
+                anyhow::bail!(
+                    "{:?} and {:?} are both declared as GPIO pin {}",
+                    other_name,
+                    name,
+                    pin
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that each pin exists on a GPIO chip with `line_count` lines, before any are requested
+    fn validate_line_count(&self, line_count: u32) -> anyhow::Result<()> {
+        for (name, pin) in self.used_pins() {
+            anyhow::ensure!(
+                pin < line_count,
+                "GPIO pin {} for {:?} doesn't exist, as pins are numbered from 0 to {}",
+                pin,
+                name,
+                line_count.saturating_sub(1)
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Create and initialise the display, returning it with the selector of the controller which is written to
+    fn create_display(
+        &self,
+        chip: &mut gpio_cdev::Chip,
+    ) -> Result<(ClerkDisplay, hal::EnableSelector), anyhow::Error> {
         let register_select = get_line(chip, self.rs, "register_select")?;
-        let read = hal::FakeLine;
-        let enable = get_line(chip, self.enable, "enable")?;
+        let enable_lines = std::iter::once(get_line(chip, self.enable, "enable"))
+            .chain(
+                self.additional_enables
+                    .iter()
+                    .map(|&offset| get_line(chip, offset, "additional_enable")),
+            )
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let (enable, controller_selector) = hal::EnableLines::new(enable_lines);
+        log::debug!("{} chained controllers", enable.count());
         let data4 = get_line(chip, self.data4, "data4")?;
         let data5 = get_line(chip, self.data5, "data5")?;
         let data6 = get_line(chip, self.data6, "data6")?;
         let data7 = get_line(chip, self.data7, "data7")?;
 
-        let pins = clerk::Pins {
-            register_select,
-            read,
-            enable,
-            data: clerk::DataPins4Lines {
-                data4,
-                data5,
-                data6,
-                data7,
-            },
-        };
+        match &self.mode {
+            DataMode::FourBit => {
+                let pins = clerk::Pins {
+                    register_select,
+                    read: hal::FakeLine,
+                    enable,
+                    data: clerk::DataPins4Lines {
+                        data4,
+                        data5,
+                        data6,
+                        data7,
+                    },
+                };
+
+                let lcd = clerk::Display::<_, clerk::DefaultLines>::new(
+                    pins.into_connection::<hal::Delay>(),
+                );
+
+                lcd.init(
+                    clerk::FunctionSetBuilder::default().set_line_number(clerk::LineNumber::Two),
+                ); // screen has 4 lines, but electrically, only 2
+                std::thread::sleep(std::time::Duration::from_millis(3)); // with this line commented out, screen goes blank, and cannot be written to subsequently
+                                                                         // 1.5 ms is marginal as 1.2ms does not work.
+
+                configure_display(&lcd);
+
+                Ok((ClerkDisplay::FourBit(lcd), controller_selector))
+            }
+            DataMode::EightBit => {
+                let mut get_optional_line = |offset: Option<u32>, consumer: &'static str| {
+                    get_line(
+                        chip,
+                        offset.with_context(|| {
+                            format!("GPIO pin for {:?} is required in 8bit mode", consumer)
+                        })?,
+                        consumer,
+                    )
+                };
+
+                let data0 = get_optional_line(self.data0, "data0")?;
+                let data1 = get_optional_line(self.data1, "data1")?;
+                let data2 = get_optional_line(self.data2, "data2")?;
+                let data3 = get_optional_line(self.data3, "data3")?;
 
-        let lcd =
-            clerk::Display::<_, clerk::DefaultLines>::new(pins.into_connection::<hal::Delay>());
+                let connection = hal::EightBitConnection {
+                    register_select,
+                    enable,
+                    data: [data0, data1, data2, data3, data4, data5, data6, data7],
+                };
 
-        lcd.init(clerk::FunctionSetBuilder::default().set_line_number(clerk::LineNumber::Two)); // screen has 4 lines, but electrically, only 2
-        std::thread::sleep(std::time::Duration::from_millis(3)); // with this line commented out, screen goes blank, and cannot be written to subsequently
-                                                                 // 1.5 ms is marginal as 1.2ms does not work.
+                // clerk's FunctionSetBuilder can't select 8 bit mode, so initialise by instruction as described in the HD44780U datasheet
+                clerk::Init::init(&connection);
+                for delay in [4100, 100, 100] {
+                    clerk::Send::send(&connection, clerk::WriteMode::Command(0b0011_0000)); // Function set: 8 bit mode
+                    std::thread::sleep(std::time::Duration::from_micros(delay));
+                }
+                clerk::Send::send(&connection, clerk::WriteMode::Command(0b0011_1000)); // Function set: 8 bit mode, 2 lines, 5x8 dots
+                std::thread::sleep(std::time::Duration::from_millis(3));
 
-        lcd.set_display_control(
-            clerk::DisplayControlBuilder::default() // defaults are display on cursor off blinking off ie cursor is an underscore
-                .set_cursor(clerk::CursorState::Off), // normally we want the cursor off
-        ); //no extra delay needed here
+                let lcd = clerk::Display::<_, clerk::DefaultLines>::new(connection);
 
-        lcd.clear();
-        std::thread::sleep(std::time::Duration::from_millis(2)); // if this line is commented out, garbage or nothing appears. 1ms is marginal
+                configure_display(&lcd);
 
-        Ok(lcd)
+                Ok((ClerkDisplay::EightBit(lcd), controller_selector))
+            }
+        }
     }
 }
 
+fn configure_display<P: clerk::Init + clerk::Send + clerk::Receive>(
+    lcd: &clerk::Display<P, clerk::DefaultLines>,
+) {
+    lcd.set_display_control(
+        clerk::DisplayControlBuilder::default() // defaults are display on cursor off blinking off ie cursor is an underscore
+            .set_cursor(clerk::CursorState::Off), // normally we want the cursor off
+    ); //no extra delay needed here
+
+    lcd.clear();
+    std::thread::sleep(std::time::Duration::from_millis(2)); // if this line is commented out, garbage or nothing appears. 1ms is marginal
+}
+
 fn get_line(
     chip: &mut gpio_cdev::Chip,
     offset: u32,
@@ -157,3 +442,106 @@ fn get_line(
         .with_context(|| format!("GPIO pin for {:?} already in use. Are you running another copy of the program elsewhere?", consumer))?;
     Ok(hal::Line::new(handle))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write `contents` to a fixture file, and load it
+    fn load_fixture(name: &str, contents: &str) -> anyhow::Result<PinDeclarations> {
+        let path = std::env::temp_dir().join(format!("rradio_wiring_pins_{}.toml", name));
+        std::fs::write(&path, contents).unwrap();
+
+        let pins = PinDeclarations::load(&path);
+
+        std::fs::remove_file(&path).unwrap();
+
+        pins
+    }
+
+    #[test]
+    fn load_wiring_pins() {
+        let pins = load_fixture(
+            "four_bit",
+            "rs = 7\nenable = 8\ndata4 = 25\ndata5 = 24\ndata6 = 23\ndata7 = 18\n",
+        )
+        .unwrap();
+
+        assert_eq!(pins.chip, std::path::Path::new("/dev/gpiochip0"));
+        assert!(matches!(pins.mode, DataMode::FourBit));
+        assert_eq!((pins.rs, pins.enable), (7, 8));
+        assert_eq!(
+            [pins.data4, pins.data5, pins.data6, pins.data7],
+            [25, 24, 23, 18]
+        );
+        assert!(pins.additional_enables.is_empty());
+    }
+
+    #[test]
+    fn gpio_chip() {
+        let pins = load_fixture(
+            "chip",
+            "chip = \"/dev/gpiochip4\"\nrs = 7\nenable = 8\ndata4 = 25\ndata5 = 24\ndata6 = 23\ndata7 = 18\n",
+        )
+        .unwrap();
+
+        assert_eq!(pins.chip, std::path::Path::new("/dev/gpiochip4"));
+    }
+
+    #[test]
+    fn pin_validation() {
+        let four_bit = "enable = 8\ndata4 = 25\ndata5 = 24\ndata6 = 23\ndata7 = 18\n";
+
+        let error =
+            |name, contents: &str| format!("{:#}", load_fixture(name, contents).unwrap_err());
+
+        assert!(error("duplicate", &format!("rs = 24\n{}", four_bit))
+            .ends_with("\"rs\" and \"data5\" are both declared as GPIO pin 24"));
+
+        let out_of_range = load_fixture("out_of_range", &format!("rs = 54\n{}", four_bit)).unwrap();
+        assert!(out_of_range.validate_line_count(58).is_ok());
+        assert_eq!(
+            out_of_range
+                .validate_line_count(54)
+                .unwrap_err()
+                .to_string(),
+            "GPIO pin 54 for \"rs\" doesn't exist, as pins are numbered from 0 to 53"
+        );
+
+        assert!(error(
+            "duplicate_enable",
+            &format!("rs = 7\nadditional_enables = [9, 8]\n{}", four_bit)
+        )
+        .ends_with("\"enable\" and \"additional_enables[1]\" are both declared as GPIO pin 8"));
+
+        // data0 to data3 are only used in 8 bit mode
+        let unused_data0 = format!("rs = 7\ndata0 = 8\n{}", four_bit);
+        assert!(load_fixture("unused_data0", &unused_data0).is_ok());
+        assert!(error(
+            "eight_bit_data0",
+            &format!("mode = \"8bit\"\n{}", unused_data0)
+        )
+        .ends_with("\"enable\" and \"data0\" are both declared as GPIO pin 8"));
+    }
+
+    #[test]
+    fn invalid_wiring_pins() {
+        // data7 is missing
+        assert!(load_fixture(
+            "missing_pin",
+            "rs = 7\nenable = 8\ndata4 = 25\ndata5 = 24\ndata6 = 23\n"
+        )
+        .is_err());
+
+        // The second controller has no enable pin
+        assert!(load_fixture(
+            "bad_layout",
+            "rs = 7\nenable = 8\ndata4 = 25\ndata5 = 24\ndata6 = 23\ndata7 = 18\n\n[ddram_layout]\nrow_controllers = [0, 1]\n",
+        )
+        .is_err());
+
+        assert!(
+            PinDeclarations::load(std::path::Path::new("/nonexistent/wiring_pins.toml")).is_err()
+        );
+    }
+}
@@ -0,0 +1,88 @@
+//! Drive a PWM channel using the Linux sysfs interface, to set the backlight brightness or the contrast
+
+use std::path::PathBuf;
+
+use anyhow::Context;
+
+fn default_period_ns() -> u32 {
+    1_000_000 // 1kHz
+}
+
+/// A PWM channel, as declared in the GPIO pin declarations file, e.g. `brightness_pwm = { chip = 0, channel = 0 }`
+#[derive(Debug, serde::Deserialize)]
+pub struct PwmDeclaration {
+    chip: u32,
+    channel: u32,
+    #[serde(default = "default_period_ns")]
+    period_ns: u32,
+}
+
+pub struct PwmChannel {
+    path: PathBuf,
+    period_ns: u32,
+}
+
+fn write_attribute(path: &std::path::Path, value: impl ToString) -> anyhow::Result<()> {
+    std::fs::write(path, value.to_string())
+        .with_context(|| format!("Failed to write to {}", path.display()))
+}
+
+impl PwmChannel {
+    /// Export and enable the declared PWM channel, with the output initially off
+    pub fn new(
+        &PwmDeclaration {
+            chip,
+            channel,
+            period_ns,
+        }: &PwmDeclaration,
+    ) -> anyhow::Result<Self> {
+        let chip_path = PathBuf::from(format!("/sys/class/pwm/pwmchip{}", chip));
+        let path = chip_path.join(format!("pwm{}", channel));
+
+        if !path.exists() {
+            write_attribute(&chip_path.join("export"), channel)
+                .context("Failed to export PWM channel")?;
+        }
+
+        // The duty cycle must never be longer than the period, so clear it before changing the period
+        write_attribute(&path.join("duty_cycle"), 0)?;
+        write_attribute(&path.join("period"), period_ns)?;
+        write_attribute(&path.join("enable"), 1)?;
+
+        Ok(Self { path, period_ns })
+    }
+
+    /// Set the duty cycle, where 0 is always off and 255 is always on
+    pub fn set_level(&self, level: u8) -> anyhow::Result<()> {
+        write_attribute(
+            &self.path.join("duty_cycle"),
+            duty_cycle_ns(self.period_ns, level),
+        )
+    }
+}
+
+fn duty_cycle_ns(period_ns: u32, level: u8) -> u32 {
+    (u64::from(period_ns) * u64::from(level) / u64::from(u8::MAX)) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duty_cycles() {
+        assert_eq!(duty_cycle_ns(1_000_000, 0), 0);
+        assert_eq!(duty_cycle_ns(1_000_000, 255), 1_000_000);
+        assert_eq!(duty_cycle_ns(u32::MAX, 255), u32::MAX);
+        assert_eq!(duty_cycle_ns(255_000, 51), 51_000);
+    }
+
+    #[test]
+    fn parse_declaration() {
+        let declaration: PwmDeclaration = toml::from_str("chip = 0\nchannel = 1").unwrap();
+
+        assert_eq!(declaration.chip, 0);
+        assert_eq!(declaration.channel, 1);
+        assert_eq!(declaration.period_ns, default_period_ns());
+    }
+}
@@ -0,0 +1,47 @@
+//! Log to stderr, which is captured by the systemd journal when running as a service
+
+/// Set to a level such as "debug" to show more detailed logs. Defaults to "info"
+const LOG_LEVEL_VARIABLE: &str = "RRADIO_SCREEN_LOG";
+
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{} {}] {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn parse_level(level: Option<&str>) -> log::LevelFilter {
+    level
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(log::LevelFilter::Info)
+}
+
+pub fn init() {
+    let level = parse_level(std::env::var(LOG_LEVEL_VARIABLE).ok().as_deref());
+
+    if log::set_logger(&StderrLogger).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_levels() {
+        assert_eq!(parse_level(None), log::LevelFilter::Info);
+        assert_eq!(parse_level(Some("debug")), log::LevelFilter::Debug);
+        assert_eq!(parse_level(Some("TRACE")), log::LevelFilter::Trace);
+        assert_eq!(parse_level(Some("nonsense")), log::LevelFilter::Info);
+    }
+}
@@ -1,25 +1,20 @@
+mod config;
+mod ip_address;
 mod lcd_screen;
+mod logger;
+#[cfg(feature = "mirror")]
+mod mirror;
+mod self_test;
+mod ssd1306_screen;
 
-pub fn local_ip_address() -> String {
-    pnet::datalink::interfaces()
-        .into_iter()
-        .filter(|interface| {
-            interface.is_up() && !interface.is_loopback() && interface.ips.len() > 0
-        })
-        .flat_map(|interface| interface.ips)
-        .filter_map(|ip_network| match ip_network {
-            pnet::ipnetwork::IpNetwork::V4(addr) => Some(addr.ip()),
-            pnet::ipnetwork::IpNetwork::V6(_) => None,
-        })
-        .last()
-        .map_or_else(|| String::from("No IP Address"), |addr| addr.to_string())
+/// The CPU temperature, read from the thermal zone pseudo-file
+pub struct CpuTemperature {
+    thermal_zone: std::path::PathBuf,
 }
 
-pub struct CpuTemperature;
-
 impl app::TemperatureSource for CpuTemperature {
     fn get_temperature(&mut self) -> app::Temperature {
-        let temp_milli_c: u32 = std::fs::read_to_string("/sys/class/thermal/thermal_zone0/temp")
+        let temp_milli_c: u32 = std::fs::read_to_string(&self.thermal_zone)
             .expect("Failed to open the CPU temperature pseudo-file")
             .trim()
             .parse()
@@ -33,8 +28,121 @@ impl app::TemperatureSource for CpuTemperature {
     }
 }
 
+/// Completes when the process receives SIGTERM, e.g. from systemd stopping the service, or SIGINT, e.g. from Ctrl+C
+fn shutdown_signal() -> anyhow::Result<impl std::future::Future<Output = ()>> {
+    use anyhow::Context;
+
+    let (receiver, sender) =
+        std::os::unix::net::UnixStream::pair().context("Failed to create signal pipe")?;
+
+    for signal in [signal_hook::consts::SIGTERM, signal_hook::consts::SIGINT] {
+        signal_hook::low_level::pipe::register(signal, sender.try_clone()?)
+            .with_context(|| format!("Failed to register handler for signal {}", signal))?;
+    }
+
+    let receiver = smol::Async::new(receiver).context("Failed to watch signal pipe")?;
+
+    Ok(async move {
+        // An error here means that the signal can't be received, so the process should keep running
+        if receiver.readable().await.is_err() {
+            smol::future::pending::<()>().await;
+        }
+    })
+}
+
+/// Run the application on `screen`, and on `secondary_screen` if given, until shutdown,
+/// the demo if `--demo` was passed, or the self-test if `--self-test` was passed. The demo and the self-test only use `screen`
+fn run<S: app::CharacterDisplay>(
+    args: &app::Args,
+    config: config::Config,
+    screen: impl app::CharacterDisplay,
+    secondary_screen: Option<S>,
+    shutdown: impl std::future::Future<Output = ()>,
+) {
+    let config::Config {
+        app: mut config,
+        thermal_zone,
+        secondary_screen: secondary_idle_screen,
+        ..
+    } = config;
+
+    args.apply(&mut config);
+
+    let cpu_temperature = CpuTemperature { thermal_zone };
+
+    #[cfg(feature = "mirror")]
+    let screen = mirror::MirroredScreen::from_env(screen);
+
+    if args.self_test {
+        let mut screen = screen;
+
+        self_test::run_with_backlight(
+            &mut screen,
+            self_test::PAGE_DURATION,
+            config.brightness.unwrap_or(u8::MAX),
+            config.contrast,
+        );
+    } else if args.demo {
+        app::run_demo(
+            config,
+            ip_address::local_ip_address(),
+            cpu_temperature,
+            app::LocalClock,
+            screen,
+            shutdown,
+        )
+    } else {
+        match secondary_screen.zip(secondary_idle_screen) {
+            Some((secondary_screen, secondary_idle_screen)) => app::run_with_secondary_display(
+                config,
+                ip_address::local_ip_address(),
+                cpu_temperature,
+                app::LocalClock,
+                screen,
+                secondary_screen,
+                secondary_idle_screen,
+                shutdown,
+            ),
+            None => app::run_until(
+                config,
+                ip_address::local_ip_address(),
+                cpu_temperature,
+                app::LocalClock,
+                screen,
+                shutdown,
+            ),
+        }
+    }
+}
+
 fn main() {
-    let screen = lcd_screen::LcdScreen::new().expect("Failed to create LCD screen");
+    logger::init();
+
+    let args = app::Args::from_env().unwrap_or_else(|err| {
+        eprintln!("{:#}", err);
+        std::process::exit(2)
+    });
 
-    app::run(local_ip_address(), CpuTemperature, screen);
+    let config = config::Config::load(args.config_file.as_deref()).expect("Failed to load config");
+
+    let shutdown = shutdown_signal().expect("Failed to handle signals");
+
+    let ssd1306_screen = ssd1306_screen::Ssd1306Screen::from_config_file(&config.ssd1306_file)
+        .expect("Failed to create SSD1306 screen");
+
+    match ssd1306_screen {
+        // Without a secondary screen configured, the SSD1306 screen replaces the LCD screen
+        Some(screen) if config.secondary_screen.is_none() => run(
+            &args,
+            config,
+            screen,
+            None::<ssd1306_screen::Ssd1306Screen>,
+            shutdown,
+        ),
+        ssd1306_screen => {
+            let screen = lcd_screen::LcdScreen::new(&config).expect("Failed to create LCD screen");
+
+            run(&args, config, screen, ssd1306_screen, shutdown);
+        }
+    }
 }
@@ -0,0 +1,211 @@
+//! Mirror the contents of the screen over the network, so that what's on the physical screen can be seen without being in the room.
+//! Whenever the contents change, the whole screen is sent as plain text in a UDP datagram, one line per row,
+//! which can be watched with e.g. `socat -u UDP-RECV:9999 STDOUT`
+
+use app::{CharacterDisplay, CursorPosition, CursorStyle, GlyphMap};
+
+/// Set to the address to send the screen contents to, e.g. "192.168.0.10:9999", or a broadcast address. If not set, the screen isn't mirrored
+const MIRROR_ADDRESS_VARIABLE: &str = "RRADIO_SCREEN_MIRROR";
+
+/// The size of the screen which the app draws
+const WIDTH: usize = 20;
+const HEIGHT: usize = 4;
+
+struct Mirror {
+    socket: std::net::UdpSocket,
+    address: std::net::SocketAddr,
+}
+
+impl Mirror {
+    fn new(address: &str) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let address = address
+            .parse()
+            .with_context(|| format!("Bad mirror address {:?}", address))?;
+
+        let socket = std::net::UdpSocket::bind(("0.0.0.0", 0)).context("Failed to bind socket")?;
+
+        socket
+            .set_broadcast(true)
+            .context("Failed to enable broadcast")?;
+
+        Ok(Self { socket, address })
+    }
+}
+
+/// Passes everything through to the inner display, and if mirroring is enabled, also keeps a copy of the screen to send whenever it changes
+pub struct MirroredScreen<D> {
+    inner: D,
+    mirror: Option<Mirror>,
+    characters: [[char; WIDTH]; HEIGHT],
+    cursor: CursorPosition,
+    has_changed: bool,
+}
+
+impl<D: CharacterDisplay> MirroredScreen<D> {
+    /// Mirror `inner` to the address in the `RRADIO_SCREEN_MIRROR` environment variable, if set
+    pub fn from_env(inner: D) -> Self {
+        let mirror = std::env::var(MIRROR_ADDRESS_VARIABLE)
+            .ok()
+            .and_then(|address| match Mirror::new(&address) {
+                Ok(mirror) => {
+                    log::info!("Mirroring the screen to {}", mirror.address);
+                    Some(mirror)
+                }
+                Err(err) => {
+                    // Mirroring is only for debugging, so isn't worth stopping the screen for
+                    log::error!("Not mirroring the screen: {:#}", err);
+                    None
+                }
+            });
+
+        Self {
+            inner,
+            mirror,
+            characters: [[' '; WIDTH]; HEIGHT],
+            cursor: CursorPosition { row: 0, column: 0 },
+            has_changed: true,
+        }
+    }
+
+    fn record_char(&mut self, c: char) {
+        let CursorPosition { row, column } = self.cursor;
+
+        if let Some(cell) = self
+            .characters
+            .get_mut(usize::from(row))
+            .and_then(|line| line.get_mut(usize::from(column)))
+        {
+            if *cell != c {
+                *cell = c;
+                self.has_changed = true;
+            }
+        }
+
+        self.cursor.column = self.cursor.column.saturating_add(1);
+    }
+
+    /// The screen as plain text, with the bar glyphs shown as the nearest ordinary characters
+    fn text(&self) -> String {
+        self.characters
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|&c| app::DefaultGlyphMap.map_char(c))
+                    .chain(std::iter::once('\n'))
+                    .collect::<String>()
+            })
+            .collect()
+    }
+}
+
+impl<D: CharacterDisplay> CharacterDisplay for MirroredScreen<D> {
+    fn clear(&mut self) {
+        self.characters = [[' '; WIDTH]; HEIGHT];
+        self.cursor = CursorPosition { row: 0, column: 0 };
+        self.has_changed = true;
+
+        self.inner.clear();
+    }
+
+    fn move_cursor(&mut self, position: CursorPosition) {
+        self.cursor = position;
+
+        self.inner.move_cursor(position);
+    }
+
+    fn write_char(&mut self, c: char) {
+        self.record_char(c);
+
+        self.inner.write_char(c);
+    }
+
+    fn write_str(&mut self, s: &str) {
+        for c in s.chars() {
+            self.record_char(c);
+        }
+
+        self.inner.write_str(s);
+    }
+
+    fn flush(&mut self) {
+        self.inner.flush();
+
+        if let Some(mirror) = &self.mirror {
+            if self.has_changed {
+                if let Err(err) = mirror
+                    .socket
+                    .send_to(self.text().as_bytes(), mirror.address)
+                {
+                    log::debug!("Failed to mirror the screen: {}", err);
+                }
+            }
+        }
+
+        self.has_changed = false;
+    }
+
+    fn set_brightness(&mut self, level: u8) {
+        self.inner.set_brightness(level);
+    }
+
+    fn set_contrast(&mut self, level: u8) {
+        self.inner.set_contrast(level);
+    }
+
+    fn set_cursor_style(&mut self, style: CursorStyle) {
+        self.inner.set_cursor_style(style);
+    }
+
+    fn can_render(&self, c: char) -> bool {
+        self.inner.can_render(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NullDisplay;
+
+    impl CharacterDisplay for NullDisplay {
+        fn clear(&mut self) {}
+        fn move_cursor(&mut self, _position: CursorPosition) {}
+        fn write_char(&mut self, _c: char) {}
+    }
+
+    #[test]
+    fn changed_frames_are_sent() {
+        let receiver = std::net::UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        receiver.set_nonblocking(true).unwrap();
+
+        let mut screen = MirroredScreen::from_env(NullDisplay);
+        screen.mirror = Some(Mirror::new(&receiver.local_addr().unwrap().to_string()).unwrap());
+
+        screen.clear();
+        screen.write_str("Hello");
+        screen.move_cursor(CursorPosition { row: 3, column: 18 });
+        screen.write_char(app::BarGlyph::Right.to_char());
+        screen.write_char('!');
+        screen.write_char('?'); // Off the edge of the screen
+        screen.flush();
+
+        // Nothing has changed, so nothing is sent
+        screen.move_cursor(CursorPosition { row: 0, column: 0 });
+        screen.write_str("Hello");
+        screen.flush();
+
+        std::thread::sleep(std::time::Duration::from_millis(50));
+
+        let mut buffer = [0; 1024];
+        let length = receiver.recv(&mut buffer).unwrap();
+
+        assert_eq!(
+            std::str::from_utf8(&buffer[..length]).unwrap(),
+            "Hello               \n                    \n                    \n                  ▐!\n"
+        );
+
+        assert!(receiver.recv(&mut buffer).is_err());
+    }
+}
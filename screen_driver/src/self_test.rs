@@ -0,0 +1,166 @@
+//! A self-test for checking a freshly wired screen, shown instead of the application when `--self-test` is passed.
+//! Each page is shown in turn, so that bad pins, missing character patterns, and misaligned rows are easy to spot
+
+use app::{BarGlyph, CharacterDisplay, CursorPosition};
+
+/// How long each page is shown for
+pub const PAGE_DURATION: std::time::Duration = std::time::Duration::from_secs(3);
+
+const COLUMNS: usize = 20;
+
+/// A row of the ruler, which has the row number at both ends and the column number, modulo 10, in between.
+/// Rows which start at the wrong address show the wrong row number, or are shifted along
+fn ruler_row(row: usize) -> String {
+    (0..COLUMNS)
+        .map(|column| {
+            let digit = if column == 0 || column == COLUMNS - 1 {
+                row
+            } else {
+                column % 10
+            };
+
+            char::from_digit(digit as u32, 10).unwrap()
+        })
+        .collect()
+}
+
+/// The pages of the self-test, each a line of text per row
+pub fn pages() -> Vec<[String; 4]> {
+    let filled = || "█".repeat(COLUMNS);
+
+    let bar_glyphs = BarGlyph::ALL
+        .iter()
+        .map(|glyph| glyph.to_char())
+        .collect::<String>();
+
+    vec![
+        // Every pixel is on, which shows up dead pixels, and characters which are never written to
+        [filled(), filled(), filled(), filled()],
+        [ruler_row(0), ruler_row(1), ruler_row(2), ruler_row(3)],
+        // The eight bespoke characters, the bar glyphs followed by the accented letters
+        [
+            String::from("Custom characters:"),
+            format!("{}éèà", bar_glyphs),
+            String::from("Bars:"),
+            bar_glyphs.chars().flat_map(|c| [c, ' ']).collect(),
+        ],
+        // Characters which come from the character ROM rather than ASCII
+        [
+            String::from("ROM characters:"),
+            String::from("äñöüπµ~"),
+            String::from("ABCxyz 0123456789"),
+            String::from("!\"#$%&'()*+,-./:;<=>"),
+        ],
+    ]
+}
+
+fn show_page(screen: &mut impl CharacterDisplay, page: &[String; 4]) {
+    screen.clear();
+
+    for (row, line) in (0..).zip(page) {
+        screen.move_cursor(CursorPosition { row, column: 0 });
+        screen.write_str(line);
+    }
+
+    screen.flush();
+}
+
+/// Show each page of the self-test for `page_duration`
+pub fn run(screen: &mut impl CharacterDisplay, page_duration: std::time::Duration) {
+    for page in pages() {
+        show_page(screen, &page);
+        std::thread::sleep(page_duration);
+    }
+}
+
+/// As [run], with the backlight at `brightness` and the contrast at `contrast` so that the pages can be seen,
+/// and then flash the backlight on and off, ending at `brightness`. Screens without backlight control don't flash
+pub fn run_with_backlight(
+    screen: &mut impl CharacterDisplay,
+    page_duration: std::time::Duration,
+    brightness: u8,
+    contrast: Option<u8>,
+) {
+    screen.set_brightness(brightness);
+
+    if let Some(contrast) = contrast {
+        screen.set_contrast(contrast);
+    }
+
+    run(screen, page_duration);
+
+    for level in [0, u8::MAX, 0, u8::MAX, 0, brightness] {
+        screen.set_brightness(level);
+        std::thread::sleep(page_duration / 6);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records each line written, and the brightness changes
+    #[derive(Default)]
+    struct Recorder {
+        lines: Vec<(u8, String)>,
+        brightness: Vec<u8>,
+        contrast: Vec<u8>,
+    }
+
+    impl CharacterDisplay for Recorder {
+        fn clear(&mut self) {}
+
+        fn move_cursor(&mut self, position: CursorPosition) {
+            assert_eq!(position.column, 0);
+            self.lines.push((position.row, String::new()));
+        }
+
+        fn write_char(&mut self, c: char) {
+            self.lines.last_mut().unwrap().1.push(c);
+        }
+
+        fn set_brightness(&mut self, level: u8) {
+            self.brightness.push(level);
+        }
+
+        fn set_contrast(&mut self, level: u8) {
+            self.contrast.push(level);
+        }
+    }
+
+    #[test]
+    fn every_line_fits() {
+        for page in pages() {
+            for line in &page {
+                assert!(line.chars().count() <= COLUMNS, "{:?}", line);
+            }
+        }
+    }
+
+    #[test]
+    fn ruler() {
+        assert_eq!(ruler_row(0), "01234567890123456780");
+        assert_eq!(ruler_row(3), "31234567890123456783");
+    }
+
+    #[test]
+    fn self_test_writes_every_page() {
+        let mut screen = Recorder::default();
+
+        run_with_backlight(&mut screen, std::time::Duration::ZERO, 200, Some(100));
+
+        let pages = pages();
+
+        assert_eq!(screen.lines.len(), 4 * pages.len());
+
+        for ((row, line), expected) in screen.lines.iter().zip(pages.iter().flatten()) {
+            assert!(*row < 4);
+            assert_eq!(line, expected);
+        }
+
+        // The pages are shown with the backlight on
+        assert_eq!(screen.brightness.first(), Some(&200));
+        assert_eq!(screen.brightness.last(), Some(&200));
+        assert_eq!(screen.contrast, [100]);
+    }
+}
@@ -0,0 +1,201 @@
+//! Drive a 128x32 SSD1306 OLED screen over I2C as if it were a 20x4 character display
+
+use std::{io::Write, os::unix::io::AsRawFd};
+
+use anyhow::Context;
+
+mod font;
+
+const ROWS: usize = 4;
+const COLUMNS: usize = 20;
+
+/// The width in pixels of a character, i.e. a 5 pixel glyph and a 1 pixel gap
+const CHARACTER_WIDTH: usize = 6;
+const SCREEN_WIDTH_PIXELS: usize = 128;
+
+/// Used by I2C to set the address of the device. See linux/i2c-dev.h
+const I2C_SLAVE: u16 = 0x0703;
+
+/// Prefixes a sequence of commands
+const COMMAND_CONTROL_BYTE: u8 = 0x00;
+/// Prefixes a sequence of display data
+const DATA_CONTROL_BYTE: u8 = 0x40;
+
+const INITIALISATION_COMMANDS: &[u8] = &[
+    0xAE, // Display off
+    0xD5, 0x80, // Display clock divide ratio
+    0xA8, 0x1F, // Multiplex ratio, i.e. 32 rows
+    0xD3, 0x00, // Display offset
+    0x40, // Display start line
+    0x8D, 0x14, // Enable the charge pump
+    0x20, 0x00, // Horizontal addressing mode
+    0xA1, // Map column 127 to SEG0
+    0xC8, // Scan from COM[N-1] to COM0
+    0xDA, 0x02, // COM pins configuration for 128x32 screens
+    0x81, 0x8F, // Contrast
+    0xD9, 0xF1, // Pre-charge period
+    0xDB, 0x40, // VCOMH deselect level
+    0xA4, // Display the contents of RAM
+    0xA6, // Not inverted
+    0xAF, // Display on
+];
+
+/// Select the whole screen as the area written by display data
+const SELECT_ENTIRE_SCREEN_COMMANDS: &[u8] = &[
+    0x21, 0x00, 0x7F, // Columns 0 to 127
+    0x22, 0x00, 0x03, // Pages 0 to 3, i.e. all 32 rows
+];
+
+#[derive(Debug, serde::Deserialize)]
+struct Ssd1306Config {
+    /// The I2C bus device, e.g. "/dev/i2c-1"
+    i2c_bus: String,
+    /// The I2C address of the screen, which is usually 0x3C
+    address: u16,
+}
+
+/// As characters are written one at a time, they are written to a grid, which is sent to the screen when flushed
+pub struct Ssd1306Screen {
+    i2c: std::fs::File,
+    grid: [[char; COLUMNS]; ROWS],
+    cursor: app::CursorPosition,
+    is_dirty: bool,
+}
+
+impl Ssd1306Screen {
+    /// If `config_file` exists, connect to the screen it describes, otherwise return `None`
+    pub fn from_config_file(config_file: &std::path::Path) -> anyhow::Result<Option<Self>> {
+        let config_src = match std::fs::read_to_string(config_file) {
+            Ok(config_src) => config_src,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!(
+                        "Failed to read SSD1306 config file {}",
+                        config_file.display()
+                    )
+                })
+            }
+        };
+
+        let config: Ssd1306Config =
+            toml::from_str(&config_src).context("Failed to parse SSD1306 config file")?;
+        log::info!("SSD1306 {:?}", config);
+
+        Self::new(&config).map(Some)
+    }
+
+    fn new(config: &Ssd1306Config) -> anyhow::Result<Self> {
+        let i2c = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&config.i2c_bus)
+            .with_context(|| format!("Failed to open I2C bus {}", config.i2c_bus))?;
+
+        // Safety: the file descriptor is valid for the lifetime of `i2c`, and I2C_SLAVE takes the address by value
+        if unsafe {
+            libc::ioctl(
+                i2c.as_raw_fd(),
+                I2C_SLAVE as _,
+                libc::c_ulong::from(config.address),
+            )
+        } < 0
+        {
+            return Err(std::io::Error::last_os_error())
+                .with_context(|| format!("Failed to select I2C address {:#X}", config.address));
+        }
+
+        let mut screen = Self {
+            i2c,
+            grid: [[' '; COLUMNS]; ROWS],
+            cursor: app::CursorPosition { row: 0, column: 0 },
+            is_dirty: true,
+        };
+
+        screen
+            .send(COMMAND_CONTROL_BYTE, INITIALISATION_COMMANDS)
+            .context("Failed to initialise SSD1306")?;
+
+        screen.flush_grid().context("Failed to clear SSD1306")?;
+
+        Ok(screen)
+    }
+
+    fn send(&mut self, control_byte: u8, bytes: &[u8]) -> std::io::Result<()> {
+        let mut message = Vec::with_capacity(bytes.len() + 1);
+        message.push(control_byte);
+        message.extend_from_slice(bytes);
+
+        self.i2c.write_all(&message)
+    }
+
+    fn flush_grid(&mut self) -> std::io::Result<()> {
+        self.send(COMMAND_CONTROL_BYTE, SELECT_ENTIRE_SCREEN_COMMANDS)?;
+
+        let mut pixels = Vec::with_capacity(ROWS * SCREEN_WIDTH_PIXELS);
+
+        for row in &self.grid {
+            for &c in row {
+                pixels.extend_from_slice(&font::glyph(c).unwrap_or(font::SPLODGE));
+                pixels.push(0);
+            }
+
+            pixels.resize(
+                pixels.len() + SCREEN_WIDTH_PIXELS - COLUMNS * CHARACTER_WIDTH,
+                0,
+            );
+        }
+
+        self.send(DATA_CONTROL_BYTE, &pixels)?;
+
+        self.is_dirty = false;
+
+        Ok(())
+    }
+}
+
+impl app::CharacterDisplay for Ssd1306Screen {
+    fn clear(&mut self) {
+        self.grid = [[' '; COLUMNS]; ROWS];
+        self.is_dirty = true;
+    }
+
+    fn move_cursor(&mut self, position: app::CursorPosition) {
+        self.cursor = position;
+    }
+
+    fn write_char(&mut self, c: char) {
+        let app::CursorPosition { row, column } = self.cursor;
+
+        if let Some(cell) = self
+            .grid
+            .get_mut(usize::from(row))
+            .and_then(|row| row.get_mut(usize::from(column)))
+        {
+            if *cell != c {
+                *cell = c;
+                self.is_dirty = true;
+            }
+        }
+
+        self.cursor.column += 1;
+    }
+
+    fn can_render(&self, c: char) -> bool {
+        font::has_glyph(c)
+    }
+
+    fn set_contrast(&mut self, level: u8) {
+        if let Err(err) = self.send(COMMAND_CONTROL_BYTE, &[0x81, level]) {
+            log::error!("Failed to set SSD1306 contrast: {}", err);
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.is_dirty {
+            if let Err(err) = self.flush_grid() {
+                log::error!("Failed to update SSD1306: {}", err);
+            }
+        }
+    }
+}
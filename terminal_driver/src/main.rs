@@ -3,8 +3,10 @@
 
 use std::io::Write;
 
+use anyhow::Context;
+use app::GlyphMap;
 use crossterm::{
-    cursor::MoveTo,
+    cursor::{CursorShape, DisableBlinking, EnableBlinking, Hide, MoveTo, SetCursorShape, Show},
     terminal::{Clear, ClearType},
     ExecutableCommand,
 };
@@ -44,19 +46,134 @@ impl app::CharacterDisplay for TerminalDisplay {
     }
 
     fn write_char(&mut self, c: char) {
-        let c = match c {
-            '\u{E000}' => '▌',
-            '\u{E001}' => '▏',
-            '\u{E002}' => '|',
-            '\u{E003}' => '▕',
-            '\u{E004}' => '▐',
-            _ => c,
-        };
+        let c = app::DefaultGlyphMap.map_char(c);
         write!(self.stdout, "{}", c).unwrap();
         self.stdout.flush().unwrap();
     }
+
+    fn write_str(&mut self, s: &str) {
+        let s = s
+            .chars()
+            .map(|c| app::DefaultGlyphMap.map_char(c))
+            .collect::<String>();
+        write!(self.stdout, "{}", s).unwrap();
+        self.stdout.flush().unwrap();
+    }
+
+    fn set_cursor_style(&mut self, style: app::CursorStyle) {
+        match style {
+            app::CursorStyle::Off => self.stdout.execute(Hide),
+            app::CursorStyle::Underline => self
+                .stdout
+                .execute(SetCursorShape(CursorShape::UnderScore))
+                .and_then(|stdout| stdout.execute(DisableBlinking))
+                .and_then(|stdout| stdout.execute(Show)),
+            app::CursorStyle::Blinking => self
+                .stdout
+                .execute(SetCursorShape(CursorShape::UnderScore))
+                .and_then(|stdout| stdout.execute(EnableBlinking))
+                .and_then(|stdout| stdout.execute(Show)),
+        }
+        .unwrap();
+    }
+}
+
+/// The screen driver's own keys, which may be in the same configuration file, but which the terminal driver has no use for
+const SCREEN_DRIVER_KEYS: &[&str] = &[
+    "thermal_zone",
+    "secondary_screen",
+    "wiring_pins_file",
+    "character_map_file",
+    "character_patterns_file",
+    "ssd1306_file",
+];
+
+/// The configuration file shared with the screen driver
+#[derive(serde::Deserialize)]
+struct ConfigFile {
+    #[serde(flatten)]
+    app: app::Config,
+    #[serde(flatten)]
+    unknown_keys: app::UnknownKeys,
+}
+
+/// Parse the application's configuration, ignoring the screen driver's keys and rejecting keys which neither driver knows
+fn parse_config(config_src: &str) -> anyhow::Result<app::Config> {
+    let mut table: toml::value::Table = toml::from_str(config_src)?;
+
+    for key in SCREEN_DRIVER_KEYS {
+        table.remove(*key);
+    }
+
+    let config_file: ConfigFile = toml::Value::Table(table).try_into()?;
+
+    config_file.unknown_keys.check()?;
+
+    Ok(config_file.app)
+}
+
+/// The configuration in the file passed with `--config`, or named by the `SCREEN_CONFIG_FILE` environment variable,
+/// or the default configuration if neither is given, with the overrides from the command line.
+/// The volume and screen are always saved in the temporary directory, so that they don't disturb an installed screen driver
+fn load_config(args: &app::Args) -> anyhow::Result<app::Config> {
+    let config_file = args
+        .config_file
+        .clone()
+        .or_else(|| std::env::var_os("SCREEN_CONFIG_FILE").map(Into::into));
+
+    let mut config = match config_file {
+        Some(config_file) => {
+            let config_src = std::fs::read_to_string(&config_file)
+                .with_context(|| format!("Failed to read config file {}", config_file.display()))?;
+
+            parse_config(&config_src)
+                .with_context(|| format!("Failed to parse config file {}", config_file.display()))?
+        }
+        None => app::Config::default(),
+    };
+
+    args.apply(&mut config);
+
+    Ok(app::Config {
+        volume_file: Some(std::env::temp_dir().join("rradio_terminal_driver_volume")),
+        screen_file: Some(std::env::temp_dir().join("rradio_terminal_driver_screen")),
+        ..config
+    })
 }
 
 fn main() {
-    app::run("MOCK IP", MockTemperatureSource(0), TerminalDisplay::new())
+    let args = app::Args::from_env().unwrap_or_else(|err| {
+        eprintln!("{:#}", err);
+        std::process::exit(2)
+    });
+
+    // The terminal has no backlight or bespoke characters to test
+    if args.self_test {
+        eprintln!("The self-test is only available on the screen driver");
+        std::process::exit(2)
+    }
+
+    let config = load_config(&args).unwrap_or_else(|err| {
+        eprintln!("{:#}", err);
+        std::process::exit(2)
+    });
+
+    if args.demo {
+        app::run_demo(
+            config,
+            "MOCK IP",
+            MockTemperatureSource(0),
+            app::LocalClock,
+            TerminalDisplay::new(),
+            std::future::pending(),
+        )
+    } else {
+        app::run(
+            config,
+            "MOCK IP",
+            MockTemperatureSource(0),
+            app::LocalClock,
+            TerminalDisplay::new(),
+        )
+    }
 }